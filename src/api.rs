@@ -0,0 +1,505 @@
+use axum::extract::{Path, Query, Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use tracing::{error, info};
+
+use crate::health::SiteHealth;
+use crate::models::{BoxStatus, ListingStatus, PapersStatus, Site, WatchId};
+use crate::server::ServerState;
+use crate::storage::{ListingFilter, ListingSort, PriceRecord, StoredListing};
+
+/// Listings returned by a search unless `limit` says otherwise
+const DEFAULT_LIMIT: u32 = 50;
+pub const MAX_LIMIT: u32 = 500;
+
+pub fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/api/listings", get(search_listings))
+        .route("/api/listings/:site/:id", get(get_listing))
+        .route("/api/sites", get(list_sites))
+        .route("/api/sites/:site/scrape", post(trigger_scrape))
+        .route("/api/sites/:site/seen/:id", put(mark_seen).delete(unmark_seen))
+        .route_layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state)
+}
+
+/// Error response with a JSON body of `{"error": "..."}`
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        error!("API request failed: {:#}", e);
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal error, see the monitor's log")
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+/// Hide the API while it is disabled and check the bearer token if one is set
+async fn authorize(State(state): State<ServerState>, request: Request, next: Next) -> Response {
+    let api = state.config.borrow().api.clone();
+    if !api.enabled {
+        return ApiError::new(StatusCode::NOT_FOUND, "The API is disabled").into_response();
+    }
+
+    if let Some(token) = &api.token {
+        let bearer = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if !bearer.is_some_and(|bearer| token_matches(bearer, token.expose())) {
+            return ApiError::new(StatusCode::UNAUTHORIZED, "Missing or invalid bearer token").into_response();
+        }
+    }
+
+    next.run(request).await
+}
+
+/// Compare MACs of both values in constant time, so that response times
+/// reveal nothing about how much of a guess was right
+fn token_matches(presented: &str, token: &str) -> bool {
+    let mac = |value: &str| {
+        let mut mac = Hmac::<Sha256>::new_from_slice(token.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(value.as_bytes());
+        mac
+    };
+
+    mac(presented).verify(&mac(token).finalize().into_bytes()).is_ok()
+}
+
+fn parse_site(key: &str) -> Result<Site, ApiError> {
+    Site::from_key(key).ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("Unknown site `{}`", key)))
+}
+
+/// Query string selecting listings, shared by the API and the dashboard.
+/// Empty values, as sent by HTML forms, count as unset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListingParams {
+    pub site: Option<String>,
+    pub status: Option<String>,
+    pub brand: Option<String>,
+    pub q: Option<String>,
+    /// Price bounds in whole euros
+    pub min_price: Option<String>,
+    pub max_price: Option<String>,
+    pub min_year: Option<String>,
+    pub max_year: Option<String>,
+    #[serde(rename = "box")]
+    pub box_status: Option<String>,
+    pub papers: Option<String>,
+    pub sort: Option<String>,
+    /// `asc` or `desc`; newest first by default, otherwise ascending
+    pub order: Option<String>,
+    pub limit: Option<String>,
+    pub offset: Option<String>,
+}
+
+impl ListingParams {
+    /// The storage filter these parameters describe, or why they are invalid
+    pub fn to_filter(&self, default_limit: u32) -> Result<ListingFilter, String> {
+        fn value(param: &Option<String>) -> Option<&str> {
+            param.as_deref().map(str::trim).filter(|value| !value.is_empty())
+        }
+        fn number<T: std::str::FromStr>(param: &Option<String>, name: &str) -> Result<Option<T>, String> {
+            value(param)
+                .map(|value| value.parse().map_err(|_| format!("Invalid {} `{}`", name, value)))
+                .transpose()
+        }
+        fn cents(param: &Option<String>, name: &str) -> Result<Option<i64>, String> {
+            Ok(number::<f64>(param, name)?.map(|euros| (euros * 100.0).round() as i64))
+        }
+        fn presence<T>(param: &Option<String>, name: &str, parse: fn(&str) -> T) -> Result<Option<T>, String> {
+            value(param)
+                .map(|key| match key {
+                    "yes" | "no" | "unknown" => Ok(parse(key)),
+                    _ => Err(format!("Unknown {} `{}`, expected yes, no or unknown", name, key)),
+                })
+                .transpose()
+        }
+
+        let sort = value(&self.sort)
+            .map(|key| ListingSort::from_key(key).ok_or_else(|| format!("Unknown sort `{}`", key)))
+            .transpose()?
+            .unwrap_or_default();
+        let ascending = match value(&self.order) {
+            None => sort.ascending_by_default(),
+            Some("asc") => true,
+            Some("desc") => false,
+            Some(order) => return Err(format!("Unknown order `{}`, expected asc or desc", order)),
+        };
+
+        Ok(ListingFilter {
+            site: value(&self.site)
+                .map(|key| Site::from_key(key).ok_or_else(|| format!("Unknown site `{}`", key)))
+                .transpose()?,
+            status: value(&self.status)
+                .map(|key| {
+                    ListingStatus::from_key(key)
+                        .ok_or_else(|| format!("Unknown status `{}`, expected active, sold or removed", key))
+                })
+                .transpose()?,
+            brand: value(&self.brand).map(str::to_string),
+            text: value(&self.q).map(str::to_string),
+            min_price_eur: cents(&self.min_price, "min_price")?,
+            max_price_eur: cents(&self.max_price, "max_price")?,
+            min_year: number(&self.min_year, "min_year")?,
+            max_year: number(&self.max_year, "max_year")?,
+            box_status: presence(&self.box_status, "box", BoxStatus::from_key)?,
+            papers_status: presence(&self.papers, "papers", PapersStatus::from_key)?,
+            sort,
+            ascending,
+            limit: number(&self.limit, "limit")?.unwrap_or(default_limit).min(MAX_LIMIT),
+            offset: number(&self.offset, "offset")?.unwrap_or(0),
+        })
+    }
+}
+
+async fn search_listings(
+    State(state): State<ServerState>,
+    Query(params): Query<ListingParams>,
+) -> Result<Json<Vec<StoredListing>>, ApiError> {
+    let filter = params
+        .to_filter(DEFAULT_LIMIT)
+        .map_err(|message| ApiError::new(StatusCode::BAD_REQUEST, message))?;
+
+    Ok(Json(state.storage.search_listings(&filter).await?))
+}
+
+/// A listing together with every price it was offered at
+#[derive(Serialize)]
+struct ListingDetails {
+    #[serde(flatten)]
+    listing: StoredListing,
+    price_history: Vec<PriceRecord>,
+}
+
+async fn get_listing(
+    State(state): State<ServerState>,
+    Path((site, id)): Path<(String, String)>,
+) -> Result<Json<ListingDetails>, ApiError> {
+    let site = parse_site(&site)?;
+    let listing_id = WatchId(id);
+
+    let listing = state
+        .storage
+        .load_listing(&site, &listing_id)
+        .await?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("No listing `{}` on {}", listing_id.0, site.key())))?;
+    let price_history = state.storage.price_history(&site, &listing_id).await?;
+
+    Ok(Json(ListingDetails { listing, price_history }))
+}
+
+/// A configured site and how its scraper is doing
+#[derive(Serialize)]
+struct SiteSummary {
+    site: &'static str,
+    name: String,
+    url: String,
+    enabled: bool,
+    interval_seconds: u64,
+    /// `None` until the site has been checked
+    health: Option<SiteHealth>,
+}
+
+async fn list_sites(State(state): State<ServerState>) -> Result<Json<Vec<SiteSummary>>, ApiError> {
+    let config = state.config.borrow().clone();
+
+    let mut sites = Vec::new();
+    for site in Site::all() {
+        let Some(site_config) = config.sites.get(site.key()) else {
+            continue;
+        };
+        sites.push(SiteSummary {
+            site: site.key(),
+            name: site_config.name.clone(),
+            url: site_config.url.clone(),
+            enabled: site_config.enabled,
+            interval_seconds: config.poll_interval(site_config).as_secs(),
+            health: state.storage.load_site_health(site).await?,
+        });
+    }
+
+    Ok(Json(sites))
+}
+
+/// Check a site right away; answers before the check has run
+async fn trigger_scrape(
+    State(state): State<ServerState>,
+    Path(site): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let site = parse_site(&site)?;
+
+    match state.config.borrow().sites.get(site.key()) {
+        None => {
+            return Err(ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("Site `{}` is not configured", site.key()),
+            ))
+        }
+        Some(site_config) if !site_config.enabled => {
+            return Err(ApiError::new(
+                StatusCode::CONFLICT,
+                format!("Site `{}` is disabled", site.key()),
+            ))
+        }
+        Some(_) => {}
+    }
+
+    state.triggers.trigger(&site);
+    info!("Check of {} requested through the API", site.key());
+
+    Ok((StatusCode::ACCEPTED, Json(json!({ "site": site.key(), "queued": true }))))
+}
+
+async fn mark_seen(
+    State(state): State<ServerState>,
+    Path((site, id)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let site = parse_site(&site)?;
+    state.storage.mark_seen(&site, &WatchId(id)).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Forget a listing id so that the listing is announced again by the next
+/// check. Listings first seen under their legacy composite id are adopted
+/// silently while that id is known, so it is forgotten as well.
+async fn unmark_seen(
+    State(state): State<ServerState>,
+    Path((site, id)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let site = parse_site(&site)?;
+    let listing_id = WatchId(id);
+
+    let mut forgotten = state.storage.unmark_seen(&site, &listing_id).await?;
+    if let Some(stored) = state.storage.load_listing(&site, &listing_id).await? {
+        forgotten |= state
+            .storage
+            .unmark_seen(&site, &stored.listing.generate_composite_id())
+            .await?;
+    }
+
+    if !forgotten {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("`{}` was not seen on {}", listing_id.0, site.key()),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::models::{Currency, Money, WatchListing};
+    use crate::monitor::ScrapeTriggers;
+    use crate::storage::{PriceObservation, SqliteStorage, Storage};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::watch;
+
+    async fn spawn_api(api: serde_json::Value) -> (String, ServerState) {
+        let config: Config = serde_json::from_value(json!({
+            "sites": {
+                "watch_out": {
+                    "url": "https://www.watch-out.shop/uhren",
+                    "webhook": "https://discord.com/api/webhooks/0/test",
+                    "name": "Watch Out",
+                    "color": 0,
+                    "base_url": "https://www.watch-out.shop",
+                },
+                "grimmeissen": {
+                    "url": "https://www.grimmeissen.de/de/uhren",
+                    "webhook": "https://discord.com/api/webhooks/0/test",
+                    "name": "Grimmeissen",
+                    "color": 0,
+                    "base_url": "https://www.grimmeissen.de",
+                    "enabled": false,
+                }
+            },
+            "listen": "127.0.0.1:0",
+            "api": api,
+        }))
+        .unwrap();
+
+        let storage = SqliteStorage::overlay(std::path::Path::new("/nonexistent/watch-monitor-api-test.db"))
+            .await
+            .unwrap();
+        storage.migrate().await.unwrap();
+
+        let (_, config) = watch::channel(Arc::new(config));
+        let state = ServerState {
+            config,
+            storage: Arc::new(storage),
+            triggers: ScrapeTriggers::default(),
+        };
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let app = router(state.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        (base, state)
+    }
+
+    fn listing(title: &str, brand: &str, euros: i64) -> WatchListing {
+        WatchListing {
+            title: title.to_string(),
+            brand: brand.to_string(),
+            price: Some(Money::new(euros * 100, Currency::Eur)),
+            price_eur: Some(Money::new(euros * 100, Currency::Eur)),
+            watch_url: format!("https://www.watch-out.shop/{}", title.to_lowercase().replace(' ', "-")),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn searches_listings_and_returns_price_history() {
+        let (base, state) = spawn_api(json!({ "enabled": true })).await;
+        let site = Site::WatchOut;
+
+        let submariner = listing("Rolex Submariner", "Rolex", 9500);
+        let speedmaster = listing("Omega Speedmaster", "Omega", 5200);
+        for item in [&submariner, &speedmaster] {
+            let id = item.generate_listing_id();
+            state.storage.touch_listing(&site, &id, item).await.unwrap();
+            state.storage.record_price(&site, &id, &PriceObservation::from_listing(item).unwrap()).await.unwrap();
+        }
+        let cheaper = listing("Rolex Submariner", "Rolex", 9100);
+        let id = submariner.generate_listing_id();
+        state.storage.record_price(&site, &id, &PriceObservation::from_listing(&cheaper).unwrap()).await.unwrap();
+
+        let client = reqwest::Client::new();
+        let found: serde_json::Value = client
+            .get(format!("{}/api/listings?brand=rolex&max_price=9600", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(found.as_array().unwrap().len(), 1);
+        assert_eq!(found[0]["title"], "Rolex Submariner");
+        assert_eq!(found[0]["status"], "active");
+
+        let all: serde_json::Value = client
+            .get(format!("{}/api/listings?q=ster&site=watch_out", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(all[0]["title"], "Omega Speedmaster");
+
+        let by_price: serde_json::Value = client
+            .get(format!("{}/api/listings?sort=price&order=desc", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(by_price[0]["title"], "Rolex Submariner");
+        assert_eq!(by_price[1]["title"], "Omega Speedmaster");
+
+        let details: serde_json::Value = client
+            .get(format!("{}/api/listings/watch_out/{}", base, id.0))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let history = details["price_history"].as_array().unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1]["price"]["amount_minor"], 910000);
+
+        let bad_status = client.get(format!("{}/api/listings?status=gone", base)).send().await.unwrap();
+        assert_eq!(bad_status.status().as_u16(), 400);
+        let missing = client.get(format!("{}/api/listings/watch_out/nope", base)).send().await.unwrap();
+        assert_eq!(missing.status().as_u16(), 404);
+    }
+
+    #[tokio::test]
+    async fn requires_the_token_to_change_seen_ids() {
+        let (base, state) = spawn_api(json!({ "enabled": true, "token": "s3cret" })).await;
+        let client = reqwest::Client::new();
+        let url = format!("{}/api/sites/watch_out/seen/abc123", base);
+
+        let anonymous = client.put(&url).send().await.unwrap();
+        assert_eq!(anonymous.status().as_u16(), 401);
+        for wrong in ["s3cre", "s3cret2", "S3CRET"] {
+            let rejected = client.put(&url).bearer_auth(wrong).send().await.unwrap();
+            assert_eq!(rejected.status().as_u16(), 401);
+        }
+
+        let marked = client.put(&url).bearer_auth("s3cret").send().await.unwrap();
+        assert_eq!(marked.status().as_u16(), 204);
+        assert!(state.storage.has_seen(&Site::WatchOut, &WatchId("abc123".to_string())).await.unwrap());
+
+        let unmarked = client.delete(&url).bearer_auth("s3cret").send().await.unwrap();
+        assert_eq!(unmarked.status().as_u16(), 204);
+        assert!(!state.storage.has_seen(&Site::WatchOut, &WatchId("abc123".to_string())).await.unwrap());
+
+        let again = client.delete(&url).bearer_auth("s3cret").send().await.unwrap();
+        assert_eq!(again.status().as_u16(), 404);
+    }
+
+    #[tokio::test]
+    async fn queues_checks_of_enabled_sites() {
+        let (base, state) = spawn_api(json!({ "enabled": true })).await;
+        let client = reqwest::Client::new();
+
+        let queued = client.post(format!("{}/api/sites/watch_out/scrape", base)).send().await.unwrap();
+        assert_eq!(queued.status().as_u16(), 202);
+        let check_now = state.triggers.get(&Site::WatchOut);
+        tokio::time::timeout(Duration::from_secs(1), check_now.notified()).await.unwrap();
+
+        let disabled = client.post(format!("{}/api/sites/grimmeissen/scrape", base)).send().await.unwrap();
+        assert_eq!(disabled.status().as_u16(), 409);
+        let unconfigured = client.post(format!("{}/api/sites/rueschenbeck/scrape", base)).send().await.unwrap();
+        assert_eq!(unconfigured.status().as_u16(), 404);
+
+        let sites: serde_json::Value = client.get(format!("{}/api/sites", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(sites[0]["site"], "grimmeissen");
+        assert_eq!(sites[1]["enabled"], true);
+        assert_eq!(sites[1]["health"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn hides_the_api_while_disabled() {
+        let (base, _) = spawn_api(json!({ "enabled": false })).await;
+
+        let response = reqwest::get(format!("{}/api/sites", base)).await.unwrap();
+        assert_eq!(response.status().as_u16(), 404);
+    }
+}
@@ -0,0 +1,150 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+use crate::notify::Delivery;
+use crate::utils::http::HttpMode;
+
+#[derive(Debug, Parser)]
+#[command(name = "watch-monitor", version, about = "Monitor watch dealers for new listings")]
+pub struct Cli {
+    /// Configuration file [default: $WATCH_MONITOR_CONFIG or watch-monitor.toml]
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// SQLite database file
+    #[arg(long, global = true, value_name = "PATH", env = "WATCH_MONITOR_DB", default_value = "watch_monitor.db")]
+    pub db: PathBuf,
+
+    /// Render notifications to stdout instead of sending them, and keep all
+    /// database writes in a throwaway in-memory copy
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Write dry-run notifications as JSON files into this directory instead
+    /// of stdout (implies --dry-run)
+    #[arg(long, global = true, value_name = "DIR")]
+    pub dry_run_dir: Option<PathBuf>,
+
+    /// Save every HTTP response into this directory while fetching live
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Serve HTTP responses only from a directory written by --record,
+    /// without any network access
+    #[arg(long, global = true, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+
+    /// Log output format
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    pub fn delivery(&self) -> Delivery {
+        match &self.dry_run_dir {
+            Some(dir) => Delivery::Directory(dir.clone()),
+            None if self.dry_run => Delivery::Stdout,
+            None => Delivery::Webhook,
+        }
+    }
+
+    pub fn http_mode(&self) -> HttpMode {
+        match (&self.record, &self.replay) {
+            (Some(dir), _) => HttpMode::Record(dir.clone()),
+            (None, Some(dir)) => HttpMode::Replay(dir.clone()),
+            (None, None) => HttpMode::Live,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Monitor all enabled sites until stopped (default)
+    Run,
+    /// Check every enabled site once, sending notifications, then exit
+    Once,
+    /// Print a site's current listings as JSON without notifying or marking them seen
+    Scrape {
+        /// Site key, e.g. `grimmeissen`
+        site: String,
+    },
+    /// Mark every listing currently online as seen without sending notifications
+    Seed,
+    /// Import seen watch ids from a JSON file of `{"<site>": ["<id>", ...]}`
+    ImportJson {
+        path: PathBuf,
+    },
+    /// Inspect the database
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DbCommand {
+    /// Show listing, price history and schema counts
+    Stats,
+    /// Show how many listings had each detail resolved in recent scrapes
+    Completeness {
+        /// Only this site, e.g. `grimmeissen`
+        #[arg(long)]
+        site: Option<String>,
+        /// Number of scrapes to show
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(["watch-monitor"].iter().chain(args)).unwrap()
+    }
+
+    #[test]
+    fn parses_each_command() {
+        assert!(matches!(parse(&["once"]).command, Some(Command::Once)));
+        assert!(matches!(parse(&["seed"]).command, Some(Command::Seed)));
+        assert!(matches!(parse(&["scrape", "grimmeissen"]).command, Some(Command::Scrape { site }) if site == "grimmeissen"));
+        assert!(matches!(
+            parse(&["import-json", "seen.json"]).command,
+            Some(Command::ImportJson { path }) if path == Path::new("seen.json")
+        ));
+        assert!(matches!(parse(&["db", "stats"]).command, Some(Command::Db { command: DbCommand::Stats })));
+        assert!(parse(&[]).command.is_none());
+
+        assert!(Cli::try_parse_from(["watch-monitor", "scrape"]).is_err());
+        assert!(Cli::try_parse_from(["watch-monitor", "db"]).is_err());
+    }
+
+    #[test]
+    fn accepts_global_options_after_the_command() {
+        let cli = parse(&["once", "--config", "monitor.yaml", "--db", "test.db", "--dry-run"]);
+
+        assert_eq!(cli.config.as_deref(), Some(Path::new("monitor.yaml")));
+        assert_eq!(cli.db, Path::new("test.db"));
+        assert!(matches!(cli.delivery(), Delivery::Stdout));
+        assert!(matches!(parse(&["seed", "--dry-run-dir", "out"]).delivery(), Delivery::Directory(dir) if dir == Path::new("out")));
+        assert!(matches!(parse(&["seed"]).delivery(), Delivery::Webhook));
+    }
+
+    #[test]
+    fn records_or_replays_but_not_both() {
+        assert!(matches!(parse(&["scrape", "grimmeissen", "--record", "fixtures"]).http_mode(), HttpMode::Record(_)));
+        assert!(matches!(parse(&["scrape", "grimmeissen", "--replay", "fixtures"]).http_mode(), HttpMode::Replay(_)));
+        assert!(Cli::try_parse_from(["watch-monitor", "once", "--record", "a", "--replay", "b"]).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+use anyhow::{Context, Result};
+use futures::future::join_all;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::config::Config;
+use crate::models::{Site, DETAIL_FIELDS};
+use crate::monitor;
+use crate::notify::Dispatcher;
+use crate::scrapers::{build_scraper, build_scrapers};
+use crate::storage::Storage;
+use crate::utils::exchange_rate::ExchangeRateClient;
+use crate::utils::http::{create_client, HttpMode};
+
+/// Check every enabled site once, concurrently, then deliver the queued
+/// Discord messages. With `notify` unset new listings are only marked as seen.
+pub async fn check_all_once(
+    config: Arc<Config>,
+    storage: Arc<dyn Storage>,
+    dispatcher: &Dispatcher,
+    http_mode: &HttpMode,
+    notify: bool,
+) -> Result<()> {
+    dispatcher.configure(&config);
+    let client = create_client(&config.user_agent, http_mode.clone())?;
+    let exchange_rates = exchange_rate_client(&config, storage.clone());
+    let scrapers = build_scrapers(config.clone());
+    info!("Checking {} sites once", scrapers.len());
+
+    let checks = scrapers.iter().map(|scraper| {
+        monitor::check_site(scraper.as_ref(), &config, &client, storage.as_ref(), &exchange_rates, dispatcher, notify)
+    });
+
+    for (scraper, result) in scrapers.iter().zip(join_all(checks).await) {
+        if let Err(e) = result {
+            error!("Error checking {}: {:#}", scraper.site_config().name, e);
+        }
+    }
+
+    if notify {
+        dispatcher.drain_outbox().await?;
+    }
+
+    Ok(())
+}
+
+/// Print the current listings of one site as JSON on stdout
+pub async fn scrape(
+    config: Arc<Config>,
+    storage: Arc<dyn Storage>,
+    http_mode: &HttpMode,
+    site_key: &str,
+) -> Result<()> {
+    let site = parse_site(site_key)?;
+    if !config.sites.contains_key(site.key()) {
+        anyhow::bail!("Site `{}` is not configured under [sites]", site_key);
+    }
+
+    let client = create_client(&config.user_agent, http_mode.clone())?;
+    let exchange_rates = exchange_rate_client(&config, storage);
+    let scraper = build_scraper(&site, config.clone());
+
+    let mut listings = scraper.scrape(&client).await?;
+    exchange_rates.convert_listing_prices(&client, &mut listings).await;
+    info!("Found {} watch items on {}", listings.len(), scraper.site_config().name);
+
+    println!("{}", serde_json::to_string_pretty(&listings)?);
+    Ok(())
+}
+
+pub async fn import_json(storage: Arc<dyn Storage>, path: &Path) -> Result<()> {
+    storage.import_from_json(path).await
+}
+
+pub async fn db_stats(storage: Arc<dyn Storage>) -> Result<()> {
+    let stats = storage.stats().await?;
+
+    println!("Schema version: {}", stats.schema_version);
+    println!();
+    println!(
+        "{:<20} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "SITE", "SEEN", "ACTIVE", "SOLD", "REMOVED", "PRICES"
+    );
+    for (site, counts) in &stats.sites {
+        println!(
+            "{:<20} {:>8} {:>8} {:>8} {:>8} {:>8}",
+            site, counts.seen, counts.active, counts.sold, counts.removed, counts.price_observations
+        );
+    }
+
+    println!();
+    println!("{:<20} {:<8} {:>8} {:>8} {:>8} {:>8}", "SITE", "HEALTH", "ITEMS", "AVERAGE", "UNKNOWN", "FAILURES");
+    for site in Site::all() {
+        let Some(health) = storage.load_site_health(site).await? else {
+            continue;
+        };
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<20} {:<8} {:>8} {:>8} {:>8} {:>8}",
+            site.key(),
+            health.status,
+            or_dash(health.last_items.map(|items| items.to_string())),
+            or_dash(health.average_items.map(|average| format!("{:.1}", average))),
+            or_dash(health.unknown_share.map(|share| format!("{:.0}%", share * 100.0))),
+            health.consecutive_failures
+        );
+        for problem in &health.problems {
+            println!("  {}", problem);
+        }
+    }
+
+    match storage.load_exchange_rates().await? {
+        Some(rates) => println!(
+            "\nExchange rates: {} currencies per {}, fetched {}",
+            rates.rates.len(),
+            rates.base,
+            rates.fetched_at.format("%Y-%m-%d %H:%M UTC")
+        ),
+        None => println!("\nExchange rates: none stored"),
+    }
+
+    Ok(())
+}
+
+pub async fn db_completeness(storage: Arc<dyn Storage>, site_key: Option<&str>, limit: u32) -> Result<()> {
+    let site = site_key.map(parse_site).transpose()?;
+    let records = storage.completeness_history(site.as_ref(), limit).await?;
+
+    print!("{:<19}  {:<18} {:>8}", "CHECKED (UTC)", "SITE", "LISTINGS");
+    for name in DETAIL_FIELDS {
+        print!(" {:>9}", name.to_uppercase());
+    }
+    println!();
+
+    for record in &records {
+        print!(
+            "{:<19}  {:<18} {:>8}",
+            record.checked_at.format("%Y-%m-%d %H:%M:%S"),
+            record.site,
+            record.completeness.listings
+        );
+        for (_, percent) in &record.completeness.fields {
+            print!(" {:>8.0}%", percent);
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+fn parse_site(site_key: &str) -> Result<Site> {
+    Site::from_key(site_key).with_context(|| {
+        let keys: Vec<_> = Site::all().iter().map(|site| site.key()).collect();
+        format!("Unknown site `{}`, expected one of: {}", site_key, keys.join(", "))
+    })
+}
+
+fn exchange_rate_client(config: &Config, storage: Arc<dyn Storage>) -> ExchangeRateClient {
+    ExchangeRateClient::new(&config.exchange_rate_api_url, config.exchange_rate_base, storage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::Delivery;
+    use crate::storage::SqliteStorage;
+    use serde_json::json;
+    use std::path::PathBuf;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const LISTING: &str = r#"<html><body>
+<div class="new-arrivals-watch">
+  <a href="/Watches/Rolex/Explorer-214270"><h2>Rolex Explorer</h2></a>
+  <span class="watch-price">9.800 €</span>
+</div>
+</body></html>"#;
+
+    /// A temporary database file, removed when dropped
+    struct TempDb(PathBuf);
+
+    impl TempDb {
+        async fn open(name: &str) -> (Self, Arc<dyn Storage>) {
+            let path = std::env::temp_dir().join(format!("watch-monitor-{}-{}.db", name, std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            let storage = SqliteStorage::new(&path).await.unwrap();
+            storage.migrate().await.unwrap();
+            (Self(path), Arc::new(storage))
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// World of Time with one listing on the mock server, announced to
+    /// `/webhook` `announcements` times
+    async fn serve_site(server: &MockServer, announcements: u64) -> Arc<Config> {
+        for (page, html) in [
+            ("/Watches/NewArrivals", LISTING),
+            ("/Watches/Rolex/Explorer-214270", "<html><body><h1>Rolex Explorer 214270</h1></body></html>"),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(page))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(html, "text/html; charset=utf-8"))
+                .mount(server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/webhook"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(announcements)
+            .mount(server)
+            .await;
+
+        Arc::new(
+            serde_json::from_value(json!({
+                "sites": {
+                    "worldoftime": {
+                        "url": format!("{}/Watches/NewArrivals", server.uri()),
+                        "webhook": format!("{}/webhook", server.uri()),
+                        "name": "World of Time",
+                        "color": 0,
+                        "base_url": server.uri(),
+                    }
+                },
+            }))
+            .unwrap(),
+        )
+    }
+
+    async fn seen(storage: &dyn Storage) -> u64 {
+        let stats = storage.stats().await.unwrap();
+        stats.sites.iter().find(|(site, _)| site == "worldoftime").map_or(0, |(_, counts)| counts.seen)
+    }
+
+    #[tokio::test]
+    async fn once_announces_new_listings_a_single_time() {
+        let server = MockServer::start().await;
+        let config = serve_site(&server, 1).await;
+        let (_db, storage) = TempDb::open("once").await;
+        let dispatcher = Dispatcher::new(Delivery::Webhook, storage.clone()).unwrap();
+
+        check_all_once(config.clone(), storage.clone(), &dispatcher, &HttpMode::Live, true).await.unwrap();
+        assert_eq!(seen(storage.as_ref()).await, 1);
+        assert_eq!(storage.outbox_len().await.unwrap(), 0);
+
+        // Known by now, so the second run stays quiet
+        check_all_once(config, storage.clone(), &dispatcher, &HttpMode::Live, true).await.unwrap();
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn dry_run_renders_notifications_and_leaves_the_database_untouched() {
+        let server = MockServer::start().await;
+        let config = serve_site(&server, 0).await;
+        let (db, storage) = TempDb::open("dry-run").await;
+        storage.close().await.unwrap();
+        drop(storage);
+        let on_disk = std::fs::read(&db.0).unwrap();
+        let out = db.0.with_extension("payloads");
+        std::fs::create_dir_all(&out).unwrap();
+
+        let overlay: Arc<dyn Storage> = Arc::new(SqliteStorage::overlay(&db.0).await.unwrap());
+        overlay.migrate().await.unwrap();
+        let dispatcher = Dispatcher::new(Delivery::Directory(out.clone()), overlay.clone()).unwrap();
+        check_all_once(config, overlay.clone(), &dispatcher, &HttpMode::Live, true).await.unwrap();
+
+        // The run itself saw the listing, but only in memory
+        assert_eq!(seen(overlay.as_ref()).await, 1);
+        assert_eq!(std::fs::read(&db.0).unwrap(), on_disk);
+        let reopened = SqliteStorage::new(&db.0).await.unwrap();
+        assert_eq!(seen(&reopened).await, 0);
+
+        let payloads = std::fs::read_dir(&out).unwrap().count();
+        std::fs::remove_dir_all(&out).unwrap();
+        assert_eq!(payloads, 1);
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn seed_marks_listings_seen_without_notifying() {
+        let server = MockServer::start().await;
+        let config = serve_site(&server, 0).await;
+        let (_db, storage) = TempDb::open("seed").await;
+        let dispatcher = Dispatcher::new(Delivery::Webhook, storage.clone()).unwrap();
+
+        check_all_once(config, storage.clone(), &dispatcher, &HttpMode::Live, false).await.unwrap();
+
+        assert_eq!(seen(storage.as_ref()).await, 1);
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn scrape_neither_notifies_nor_marks_listings_seen() {
+        let server = MockServer::start().await;
+        let config = serve_site(&server, 0).await;
+        let (_db, storage) = TempDb::open("scrape").await;
+
+        scrape(config.clone(), storage.clone(), &HttpMode::Live, "worldoftime").await.unwrap();
+
+        assert_eq!(seen(storage.as_ref()).await, 0);
+        let unknown = scrape(config.clone(), storage.clone(), &HttpMode::Live, "chrono24").await.unwrap_err();
+        assert!(unknown.to_string().starts_with("Unknown site `chrono24`"), "{}", unknown);
+        let unconfigured = scrape(config, storage, &HttpMode::Live, "grimmeissen").await.unwrap_err();
+        assert!(unconfigured.to_string().contains("not configured"), "{}", unconfigured);
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn import_json_marks_the_listed_ids_seen() {
+        let (db, storage) = TempDb::open("import").await;
+        let json_path = db.0.with_extension("json");
+        std::fs::write(&json_path, r#"{"worldoftime": ["a1", "b2"], "elsewhere": ["c3"]}"#).unwrap();
+
+        import_json(storage.clone(), &json_path).await.unwrap();
+        std::fs::remove_file(&json_path).unwrap();
+
+        assert_eq!(seen(storage.as_ref()).await, 2);
+        assert!(storage.has_seen(&Site::WorldOfTime, &crate::models::WatchId("a1".to_string())).await.unwrap());
+        assert!(import_json(storage, &json_path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn db_stats_reads_a_populated_database() {
+        let server = MockServer::start().await;
+        let config = serve_site(&server, 0).await;
+        let (_db, storage) = TempDb::open("stats").await;
+        let dispatcher = Dispatcher::new(Delivery::Webhook, storage.clone()).unwrap();
+        check_all_once(config, storage.clone(), &dispatcher, &HttpMode::Live, false).await.unwrap();
+
+        db_stats(storage).await.unwrap();
+    }
+}
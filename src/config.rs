@@ -1,100 +1,972 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use url::Url;
+
+use crate::models::{Currency, Site};
+use crate::notify::{self, NotifierConfig};
+use crate::rules::WatchRule;
+use crate::utils::secret::Secret;
+
+/// Environment variable pointing at the configuration file
+pub const CONFIG_PATH_ENV: &str = "WATCH_MONITOR_CONFIG";
+
+/// Configuration file used when neither `--config` nor `WATCH_MONITOR_CONFIG` is given
+pub const DEFAULT_CONFIG_PATH: &str = "watch-monitor.toml";
+
+/// Prefix for environment overrides, e.g. `WATCH_MONITOR__SITES__WATCH_OUT__WEBHOOK`
+const ENV_PREFIX: &str = "WATCH_MONITOR";
+const ENV_SEPARATOR: &str = "__";
+
+/// Top-level settings only the HTTP listener and shutdown read
+const SERVER_SETTINGS: [&str; 4] = ["listen", "api", "dashboard", "shutdown_timeout_seconds"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub sites: HashMap<String, SiteConfig>,
+    /// Default polling interval for sites without their own `interval_seconds`
+    #[serde(default = "default_check_interval_seconds")]
     pub check_interval_seconds: u64,
+    #[serde(default = "default_user_agent")]
     pub user_agent: String,
+    /// Exchange rate API; `{base}` is replaced by `exchange_rate_base`
+    #[serde(default = "default_exchange_rate_api_url")]
     pub exchange_rate_api_url: String,
+    /// Currency the exchange rate table is requested for
+    #[serde(default = "default_currency")]
+    pub exchange_rate_base: Currency,
+    /// Consecutive successful scrapes a listing may be missing from before
+    /// it counts as removed
+    #[serde(default = "default_removed_after_missed_scrapes")]
+    pub removed_after_missed_scrapes: u32,
+    /// Discord webhook for sold/removed notifications; none are sent if unset
+    #[serde(default)]
+    pub sold_webhook: Option<Secret>,
+    /// Watchlist rules routing matching new listings to their own webhooks
+    #[serde(default)]
+    pub rules: Vec<WatchRule>,
+    /// Discord webhook for scraper health alerts; problems are only logged if unset
+    #[serde(default)]
+    pub admin_webhook: Option<Secret>,
+    /// When a site's scraper counts as broken
+    #[serde(default)]
+    pub health: HealthConfig,
+    /// Address for the HTTP listener serving Prometheus metrics at `/metrics`;
+    /// none is started if unset
+    #[serde(default)]
+    pub listen: Option<SocketAddr>,
+    /// REST API served by the `listen` listener
+    #[serde(default)]
+    pub api: ApiConfig,
+    /// Web dashboard served by the `listen` listener
+    #[serde(default)]
+    pub dashboard: DashboardConfig,
+    /// How long in-flight checks may take to wind down after Ctrl-C or SIGTERM
+    #[serde(default = "default_shutdown_timeout_seconds")]
+    pub shutdown_timeout_seconds: u64,
+}
+
+/// REST API under `/api`, see the README for its endpoints
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApiConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Bearer token every API request must carry; the API is open if unset
+    #[serde(default)]
+    pub token: Option<Secret>,
+}
+
+/// Listings page served at `/`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DashboardConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Thresholds for scraper health alerts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthConfig {
+    /// Consecutive failed scrapes before a site counts as broken
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// A scrape finding fewer listings than this share of the rolling
+    /// average counts as broken
+    #[serde(default = "default_min_item_ratio")]
+    pub min_item_ratio: f64,
+    /// A scrape leaving more than this share of listing details unknown
+    /// counts as broken
+    #[serde(default = "default_max_unknown_share")]
+    pub max_unknown_share: f64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: default_failure_threshold(),
+            min_item_ratio: default_min_item_ratio(),
+            max_unknown_share: default_max_unknown_share(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SiteConfig {
     pub url: String,
-    pub webhook: String,
+    /// Discord webhook URL, inline or as an `env:`/`file:` reference;
+    /// shorthand for a Discord entry in `notifiers`
+    #[serde(default)]
+    pub webhook: Option<Secret>,
+    /// Where new listings and price drops are sent, besides `webhook`
+    #[serde(default)]
+    pub notifiers: Vec<NotifierConfig>,
     pub name: String,
     pub color: u32,
     pub base_url: String,
+    /// Currency the dealer lists prices in, used when a price has no symbol
+    #[serde(default = "default_currency")]
+    pub currency: Currency,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Polling interval for this site, overriding `check_interval_seconds`
+    #[serde(default)]
+    pub interval_seconds: Option<u64>,
+    /// Random delay of up to this many seconds added to every poll
+    #[serde(default)]
+    pub jitter_seconds: u64,
+    /// Only poll the site within this local time window
+    #[serde(default)]
+    pub active_hours: Option<ActiveHours>,
+    /// Mark listings missing from the page as removed. Disable for pages that
+    /// only show the newest arrivals, where older listings drop off unsold.
+    #[serde(default = "default_enabled")]
+    pub track_removals: bool,
+}
+
+/// Daily polling window in local time; `end` before `start` wraps past midnight
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveHours {
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+}
+
+/// Wall-clock time written as `HH:MM` in the configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay(pub NaiveTime);
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        NaiveTime::parse_from_str(value.trim(), "%H:%M")
+            .map(TimeOfDay)
+            .map_err(|_| format!("invalid time `{}`, expected HH:MM", value))
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        time.to_string()
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.format("%H:%M"))
+    }
+}
+
+/// Validation failure that names the offending key (and site, if any)
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("invalid value for `{key}`: {reason}")]
+    InvalidField { key: String, reason: String },
+
+    #[error("invalid value for `sites.{site}.{key}`: {reason}")]
+    InvalidSiteField {
+        site: String,
+        key: String,
+        reason: String,
+    },
+
+    #[error("invalid value for `{key}` in rule `{rule}`: {reason}")]
+    InvalidRuleField {
+        rule: String,
+        key: String,
+        reason: String,
+    },
+
+    #[error("invalid site `sites.{site}`: {reason}")]
+    InvalidSite { site: String, reason: String },
+
+    #[error("unknown site `sites.{site}` (expected one of: {expected})")]
+    UnknownSite { site: String, expected: String },
+}
+
+fn default_check_interval_seconds() -> u64 {
+    60
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_removed_after_missed_scrapes() -> u32 {
+    3
+}
+
+fn default_shutdown_timeout_seconds() -> u64 {
+    30
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
+fn default_min_item_ratio() -> f64 {
+    0.5
+}
+
+fn default_max_unknown_share() -> f64 {
+    0.6
+}
+
+fn default_user_agent() -> String {
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/108.0.0.0 Safari/537.36".to_string()
+}
+
+fn default_exchange_rate_api_url() -> String {
+    "https://api.exchangerate-api.com/v4/latest/{base}".to_string()
+}
+
+fn default_currency() -> Currency {
+    Currency::Eur
 }
 
 impl Config {
-    pub fn load() -> Result<Self> {
-        // For now, hardcode the configuration matching the Python script
-        let mut sites = HashMap::new();
-        
-        sites.insert(
-            "worldoftime".to_string(),
-            SiteConfig {
-                url: "https://www.worldoftime.de/Watches/NewArrivals".to_string(),
-                webhook: "https://discord.com/api/webhooks/1356956538190823534/GMUibI4sDu9I515zDvxyC0cqkFiXC_D4yh89L36WsRIdIzSlTmtFx4LTtxxsodYBSqXB".to_string(),
-                name: "World of Time".to_string(),
-                color: 0x2F4F4F,
-                base_url: "https://www.worldoftime.de".to_string(),
-            },
+    /// Resolve the configuration file path: explicit path first, then
+    /// `WATCH_MONITOR_CONFIG`, then `watch-monitor.toml` in the working directory
+    pub fn resolve_path(explicit: Option<&Path>) -> PathBuf {
+        if let Some(path) = explicit {
+            return path.to_path_buf();
+        }
+
+        match std::env::var(CONFIG_PATH_ENV) {
+            Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+            _ => PathBuf::from(DEFAULT_CONFIG_PATH),
+        }
+    }
+
+    /// Load the configuration file (TOML, YAML or JSON, chosen by extension)
+    /// and apply `WATCH_MONITOR__...` environment overrides on top
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::load_with_env(path, None)
+    }
+
+    /// `load`, taking the environment overrides from `env` instead of the
+    /// process environment if it is set
+    fn load_with_env(path: &Path, env: Option<HashMap<String, String>>) -> Result<Self> {
+        let settings = config::Config::builder()
+            .add_source(config::File::from(path).required(true))
+            .add_source(
+                config::Environment::with_prefix(ENV_PREFIX)
+                    .separator(ENV_SEPARATOR)
+                    .try_parsing(true)
+                    .source(env),
+            )
+            .build()
+            .with_context(|| format!("Failed to read configuration from {}", path.display()))?;
+
+        // Deserialize sites one by one first so type errors name the site
+        if let Ok(sites) = settings.get_table("sites") {
+            for (site_key, value) in sites {
+                value
+                    .try_deserialize::<SiteConfig>()
+                    .map_err(|e| ConfigError::InvalidSite {
+                        site: site_key.clone(),
+                        reason: e.to_string(),
+                    })?;
+            }
+        }
+
+        let config: Config = settings
+            .try_deserialize()
+            .with_context(|| format!("Invalid configuration in {}", path.display()))?;
+
+        config
+            .validate()
+            .with_context(|| format!("Invalid configuration in {}", path.display()))?;
+
+        Ok(config)
+    }
+
+    /// Check values that deserialize fine but cannot work at runtime
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.sites.is_empty() {
+            return Err(ConfigError::InvalidField {
+                key: "sites".to_string(),
+                reason: "at least one site must be configured".to_string(),
+            });
+        }
+
+        if self.check_interval_seconds == 0 {
+            return Err(ConfigError::InvalidField {
+                key: "check_interval_seconds".to_string(),
+                reason: "must be greater than 0".to_string(),
+            });
+        }
+
+        if self.user_agent.trim().is_empty() {
+            return Err(ConfigError::InvalidField {
+                key: "user_agent".to_string(),
+                reason: "must not be empty".to_string(),
+            });
+        }
+
+        if let Err(e) = Url::parse(&self.exchange_rate_api_url) {
+            return Err(ConfigError::InvalidField {
+                key: "exchange_rate_api_url".to_string(),
+                reason: format!("not a valid URL ({})", e),
+            });
+        }
+
+        if self.shutdown_timeout_seconds == 0 {
+            return Err(ConfigError::InvalidField {
+                key: "shutdown_timeout_seconds".to_string(),
+                reason: "must be greater than 0".to_string(),
+            });
+        }
+
+        if self.removed_after_missed_scrapes == 0 {
+            return Err(ConfigError::InvalidField {
+                key: "removed_after_missed_scrapes".to_string(),
+                reason: "must be greater than 0".to_string(),
+            });
+        }
+
+        for (key, webhook) in [
+            ("sold_webhook", &self.sold_webhook),
+            ("admin_webhook", &self.admin_webhook),
+        ] {
+            if let Some(webhook) = webhook {
+                if let Err(reason) = validate_http_url(webhook.expose()) {
+                    return Err(ConfigError::InvalidField {
+                        key: key.to_string(),
+                        reason,
+                    });
+                }
+            }
+        }
+
+        self.health.validate()?;
+
+        for (key, enabled) in [
+            ("api.enabled", self.api.enabled),
+            ("dashboard.enabled", self.dashboard.enabled),
+        ] {
+            if enabled && self.listen.is_none() {
+                return Err(ConfigError::InvalidField {
+                    key: key.to_string(),
+                    reason: "requires `listen` to be set".to_string(),
+                });
+            }
+        }
+
+        // Validate in a stable order so the first reported error is deterministic
+        let mut site_keys: Vec<&String> = self.sites.keys().collect();
+        site_keys.sort();
+
+        for site_key in site_keys {
+            if Site::from_key(site_key).is_none() {
+                return Err(ConfigError::UnknownSite {
+                    site: site_key.clone(),
+                    expected: Site::all()
+                        .iter()
+                        .map(|s| s.key())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                });
+            }
+
+            self.sites[site_key].validate(site_key)?;
+        }
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            rule.validate(index)?;
+
+            if self.rules[..index]
+                .iter()
+                .any(|other| other.name == rule.name)
+            {
+                return Err(ConfigError::InvalidRuleField {
+                    rule: rule.name.clone(),
+                    key: "name".to_string(),
+                    reason: "rule names must be unique".to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Describe every setting that differs from `other`, one line per change.
+    /// Webhook values are never printed.
+    pub fn diff(&self, other: &Config) -> Vec<String> {
+        let old = serde_json::to_value(self).unwrap_or_default();
+        let new = serde_json::to_value(other).unwrap_or_default();
+
+        let mut changes = Vec::new();
+        diff_values("", &old, &new, &mut changes);
+
+        // Secrets serialize as their reference, so compare resolved values too
+        for (key, old, new) in [
+            ("sold_webhook", &self.sold_webhook, &other.sold_webhook),
+            ("admin_webhook", &self.admin_webhook, &other.admin_webhook),
+            ("api.token", &self.api.token, &other.api.token),
+        ] {
+            if old != new && !changes.iter().any(|c| c.starts_with(key)) {
+                changes.push(format!("{} changed", key));
+            }
+        }
+        for (site_key, site) in &self.sites {
+            if let Some(other_site) = other.sites.get(site_key) {
+                let prefix = format!("sites.{}", site_key);
+                diff_targets(
+                    &prefix,
+                    (&site.webhook, &site.notifiers),
+                    (&other_site.webhook, &other_site.notifiers),
+                    &mut changes,
+                );
+            }
+        }
+        for (index, (rule, other_rule)) in self.rules.iter().zip(&other.rules).enumerate() {
+            let prefix = format!("rules[{}]", index);
+            diff_targets(
+                &prefix,
+                (&rule.webhook, &rule.notifiers),
+                (&other_rule.webhook, &other_rule.notifiers),
+                &mut changes,
+            );
+        }
+        changes.sort();
+
+        changes
+    }
+
+    /// Keys of the sites whose checks `other` changes: sites added, removed
+    /// or with changed settings or polling interval, and every site if a
+    /// top-level setting that checks read changed. The listener, API,
+    /// dashboard and shutdown timeout concern no site.
+    pub fn changed_sites(&self, other: &Config) -> Vec<String> {
+        let changes = self.diff(other);
+        let names = |change: &str, key: &str| {
+            change
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.starts_with(['.', '[', ':', ' ']))
+        };
+
+        let every_site = changes.iter().any(|change| {
+            !["sites", "check_interval_seconds"]
+                .iter()
+                .chain(&SERVER_SETTINGS)
+                .any(|key| names(change, key))
+        });
+
+        let mut changed: Vec<String> = self
+            .sites
+            .keys()
+            .chain(other.sites.keys())
+            .filter(|key| {
+                let interval_changed = match (self.sites.get(*key), other.sites.get(*key)) {
+                    (Some(old), Some(new)) => self.poll_interval(old) != other.poll_interval(new),
+                    _ => false,
+                };
+                every_site
+                    || interval_changed
+                    || changes
+                        .iter()
+                        .any(|change| names(change, &format!("sites.{}", key)))
+            })
+            .cloned()
+            .collect();
+        changed.sort();
+        changed.dedup();
+
+        changed
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_seconds)
+    }
+
+    /// Polling interval for a site, falling back to the global default
+    pub fn poll_interval(&self, site: &SiteConfig) -> Duration {
+        Duration::from_secs(site.interval_seconds.unwrap_or(self.check_interval_seconds))
+    }
+}
+
+fn diff_values(
+    path: &str,
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    changes: &mut Vec<String>,
+) {
+    use serde_json::Value;
+
+    if old == new {
+        return;
+    }
+
+    if let (Value::Object(old_map), Value::Object(new_map)) = (old, new) {
+        let mut keys: Vec<&String> = old_map.keys().chain(new_map.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        for key in keys {
+            let child = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            match (old_map.get(key), new_map.get(key)) {
+                (Some(_), None) => changes.push(format!("{} removed", child)),
+                (None, Some(_)) => changes.push(format!("{} added", child)),
+                (Some(old_value), Some(new_value)) => {
+                    diff_values(&child, old_value, new_value, changes)
+                }
+                (None, None) => {}
+            }
+        }
+    } else if let (Value::Array(old_items), Value::Array(new_items)) = (old, new) {
+        for index in 0..old_items.len().max(new_items.len()) {
+            let child = format!("{}[{}]", path, index);
+            match (old_items.get(index), new_items.get(index)) {
+                (Some(_), None) => changes.push(format!("{} removed", child)),
+                (None, Some(_)) => changes.push(format!("{} added", child)),
+                (Some(old_value), Some(new_value)) => {
+                    diff_values(&child, old_value, new_value, changes)
+                }
+                (None, None) => {}
+            }
+        }
+    } else if path.ends_with("webhook") {
+        changes.push(format!("{} changed", path));
+    } else {
+        changes.push(format!("{}: {} -> {}", path, old, new));
+    }
+}
+
+/// Report changed webhook and notifier secrets under `prefix`, which the
+/// serialized comparison misses for inline values
+fn diff_targets(
+    prefix: &str,
+    old: (&Option<Secret>, &Vec<NotifierConfig>),
+    new: (&Option<Secret>, &Vec<NotifierConfig>),
+    changes: &mut Vec<String>,
+) {
+    for (key, changed) in [("webhook", old.0 != new.0), ("notifiers", old.1 != new.1)] {
+        let key = format!("{}.{}", prefix, key);
+        if changed && !changes.iter().any(|c| c.starts_with(&key)) {
+            changes.push(format!("{} changed", key));
+        }
+    }
+}
+
+pub fn validate_http_url(value: &str) -> Result<(), String> {
+    match Url::parse(value) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(()),
+        Ok(url) => Err(format!("unsupported URL scheme `{}`", url.scheme())),
+        Err(e) => Err(format!("not a valid URL ({})", e)),
+    }
+}
+
+impl HealthConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |key: &str, reason: &str| ConfigError::InvalidField {
+            key: format!("health.{}", key),
+            reason: reason.to_string(),
+        };
+
+        if self.failure_threshold == 0 {
+            return Err(invalid("failure_threshold", "must be greater than 0"));
+        }
+
+        for (key, value) in [
+            ("min_item_ratio", self.min_item_ratio),
+            ("max_unknown_share", self.max_unknown_share),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(invalid(key, "must be between 0 and 1"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ActiveHours {
+    pub fn contains(&self, now: NaiveTime) -> bool {
+        let (start, end) = (self.start.0, self.end.0);
+        if start <= end {
+            now >= start && now < end
+        } else {
+            now >= start || now < end
+        }
+    }
+
+    /// Time left until the window opens again (zero if already open)
+    pub fn until_open(&self, now: NaiveTime) -> Duration {
+        if self.contains(now) {
+            return Duration::ZERO;
+        }
+
+        let mut wait = self.start.0 - now;
+        if wait < chrono::Duration::zero() {
+            wait += chrono::Duration::days(1);
+        }
+        wait.to_std().unwrap_or(Duration::ZERO)
+    }
+}
+
+impl fmt::Display for ActiveHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl SiteConfig {
+    /// Every target new listings and price drops of this site go to
+    pub fn targets(&self) -> Vec<NotifierConfig> {
+        notify::targets(self.webhook.as_ref(), &self.notifiers)
+    }
+
+    fn validate(&self, site_key: &str) -> Result<(), ConfigError> {
+        let invalid = |key: &str, reason: String| ConfigError::InvalidSiteField {
+            site: site_key.to_string(),
+            key: key.to_string(),
+            reason,
+        };
+
+        if self.name.trim().is_empty() {
+            return Err(invalid("name", "must not be empty".to_string()));
+        }
+
+        for (key, value) in [("url", self.url.as_str()), ("base_url", &self.base_url)] {
+            validate_http_url(value).map_err(|reason| invalid(key, reason))?;
+        }
+
+        if let Some(webhook) = &self.webhook {
+            validate_http_url(webhook.expose()).map_err(|reason| invalid("webhook", reason))?;
+        }
+
+        if self.webhook.is_none() && self.notifiers.is_empty() {
+            return Err(invalid(
+                "notifiers",
+                "set `webhook` or at least one notifier".to_string(),
+            ));
+        }
+
+        for (index, notifier) in self.notifiers.iter().enumerate() {
+            notifier.validate().map_err(|(key, reason)| {
+                invalid(&format!("notifiers[{}].{}", index, key), reason)
+            })?;
+        }
+
+        if self.color > 0xFFFFFF {
+            return Err(invalid(
+                "color",
+                format!("{:#X} is not a 24-bit RGB color", self.color),
+            ));
+        }
+
+        if self.interval_seconds == Some(0) {
+            return Err(invalid(
+                "interval_seconds",
+                "must be greater than 0".to_string(),
+            ));
+        }
+
+        if let Some(hours) = &self.active_hours {
+            if hours.start == hours.end {
+                return Err(invalid(
+                    "active_hours",
+                    "start and end must differ".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(overrides: serde_json::Value) -> Config {
+        let mut config = json!({
+            "sites": {
+                "watch_out": {
+                    "url": "https://www.watch-out.shop/collections/uhren",
+                    "webhook": "https://discord.com/api/webhooks/1/token",
+                    "name": "Watch Out",
+                    "color": 0x2F3136,
+                    "base_url": "https://www.watch-out.shop",
+                }
+            }
+        });
+        merge(&mut config, overrides);
+        serde_json::from_value(config).unwrap()
+    }
+
+    fn merge(target: &mut serde_json::Value, overrides: serde_json::Value) {
+        match (target, overrides) {
+            (serde_json::Value::Object(target), serde_json::Value::Object(overrides)) => {
+                for (key, value) in overrides {
+                    merge(target.entry(key).or_insert(serde_json::Value::Null), value);
+                }
+            }
+            (target, value) => *target = value,
+        }
+    }
+
+    #[test]
+    fn accepts_a_minimal_configuration() {
+        let config = config(json!({}));
+
+        config.validate().unwrap();
+        assert_eq!(
+            config.poll_interval(&config.sites["watch_out"]),
+            Duration::from_secs(60)
         );
-        
-        sites.insert(
-            "grimmeissen".to_string(),
-            SiteConfig {
-                url: "https://www.grimmeissen.de/de/uhren".to_string(),
-                webhook: "https://discord.com/api/webhooks/1353748268584009759/AmGqjGwQyzkexl6p9WSQY0JfmIsLcnEAjnxNEE4OUva-3F5ZNNWzcFj5lB7gXG4kw-I_".to_string(),
-                name: "Grimmeissen".to_string(),
-                color: 0xDAA520,
-                base_url: "https://www.grimmeissen.de".to_string(),
-            },
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        let invalid = [
+            (
+                json!({ "sites": { "watch_out": { "url": "not a url" } } }),
+                "sites.watch_out.url",
+            ),
+            (
+                json!({ "sites": { "watch_out": { "base_url": "ftp://watch-out.shop" } } }),
+                "sites.watch_out.base_url",
+            ),
+            (
+                json!({ "sites": { "watch_out": { "webhook": "discord.com/api/webhooks/1/token" } } }),
+                "sites.watch_out.webhook",
+            ),
+            (json!({ "sold_webhook": "not a url" }), "sold_webhook"),
+            (
+                json!({ "exchange_rate_api_url": "exchangerate-api.com" }),
+                "exchange_rate_api_url",
+            ),
+        ];
+
+        for (overrides, key) in invalid {
+            let error = config(overrides).validate().unwrap_err();
+            assert!(
+                error
+                    .to_string()
+                    .starts_with(&format!("invalid value for `{}`", key)),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_sites() {
+        let mut config = config(json!({}));
+        let site = config.sites["watch_out"].clone();
+        config.sites.insert("watchout".to_string(), site);
+
+        match config.validate() {
+            Err(ConfigError::UnknownSite { site, expected }) => {
+                assert_eq!(site, "watchout");
+                assert!(expected.split(", ").any(|key| key == "watch_out"));
+            }
+            other => panic!("expected an unknown site, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let path =
+            std::env::temp_dir().join(format!("watch-monitor-config-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+check_interval_seconds = 60
+
+[sites.watch_out]
+url = "https://www.watch-out.shop/collections/uhren"
+webhook = "https://discord.com/api/webhooks/1/token"
+name = "Watch Out"
+color = 3092790
+base_url = "https://www.watch-out.shop"
+"#,
+        )
+        .unwrap();
+        // Passed in rather than set, which would leak into parallel tests
+        let env = [
+            ("WATCH_MONITOR__CHECK_INTERVAL_SECONDS", "120"),
+            ("WATCH_MONITOR__SITES__WATCH_OUT__INTERVAL_SECONDS", "300"),
+            (
+                "WATCH_MONITOR__SITES__WATCH_OUT__WEBHOOK",
+                "https://discord.com/api/webhooks/2/overridden",
+            ),
+        ];
+        let env = env
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        let config = Config::load_with_env(&path, Some(env));
+        std::fs::remove_file(&path).unwrap();
+
+        let config = config.unwrap();
+        let site = &config.sites["watch_out"];
+        assert_eq!(config.check_interval_seconds, 120);
+        assert_eq!(config.poll_interval(site), Duration::from_secs(300));
+        assert_eq!(
+            site.webhook.as_ref().unwrap().expose(),
+            "https://discord.com/api/webhooks/2/overridden"
         );
-        
-        sites.insert(
-            "tropicalwatch".to_string(),
-            SiteConfig {
-                url: "https://tropicalwatch.com/?sort=recent".to_string(),
-                webhook: "https://discord.com/api/webhooks/1356956912163225700/oTbe-SP7V1zgtccFWrNFD4p5vw4uzSPyJ8D9nhQKcb9c9ZkKfImV7ZDQwrFCuxMy07wd".to_string(),
-                name: "Tropical Watch".to_string(),
-                color: 0x008080,
-                base_url: "https://tropicalwatch.com".to_string(),
-            },
+    }
+
+    #[test]
+    fn diff_names_changed_settings_without_secrets() {
+        let rule = json!({
+            "name": "Speedmasters",
+            "webhook": "https://discord.com/api/webhooks/3/old-token",
+            "model_keywords": ["speedmaster"],
+        });
+        let old = config(json!({ "rules": [rule] }));
+        let new = config(json!({
+            "sites": { "watch_out": { "interval_seconds": 300 } },
+            "rules": [
+                {
+                    "name": "Speedmasters",
+                    "webhook": "https://discord.com/api/webhooks/3/new-token",
+                    "model_keywords": ["speedmaster"],
+                    "max_price_eur": 8000.0,
+                },
+                { "name": "Daytonas", "webhook": "https://discord.com/api/webhooks/4/token" },
+            ],
+        }));
+
+        let changes = old.diff(&new);
+
+        assert_eq!(
+            changes,
+            vec![
+                "rules[0].max_price_eur: null -> 8000.0",
+                "rules[0].webhook changed",
+                "rules[1] added",
+                "sites.watch_out.interval_seconds: null -> 300",
+            ]
         );
-        
-        sites.insert(
-            "juwelier_exchange".to_string(),
-            SiteConfig {
-                url: "https://www.juwelier-exchange.de/uhren".to_string(),
-                webhook: "https://discord.com/api/webhooks/1376895131432784014/h_1ML2z1qtLTQ_SuU7YqF9l8xOF2BdB1LoAecQVvvUPO2ejojZB6H_8RnatL7c82Ew3p".to_string(),
-                name: "Juwelier Exchange".to_string(),
-                color: 0xB08D57,
-                base_url: "https://www.juwelier-exchange.de".to_string(),
-            },
+        assert!(!changes.iter().any(|change| change.contains("token")));
+        assert!(old.diff(&old.clone()).is_empty());
+    }
+
+    #[test]
+    fn restarts_only_the_sites_a_change_concerns() {
+        let grimmeissen = json!({
+            "url": "https://www.grimmeissen.de/uhren",
+            "webhook": "https://discord.com/api/webhooks/2/token",
+            "name": "Grimmeissen",
+            "color": 0,
+            "base_url": "https://www.grimmeissen.de",
+            "interval_seconds": 600,
+        });
+        let old = config(json!({ "sites": { "grimmeissen": grimmeissen.clone() } }));
+        let changed = |overrides: serde_json::Value| {
+            let mut new = json!({ "sites": { "grimmeissen": grimmeissen.clone() } });
+            merge(&mut new, overrides);
+            old.changed_sites(&config(new))
+        };
+
+        assert_eq!(
+            changed(json!({ "sites": { "watch_out": { "jitter_seconds": 30 } } })),
+            vec!["watch_out"]
         );
-        
-        sites.insert(
-            "watch_out".to_string(),
-            SiteConfig {
-                url: "https://www.watch-out.shop/collections/gebrauchte-uhren?sort_by=created-descending".to_string(),
-                webhook: "https://discord.com/api/webhooks/1376895816312291348/Hhhf6asQRoKlPzf5E_NYz0fA7VsSUphPDeBLWyLGcHw324qEorsH6B7bH8gdhzcc6SOi".to_string(),
-                name: "Watch Out".to_string(),
-                color: 0xC0C0C0,
-                base_url: "https://www.watch-out.shop".to_string(),
-            },
+        // Grimmeissen keeps its own interval
+        assert_eq!(
+            changed(json!({ "check_interval_seconds": 120 })),
+            vec!["watch_out"]
         );
-        
-        sites.insert(
-            "rueschenbeck".to_string(),
-            SiteConfig {
-                url: "https://www.rueschenbeck.de/vintage-certified-pre-owned".to_string(),
-                webhook: "https://discord.com/api/webhooks/1376895941533110333/XwN3ZJcRqnrAE_LE9LO4KIEekPnkwGw-ibpxJQ8F9BmNYbfErhBSHhQ7fmSOFDaYXmGw".to_string(),
-                name: "Rüschenbeck".to_string(),
-                color: 0xCFB53B,
-                base_url: "https://www.rueschenbeck.de".to_string(),
-            },
+        assert_eq!(
+            changed(json!({ "sites": { "grimmeissen": { "enabled": false } } })),
+            vec!["grimmeissen"]
         );
+        assert!(changed(
+            json!({ "dashboard": { "enabled": true }, "shutdown_timeout_seconds": 5 })
+        )
+        .is_empty());
+        // Every check reads the rules
+        assert_eq!(
+            changed(
+                json!({ "rules": [{ "name": "Daytonas", "webhook": "https://discord.com/api/webhooks/4/token" }] })
+            ),
+            vec!["grimmeissen", "watch_out"]
+        );
+        assert!(old.changed_sites(&old.clone()).is_empty());
+    }
 
-        Ok(Config {
-            sites,
-            check_interval_seconds: 60,
-            user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/108.0.0.0 Safari/537.36".to_string(),
-            exchange_rate_api_url: "https://api.exchangerate-api.com/v4/latest/USD".to_string(),
-        })
+    fn hours(start: &str, end: &str) -> ActiveHours {
+        ActiveHours {
+            start: TimeOfDay::try_from(start.to_string()).unwrap(),
+            end: TimeOfDay::try_from(end.to_string()).unwrap(),
+        }
     }
-}
\ No newline at end of file
+
+    fn at(time: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn active_hours_include_the_start_and_exclude_the_end() {
+        let daytime = hours("09:00", "17:00");
+        assert!(!daytime.contains(at("08:59")));
+        assert!(daytime.contains(at("09:00")));
+        assert!(daytime.contains(at("16:59")));
+        assert!(!daytime.contains(at("17:00")));
+
+        // Wraps past midnight
+        let overnight = hours("22:00", "06:00");
+        assert!(!overnight.contains(at("21:59")));
+        assert!(overnight.contains(at("22:00")));
+        assert!(overnight.contains(at("00:00")));
+        assert!(overnight.contains(at("05:59")));
+        assert!(!overnight.contains(at("06:00")));
+        assert!(!overnight.contains(at("12:00")));
+    }
+
+    #[test]
+    fn waits_until_active_hours_open() {
+        let minutes = |minutes: u64| Duration::from_secs(minutes * 60);
+
+        let daytime = hours("09:00", "17:00");
+        assert_eq!(daytime.until_open(at("08:30")), minutes(30));
+        assert_eq!(daytime.until_open(at("09:00")), Duration::ZERO);
+        assert_eq!(daytime.until_open(at("12:00")), Duration::ZERO);
+        // Closed for today, opens tomorrow morning
+        assert_eq!(daytime.until_open(at("17:00")), minutes(16 * 60));
+        assert_eq!(daytime.until_open(at("23:30")), minutes(9 * 60 + 30));
+
+        let overnight = hours("22:00", "06:00");
+        assert_eq!(overnight.until_open(at("23:00")), Duration::ZERO);
+        assert_eq!(overnight.until_open(at("03:00")), Duration::ZERO);
+        assert_eq!(overnight.until_open(at("06:00")), minutes(16 * 60));
+        assert_eq!(overnight.until_open(at("21:59")), minutes(1));
+    }
+}
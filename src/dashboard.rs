@@ -0,0 +1,464 @@
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use html_escape::{encode_double_quoted_attribute as attr, encode_text as text};
+use std::fmt::Write;
+use tracing::error;
+
+use crate::api::ListingParams;
+use crate::config::Config;
+use crate::models::{format_price, Site, WatchId, EMOJI_QUESTION};
+use crate::server::ServerState;
+use crate::storage::{ListingFilter, ListingSort, PriceRecord, StoredListing};
+
+/// Listings per page
+const PAGE_SIZE: u32 = 100;
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 1.5rem; color: #222; }
+form { display: flex; flex-wrap: wrap; gap: .5rem; align-items: end; margin-bottom: 1rem; }
+label { display: flex; flex-direction: column; font-size: .8rem; color: #555; }
+input, select { padding: .25rem; }
+input[type=number] { width: 6rem; }
+table { border-collapse: collapse; width: 100%; }
+th, td { padding: .4rem; border-bottom: 1px solid #ddd; text-align: left; vertical-align: top; }
+th a { color: inherit; }
+td img { width: 96px; height: 96px; object-fit: cover; }
+.muted { color: #777; font-size: .85rem; }
+.drop { color: #1e8449; }
+.rise { color: #c0392b; }
+.error { color: #c0392b; }
+.ended { opacity: .6; }
+";
+
+pub fn router(state: ServerState) -> Router {
+    Router::new().route("/", get(dashboard)).with_state(state)
+}
+
+/// A listing shown on the dashboard
+struct Row {
+    listing: StoredListing,
+    /// Oldest first
+    prices: Vec<PriceRecord>,
+}
+
+async fn dashboard(State(state): State<ServerState>, Query(params): Query<ListingParams>) -> Response {
+    let config = state.config.borrow().clone();
+    if !config.dashboard.enabled {
+        return (StatusCode::NOT_FOUND, "The dashboard is disabled").into_response();
+    }
+
+    let filter = match params.to_filter(PAGE_SIZE) {
+        Ok(filter) => filter,
+        Err(message) => return Html(render_page(&config, &params, Err(&message))).into_response(),
+    };
+
+    let load = async {
+        // One more than shown tells whether there is a next page
+        let lookahead = ListingFilter {
+            limit: filter.limit + 1,
+            ..filter.clone()
+        };
+        let mut listings = state.storage.search_listings(&lookahead).await?;
+        let more = listings.len() > filter.limit as usize;
+        listings.truncate(filter.limit as usize);
+
+        let keys: Vec<(Site, WatchId)> = listings
+            .iter()
+            .filter_map(|listing| Some((Site::from_key(&listing.site)?, WatchId(listing.listing_id.clone()))))
+            .collect();
+        let mut histories = state.storage.price_histories(&keys).await?;
+
+        let rows = listings
+            .into_iter()
+            .map(|listing| {
+                let prices = Site::from_key(&listing.site)
+                    .and_then(|site| histories.remove(&(site, WatchId(listing.listing_id.clone()))))
+                    .unwrap_or_default();
+                Row { listing, prices }
+            })
+            .collect::<Vec<_>>();
+        anyhow::Ok((rows, more))
+    };
+
+    match load.await {
+        Ok((rows, more)) => Html(render_page(&config, &params, Ok((&filter, &rows, more)))).into_response(),
+        Err(e) => {
+            error!("Failed to load the dashboard: {:#}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to load listings, see the monitor's log").into_response()
+        }
+    }
+}
+
+/// The whole page: filters, then the listings or why the filters are
+/// invalid. `more` tells whether listings follow this page.
+fn render_page(config: &Config, params: &ListingParams, page: Result<(&ListingFilter, &[Row], bool), &str>) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    let _ = write!(html, "<title>Watch Monitor</title>\n<style>{}</style>\n</head>\n<body>\n", STYLE);
+    html.push_str("<h1>Watch Monitor</h1>\n");
+
+    render_filters(&mut html, config, params);
+
+    match page {
+        Err(message) => {
+            let _ = writeln!(html, "<p class=\"error\">{}</p>", text(message));
+        }
+        Ok((_, [], _)) => html.push_str("<p>No listings match these filters.</p>\n"),
+        Ok((filter, rows, more)) => {
+            render_table(&mut html, config, params, filter, rows);
+            render_pagination(&mut html, params, filter, rows.len(), more);
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render_filters(html: &mut String, config: &Config, params: &ListingParams) {
+    let sites: Vec<(&str, &str)> = Site::all()
+        .iter()
+        .filter_map(|site| config.sites.get(site.key()).map(|site_config| (site.key(), site_config.name.as_str())))
+        .collect();
+    let presence = [("yes", "Yes"), ("no", "No"), ("unknown", "Unknown")];
+
+    html.push_str("<form method=\"get\">\n");
+    select(html, "site", "Site", &sites, &params.site);
+    input(html, "brand", "Brand", "text", &params.brand);
+    input(html, "q", "Search", "search", &params.q);
+    input(html, "min_price", "Min €", "number", &params.min_price);
+    input(html, "max_price", "Max €", "number", &params.max_price);
+    input(html, "min_year", "From year", "number", &params.min_year);
+    input(html, "max_year", "To year", "number", &params.max_year);
+    select(html, "box", "Box", &presence, &params.box_status);
+    select(html, "papers", "Papers", &presence, &params.papers);
+    select(
+        html,
+        "status",
+        "Status",
+        &[("active", "For sale"), ("sold", "Sold"), ("removed", "Removed")],
+        &params.status,
+    );
+    for (name, value) in [("sort", &params.sort), ("order", &params.order)] {
+        if let Some(value) = value {
+            let _ = writeln!(html, "<input type=\"hidden\" name=\"{}\" value=\"{}\">", name, attr(value));
+        }
+    }
+    html.push_str("<button type=\"submit\">Filter</button> <a href=\"/\">Reset</a>\n</form>\n");
+}
+
+fn input(html: &mut String, name: &str, label: &str, kind: &str, value: &Option<String>) {
+    let _ = writeln!(
+        html,
+        "<label>{}<input type=\"{}\" name=\"{}\" value=\"{}\"></label>",
+        label,
+        kind,
+        name,
+        attr(value.as_deref().unwrap_or_default())
+    );
+}
+
+/// Drop-down with an "Any" choice first
+fn select(html: &mut String, name: &str, label: &str, options: &[(&str, &str)], value: &Option<String>) {
+    let _ = write!(html, "<label>{}<select name=\"{}\"><option value=\"\">Any</option>", label, name);
+    for (key, option_label) in options {
+        let selected = if value.as_deref() == Some(key) { " selected" } else { "" };
+        let _ = write!(html, "<option value=\"{}\"{}>{}</option>", key, selected, text(option_label));
+    }
+    html.push_str("</select></label>\n");
+}
+
+fn render_table(html: &mut String, config: &Config, params: &ListingParams, filter: &ListingFilter, rows: &[Row]) {
+    html.push_str("<table>\n<thead><tr><th></th>");
+    let _ = write!(html, "<th>{}</th>", sort_link(params, filter, ListingSort::Brand, "Watch"));
+    html.push_str("<th>Site</th>");
+    let _ = write!(html, "<th>{}</th>", sort_link(params, filter, ListingSort::Year, "Year"));
+    html.push_str("<th>Box</th><th>Papers</th>");
+    let _ = write!(html, "<th>{}</th>", sort_link(params, filter, ListingSort::Price, "Price"));
+    let _ = write!(html, "<th>{}</th>", sort_link(params, filter, ListingSort::FirstSeen, "First seen"));
+    html.push_str("<th>Status</th></tr></thead>\n<tbody>\n");
+
+    for row in rows {
+        render_row(html, config, row);
+    }
+
+    html.push_str("</tbody>\n</table>\n");
+}
+
+fn render_row(html: &mut String, config: &Config, row: &Row) {
+    let stored = &row.listing;
+    let listing = &stored.listing;
+    let site_name = config
+        .sites
+        .get(&stored.site)
+        .map_or(listing.site_name.as_str(), |site_config| site_config.name.as_str());
+
+    let _ = write!(html, "<tr{}>", if stored.ended_at.is_some() { " class=\"ended\"" } else { "" });
+
+    match http_url(&listing.image_url) {
+        Some(image) => {
+            let _ = write!(html, "<td><img src=\"{}\" alt=\"\" loading=\"lazy\"></td>", attr(image));
+        }
+        None => html.push_str("<td></td>"),
+    }
+
+    let title = match http_url(&listing.watch_url) {
+        Some(url) => format!(
+            "<a href=\"{}\" target=\"_blank\" rel=\"noopener noreferrer\">{}</a>",
+            attr(url),
+            text(&listing.title)
+        ),
+        None => text(&listing.title).into_owned(),
+    };
+    let details: Vec<&str> = [&listing.brand, &listing.model, &listing.reference]
+        .into_iter()
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty() && *value != EMOJI_QUESTION)
+        .collect();
+    let _ = write!(
+        html,
+        "<td>{}<div class=\"muted\">{}</div></td>",
+        title,
+        text(&details.join(" · "))
+    );
+
+    let _ = write!(
+        html,
+        "<td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
+        text(site_name),
+        text(&listing.year),
+        listing.box_status,
+        listing.papers_status
+    );
+    let _ = write!(html, "<td>{}{}</td>", text(&listing.price_display()), price_change(&row.prices));
+    let _ = write!(html, "<td>{}</td>", stored.first_seen.format("%Y-%m-%d %H:%M UTC"));
+
+    match stored.ended_at {
+        Some(ended_at) => {
+            let _ = write!(
+                html,
+                "<td>{}<div class=\"muted\">{}</div></td>",
+                stored.status,
+                ended_at.format("%Y-%m-%d")
+            );
+        }
+        None => {
+            let _ = write!(html, "<td>{}</td>", stored.status);
+        }
+    }
+
+    html.push_str("</tr>\n");
+}
+
+/// The previous price struck through, with every price in the tooltip
+fn price_change(prices: &[PriceRecord]) -> String {
+    let [.., previous, latest] = prices else {
+        return String::new();
+    };
+
+    let class = match (previous.price, latest.price) {
+        (old, new) if old.currency != new.currency => "",
+        (old, new) if new.amount_minor < old.amount_minor => " drop",
+        (old, new) if new.amount_minor > old.amount_minor => " rise",
+        _ => "",
+    };
+    let changes = prices.len() - 1;
+    let history: Vec<String> = prices
+        .iter()
+        .map(|record| {
+            format!(
+                "{}: {}",
+                record.first_seen.format("%Y-%m-%d"),
+                format_price(Some(record.price), record.price_eur)
+            )
+        })
+        .collect();
+
+    format!(
+        "<div class=\"muted{}\" title=\"{}\">was <s>{}</s>, {} price {}</div>",
+        class,
+        attr(&history.join("\n")),
+        text(&format_price(Some(previous.price), previous.price_eur)),
+        changes,
+        if changes == 1 { "change" } else { "changes" }
+    )
+}
+
+/// Column header sorting by `sort`; sorting by the current column again
+/// reverses the order
+fn sort_link(params: &ListingParams, filter: &ListingFilter, sort: ListingSort, label: &str) -> String {
+    let (ascending, arrow) = if filter.sort == sort {
+        (!filter.ascending, if filter.ascending { " ▲" } else { " ▼" })
+    } else {
+        (sort.ascending_by_default(), "")
+    };
+
+    let linked = ListingParams {
+        sort: Some(sort.key().to_string()),
+        order: Some(if ascending { "asc" } else { "desc" }.to_string()),
+        offset: None,
+        ..params.clone()
+    };
+
+    format!("<a href=\"?{}\">{}{}</a>", attr(&query_string(&linked)), label, arrow)
+}
+
+fn render_pagination(html: &mut String, params: &ListingParams, filter: &ListingFilter, shown: usize, more: bool) {
+    let mut links = Vec::new();
+    if filter.offset > 0 {
+        let previous = ListingParams {
+            offset: Some(filter.offset.saturating_sub(filter.limit).to_string()),
+            ..params.clone()
+        };
+        links.push(format!("<a href=\"?{}\">← Previous</a>", attr(&query_string(&previous))));
+    }
+    // Past the largest offset there is no page to link to
+    if let Some(next_offset) = filter.offset.checked_add(filter.limit).filter(|_| more) {
+        let next = ListingParams {
+            offset: Some(next_offset.to_string()),
+            ..params.clone()
+        };
+        links.push(format!("<a href=\"?{}\">Next →</a>", attr(&query_string(&next))));
+    }
+
+    let first = u64::from(filter.offset) + 1;
+    let _ = writeln!(html, "<p>Listings {}–{} {}</p>", first, first + shown as u64 - 1, links.join(" "));
+}
+
+/// Query string without the parameters left empty
+fn query_string(params: &ListingParams) -> String {
+    serde_urlencoded::to_string(params)
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !pair.ends_with('='))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Links and images come from dealer pages, so only web URLs are used
+fn http_url(url: &str) -> Option<&str> {
+    let parsed = url::Url::parse(url.trim()).ok()?;
+    matches!(parsed.scheme(), "http" | "https").then_some(url.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Currency, ListingStatus, Money, WatchListing};
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    fn config() -> Config {
+        serde_json::from_value(json!({
+            "sites": {
+                "watch_out": {
+                    "url": "https://www.watch-out.shop/uhren",
+                    "webhook": "https://discord.com/api/webhooks/0/test",
+                    "name": "Watch Out",
+                    "color": 0,
+                    "base_url": "https://www.watch-out.shop",
+                }
+            },
+            "listen": "127.0.0.1:0",
+            "dashboard": { "enabled": true },
+        }))
+        .unwrap()
+    }
+
+    fn price(euros: i64, day: u32) -> PriceRecord {
+        let seen = Utc.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap();
+        PriceRecord {
+            price: Money::new(euros * 100, Currency::Eur),
+            price_eur: Some(Money::new(euros * 100, Currency::Eur)),
+            first_seen: seen,
+            last_seen: seen,
+        }
+    }
+
+    #[test]
+    fn renders_listings_safely_with_price_changes() {
+        let listing = WatchListing {
+            title: "Rolex <Submariner> & Box".to_string(),
+            brand: "Rolex".to_string(),
+            watch_url: "javascript:alert(1)".to_string(),
+            image_url: "https://www.watch-out.shop/img/\"sub\".jpg".to_string(),
+            price: Some(Money::new(910000, Currency::Eur)),
+            price_eur: Some(Money::new(910000, Currency::Eur)),
+            ..Default::default()
+        };
+        let row = Row {
+            listing: StoredListing {
+                site: "watch_out".to_string(),
+                listing_id: "abc".to_string(),
+                listing,
+                status: ListingStatus::Active,
+                first_seen: Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap(),
+                last_seen: Utc.with_ymd_and_hms(2026, 3, 5, 12, 0, 0).unwrap(),
+                ended_at: None,
+            },
+            prices: vec![price(9500, 1), price(9100, 5)],
+        };
+        let params = ListingParams {
+            brand: Some("rolex".to_string()),
+            ..Default::default()
+        };
+        let filter = params.to_filter(PAGE_SIZE).unwrap();
+
+        let html = render_page(&config(), &params, Ok((&filter, &[row], false)));
+
+        assert!(html.contains("Rolex &lt;Submariner&gt; &amp; Box"));
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("src=\"https://www.watch-out.shop/img/&quot;sub&quot;.jpg\""));
+        assert!(html.contains("class=\"muted drop\""));
+        assert!(html.contains("was <s>9.500 €</s>, 1 price change"));
+        assert!(html.contains("<option value=\"watch_out\">Watch Out</option>"));
+        assert!(html.contains("name=\"brand\" value=\"rolex\""));
+        // Newest first, so sorting by first seen again flips to oldest first
+        assert!(html.contains("href=\"?brand=rolex&amp;sort=first_seen&amp;order=asc\">First seen ▼"));
+    }
+
+    #[test]
+    fn reports_invalid_filters() {
+        let params = ListingParams {
+            min_year: Some("soon".to_string()),
+            ..Default::default()
+        };
+
+        let html = render_page(&config(), &params, Err("Invalid min_year `soon`"));
+        assert!(html.contains("<p class=\"error\">Invalid min_year `soon`</p>"));
+    }
+
+    #[test]
+    fn links_the_next_page_only_if_listings_follow() {
+        let params = ListingParams::default();
+        let filter = params.to_filter(PAGE_SIZE).unwrap();
+
+        let mut html = String::new();
+        render_pagination(&mut html, &params, &filter, PAGE_SIZE as usize, true);
+        assert!(html.contains(&format!("Listings 1–{} <a href=\"?offset={}\">Next", PAGE_SIZE, PAGE_SIZE)));
+
+        let mut html = String::new();
+        render_pagination(&mut html, &params, &filter, PAGE_SIZE as usize, false);
+        assert!(!html.contains("Next"));
+    }
+
+    #[test]
+    fn paginates_up_to_the_largest_offset() {
+        let params = ListingParams {
+            offset: Some(u32::MAX.to_string()),
+            ..Default::default()
+        };
+        let filter = params.to_filter(PAGE_SIZE).unwrap();
+
+        let mut html = String::new();
+        render_pagination(&mut html, &params, &filter, 1, true);
+
+        let first = u64::from(u32::MAX) + 1;
+        assert!(html.contains(&format!("Listings {}–{}", first, first)));
+        assert!(!html.contains("Next"));
+        assert!(html.contains(&format!("offset={}\">← Previous", u32::MAX - PAGE_SIZE)));
+    }
+}
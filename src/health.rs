@@ -0,0 +1,288 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt;
+
+use crate::config::HealthConfig;
+use crate::models::{WatchListing, DETAIL_FIELDS};
+
+/// Non-empty scrapes needed before item counts are compared to the average
+const MIN_SAMPLES: u32 = 3;
+
+/// Weight of the newest item count in the rolling average
+const AVERAGE_WEIGHT: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    #[default]
+    Healthy,
+    /// The scraper keeps failing or its results look wrong
+    Broken,
+}
+
+impl HealthStatus {
+    pub fn key(&self) -> &'static str {
+        match self {
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Broken => "broken",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "healthy" => Some(HealthStatus::Healthy),
+            "broken" => Some(HealthStatus::Broken),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+/// How a site's scraper has been doing, kept across restarts
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SiteHealth {
+    pub status: HealthStatus,
+    pub consecutive_failures: u32,
+    /// Rolling average of listings found by non-empty scrapes
+    pub average_items: Option<f64>,
+    /// Non-empty scrapes counted into `average_items`
+    pub samples: u32,
+    pub last_items: Option<u32>,
+    /// Share of detail fields left unknown (❓) in the last scrape
+    pub unknown_share: Option<f64>,
+    /// Why the site looks broken; empty while healthy
+    pub problems: Vec<String>,
+    pub last_error: Option<String>,
+    pub last_success: Option<DateTime<Utc>>,
+}
+
+/// Share of listings with each detail field resolved in one scrape
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldCompleteness {
+    /// Listings still for sale the shares are computed over; sold listings
+    /// are reported without their details
+    pub listings: u32,
+    /// Percentage of listings with each of `DETAIL_FIELDS` resolved
+    pub fields: [(&'static str, f64); DETAIL_FIELDS.len()],
+}
+
+impl FieldCompleteness {
+    /// `None` if no listing is still for sale
+    pub fn from_listings(listings: &[WatchListing]) -> Option<Self> {
+        let for_sale: Vec<&WatchListing> = listings.iter().filter(|listing| !listing.sold).collect();
+        if for_sale.is_empty() {
+            return None;
+        }
+
+        let mut counts = [0u32; DETAIL_FIELDS.len()];
+        for listing in &for_sale {
+            for (count, known) in counts.iter_mut().zip(listing.known_details()) {
+                *count += known as u32;
+            }
+        }
+
+        let mut fields = DETAIL_FIELDS.map(|name| (name, 0.0));
+        for ((_, percent), count) in fields.iter_mut().zip(counts) {
+            *percent = count as f64 * 100.0 / for_sale.len() as f64;
+        }
+
+        Some(Self {
+            listings: for_sale.len() as u32,
+            fields,
+        })
+    }
+
+    /// Share of all detail fields left unknown, between 0 and 1
+    pub fn unknown_share(&self) -> f64 {
+        let total: f64 = self.fields.iter().map(|(_, percent)| percent).sum();
+        1.0 - total / (self.fields.len() as f64 * 100.0)
+    }
+
+    /// One-line summary such as `brand 100%, reference 92%, ...`
+    pub fn summary(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(name, percent)| format!("{} {:.0}%", name, percent))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Result of one scrape as far as health tracking is concerned
+pub enum ScrapeOutcome<'a> {
+    Scraped(&'a [WatchListing]),
+    Failed(&'a anyhow::Error),
+}
+
+impl SiteHealth {
+    /// Fold a scrape into the health record. Returns the new status when it
+    /// changed, i.e. when the site broke or recovered.
+    pub fn record(&mut self, outcome: ScrapeOutcome<'_>, config: &HealthConfig) -> Option<HealthStatus> {
+        match outcome {
+            ScrapeOutcome::Failed(e) => {
+                self.consecutive_failures += 1;
+                self.last_error = Some(format!("{:#}", e));
+
+                // A single failure is usually a network hiccup
+                if self.consecutive_failures < config.failure_threshold {
+                    return None;
+                }
+                self.problems = vec![format!("{} consecutive scrapes failed", self.consecutive_failures)];
+            }
+            ScrapeOutcome::Scraped(listings) => {
+                let items = listings.len() as u32;
+                self.consecutive_failures = 0;
+                self.last_error = None;
+                self.last_success = Some(Utc::now());
+                self.last_items = Some(items);
+                self.unknown_share =
+                    FieldCompleteness::from_listings(listings).map(|completeness| completeness.unknown_share());
+                self.problems = Vec::new();
+
+                match self.average_items {
+                    _ if items == 0 => self.problems.push("No listings found".to_string()),
+                    Some(average) if self.samples >= MIN_SAMPLES && (items as f64) < average * config.min_item_ratio => {
+                        self.problems.push(format!("Only {} listings found, {:.0} on average", items, average));
+                    }
+                    _ => {}
+                }
+
+                if let Some(share) = self.unknown_share.filter(|share| *share > config.max_unknown_share) {
+                    self.problems.push(format!("{:.0}% of listing details are unknown", share * 100.0));
+                }
+
+                // Empty results never count, so they keep being reported
+                if items > 0 {
+                    self.average_items = Some(match self.average_items {
+                        Some(average) => average + AVERAGE_WEIGHT * (items as f64 - average),
+                        None => items as f64,
+                    });
+                    self.samples += 1;
+                }
+            }
+        }
+
+        let status = if self.problems.is_empty() {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::Broken
+        };
+
+        if status == self.status {
+            return None;
+        }
+        self.status = status;
+        Some(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BoxStatus, PapersStatus, EMOJI_QUESTION};
+
+    fn complete_listing() -> WatchListing {
+        WatchListing {
+            brand: "Rolex".to_string(),
+            reference: "126610LN".to_string(),
+            year: "2021".to_string(),
+            condition_display: "Sehr gut".to_string(),
+            case_material: "Stahl".to_string(),
+            diameter: "41 mm".to_string(),
+            box_status: BoxStatus::Yes,
+            papers_status: PapersStatus::Yes,
+            ..Default::default()
+        }
+    }
+
+    fn scrape(health: &mut SiteHealth, items: usize) -> Option<HealthStatus> {
+        let listings = vec![complete_listing(); items];
+        health.record(ScrapeOutcome::Scraped(&listings), &HealthConfig::default())
+    }
+
+    #[test]
+    fn breaks_on_empty_results_and_recovers() {
+        let mut health = SiteHealth::default();
+
+        assert_eq!(scrape(&mut health, 20), None);
+        assert_eq!(scrape(&mut health, 0), Some(HealthStatus::Broken));
+        assert_eq!(health.problems, vec!["No listings found".to_string()]);
+        assert_eq!(scrape(&mut health, 0), None);
+        assert_eq!(health.average_items, Some(20.0));
+
+        assert_eq!(scrape(&mut health, 20), Some(HealthStatus::Healthy));
+        assert!(health.problems.is_empty());
+    }
+
+    #[test]
+    fn breaks_when_item_count_collapses() {
+        let mut health = SiteHealth::default();
+        for _ in 0..MIN_SAMPLES {
+            assert_eq!(scrape(&mut health, 40), None);
+        }
+
+        assert_eq!(scrape(&mut health, 25), None);
+        assert_eq!(scrape(&mut health, 5), Some(HealthStatus::Broken));
+        assert!(health.problems[0].starts_with("Only 5 listings found"));
+    }
+
+    #[test]
+    fn tolerates_failures_below_threshold() {
+        let config = HealthConfig::default();
+        let mut health = SiteHealth::default();
+        let error = anyhow::anyhow!("HTTP error: 503 Service Unavailable");
+
+        for _ in 1..config.failure_threshold {
+            assert_eq!(health.record(ScrapeOutcome::Failed(&error), &config), None);
+        }
+        assert_eq!(health.record(ScrapeOutcome::Failed(&error), &config), Some(HealthStatus::Broken));
+        assert_eq!(health.last_error.as_deref(), Some("HTTP error: 503 Service Unavailable"));
+
+        assert_eq!(scrape(&mut health, 10), Some(HealthStatus::Healthy));
+        assert_eq!(health.consecutive_failures, 0);
+        assert_eq!(health.last_error, None);
+    }
+
+    #[test]
+    fn computes_field_completeness_over_listings_for_sale() {
+        let listings = vec![
+            complete_listing(),
+            WatchListing {
+                year: EMOJI_QUESTION.to_string(),
+                diameter: String::new(),
+                papers_status: PapersStatus::Unknown,
+                ..complete_listing()
+            },
+            WatchListing {
+                sold: true,
+                ..Default::default()
+            },
+        ];
+
+        let completeness = FieldCompleteness::from_listings(&listings).unwrap();
+        assert_eq!(completeness.listings, 2);
+        assert_eq!(
+            completeness.summary(),
+            "brand 100%, reference 100%, year 50%, condition 100%, material 100%, diameter 50%, box 100%, papers 50%"
+        );
+        assert_eq!(completeness.unknown_share(), 3.0 / 16.0);
+
+        assert_eq!(FieldCompleteness::from_listings(&listings[2..]), None);
+    }
+
+    #[test]
+    fn breaks_when_details_go_missing() {
+        let mut health = SiteHealth::default();
+        let listings = vec![WatchListing::default(); 10];
+
+        let change = health.record(ScrapeOutcome::Scraped(&listings), &HealthConfig::default());
+        assert_eq!(change, Some(HealthStatus::Broken));
+        assert_eq!(health.unknown_share, Some(1.0));
+        assert_eq!(health.problems, vec!["100% of listing details are unknown".to_string()]);
+    }
+}
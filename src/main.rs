@@ -1,133 +1,413 @@
-use anyhow::Result;
-use chrono::Local;
+use anyhow::{Context, Result};
+use clap::Parser;
 use futures::future::join_all;
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::time::interval;
-use tracing::{error, info};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::{AbortHandle, JoinHandle};
+use tracing::{error, info, warn};
 
+mod api;
+mod cli;
+mod commands;
 mod config;
-mod discord;
+mod dashboard;
+mod health;
+mod metrics;
 mod models;
+mod monitor;
+mod notify;
 mod parsers;
+mod reload;
+mod rules;
 mod scrapers;
+mod server;
+mod shutdown;
 mod storage;
 mod utils;
 
+use crate::cli::{Cli, Command, DbCommand, LogFormat};
 use crate::config::Config;
-use crate::scrapers::{
-    GrimmeissenScraper, JuwelierExchangeScraper, RueschenbeckScraper, TropicalWatchScraper,
-    WatchOutScraper, WatchScraper, WorldOfTimeScraper,
-};
+use crate::models::Site;
+use crate::monitor::{ScrapeTriggers, SiteSignals};
+use crate::notify::{Delivery, Dispatcher};
+use crate::scrapers::{build_scraper, build_scrapers, WatchScraper};
+use crate::server::ServerState;
 use crate::storage::{SqliteStorage, Storage};
 use crate::utils::exchange_rate::ExchangeRateClient;
+use crate::utils::http::{HttpClient, HttpMode};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
+    let cli = Cli::parse();
+    init_logging(cli.log_format)?;
+
+    let delivery = cli.delivery();
+    let http_mode = cli.http_mode();
+    if let Delivery::Directory(dir) = &delivery {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create dry-run directory {}", dir.display()))?;
+    }
+
+    // Initialize storage
+    let storage: Arc<dyn Storage> = if delivery.is_dry_run() {
+        info!("Dry run: notifications are rendered, not sent, and nothing is written to {}", cli.db.display());
+        Arc::new(SqliteStorage::overlay(&cli.db).await?)
+    } else {
+        Arc::new(SqliteStorage::new(&cli.db).await?)
+    };
+    storage.migrate().await?;
+    let dispatcher = Dispatcher::new(delivery.clone(), storage.clone())?;
+
+    let config_path = Config::resolve_path(cli.config.as_deref());
+
+    let command = cli.command.unwrap_or(Command::Run);
+    if matches!(command, Command::Run | Command::Once | Command::Seed) {
+        shutdown::spawn_signal_handler();
+    }
+
+    match command {
+        Command::Run => run(&config_path, storage, dispatcher, http_mode).await,
+        Command::Once => {
+            commands::check_all_once(load_config(&config_path)?, storage.clone(), &dispatcher, &http_mode, true).await?;
+            finish(storage.as_ref()).await
+        }
+        Command::Seed => {
+            commands::check_all_once(load_config(&config_path)?, storage.clone(), &dispatcher, &http_mode, false).await?;
+            finish(storage.as_ref()).await
+        }
+        Command::Scrape { site } => commands::scrape(load_config(&config_path)?, storage, &http_mode, &site).await,
+        Command::ImportJson { path } => commands::import_json(storage, &path).await,
+        Command::Db { command: DbCommand::Stats } => commands::db_stats(storage).await,
+        Command::Db { command: DbCommand::Completeness { site, limit } } => {
+            commands::db_completeness(storage, site.as_deref(), limit).await
+        }
+    }
+}
+
+/// Log to stderr so that command output on stdout stays machine-readable
+fn init_logging(format: LogFormat) -> Result<()> {
+    let subscriber = tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::from_default_env()
                 .add_directive("watch_monitor=info".parse()?),
         )
-        .init();
+        .with_writer(std::io::stderr);
 
-    info!("Starting Watch Monitor");
+    match format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
 
-    // Load configuration
-    let config = Arc::new(Config::load()?);
+    Ok(())
+}
 
-    // Initialize storage
-    let storage = Arc::new(SqliteStorage::new("watch_monitor.db").await?);
-    storage.migrate().await?;
+fn load_config(path: &Path) -> Result<Arc<Config>> {
+    info!("Loading configuration from {}", path.display());
+    Ok(Arc::new(Config::load(path)?))
+}
+
+/// Monitor every enabled site until the process is stopped, applying
+/// configuration changes as they come in
+async fn run(config_path: &Path, storage: Arc<dyn Storage>, dispatcher: Dispatcher, http_mode: HttpMode) -> Result<()> {
+    info!("Starting Watch Monitor");
+
+    let mut config = load_config(config_path)?;
 
     // Initialize HTTP client with connection pooling
-    let client = Arc::new(utils::http::create_client()?);
-    
-    // Initialize exchange rate client for TropicalWatch
-    let exchange_rate_client = Arc::new(ExchangeRateClient::new());
-
-    // Initialize scrapers
-    let scrapers: Vec<Box<dyn WatchScraper>> = vec![
-        Box::new(WorldOfTimeScraper::new(config.clone())),
-        Box::new(GrimmeissenScraper::new(config.clone())),
-        Box::new(TropicalWatchScraper::new(config.clone(), exchange_rate_client)),
-        Box::new(JuwelierExchangeScraper::new(config.clone())),
-        Box::new(WatchOutScraper::new(config.clone())),
-        Box::new(RueschenbeckScraper::new(config.clone())),
-    ];
-
-    // Main monitoring loop
-    let mut interval = interval(Duration::from_secs(config.check_interval_seconds));
+    let mut client = Arc::new(utils::http::create_client(&config.user_agent, http_mode.clone())?);
     
+    // Exchange rates for non-EUR dealers, kept across reloads
+    let mut exchange_rates = Arc::new(ExchangeRateClient::new(
+        &config.exchange_rate_api_url,
+        config.exchange_rate_base,
+        storage.clone(),
+    ));
+
+    // Metrics and API listener, kept across reloads; the API always sees
+    // the current configuration
+    let (config_tx, config_rx) = watch::channel(config.clone());
+    let triggers = ScrapeTriggers::default();
+    if let Some(addr) = config.listen {
+        let listener = server::bind(addr).await?;
+        let state = ServerState {
+            config: config_rx,
+            storage: storage.clone(),
+            triggers: triggers.clone(),
+        };
+        tokio::spawn(server::serve(listener, state));
+    }
+
+    // Email digests go out on their own schedule, for the current configuration
+    tokio::spawn(dispatcher.clone().run_digests(config_tx.subscribe()));
+    // Queued Discord messages are delivered independently of the site checks
+    dispatcher.configure(&config);
+    let outbox_worker = tokio::spawn(dispatcher.clone().run_outbox());
+
+    let mut reload_triggers = reload::spawn_reload_triggers(config_path.to_path_buf());
+
+    let spawn_site = |scraper: Arc<dyn WatchScraper>,
+                      config: &Arc<Config>,
+                      client: &Arc<HttpClient>,
+                      exchange_rates: &Arc<ExchangeRateClient>,
+                      last_check: Option<Instant>| {
+        let (stop, stop_rx) = watch::channel(false);
+        let check_now = triggers.get(&scraper.site_key());
+        let handle = tokio::spawn(monitor::run_site(
+            scraper,
+            config.clone(),
+            client.clone(),
+            storage.clone(),
+            exchange_rates.clone(),
+            dispatcher.clone(),
+            SiteSignals {
+                stop: stop_rx,
+                check_now,
+                last_check,
+            },
+        ));
+        SiteTask { stop, handle }
+    };
+
+    // Every site runs on its own schedule; a new configuration restarts
+    // only the sites it changes
+    let mut site_tasks: HashMap<String, SiteTask> = build_scrapers(config.clone())
+        .into_iter()
+        .map(|scraper| {
+            let site_key = scraper.site_key().key().to_string();
+            (site_key, spawn_site(scraper, &config, &client, &exchange_rates, None))
+        })
+        .collect();
+    info!("Monitoring {} sites", site_tasks.len());
+
     loop {
-        interval.tick().await;
-        
-        info!("--- Starting new check cycle at {} ---", Local::now().format("%Y-%m-%d %H:%M:%S"));
-        
-        // Scrape all sites concurrently
-        let scraping_futures = scrapers.iter().map(|scraper| {
-            let client = client.clone();
-            let storage = storage.clone();
-            
-            async move {
-                let site_name = scraper.site_config().name.clone();
-                info!("Processing site: {}", site_name.to_uppercase());
-                
-                match scraper.scrape(&client).await {
-                    Ok(listings) => {
-                        info!("Found {} watch items on {}", listings.len(), site_name);
-                        
-                        let mut new_items = 0;
-                        for listing in listings {
-                            let watch_id = listing.generate_composite_id();
-                            
-                            // Check if we've seen this watch before
-                            if !storage.has_seen(&scraper.site_key(), &watch_id).await? {
-                                // Send Discord notification
-                                if let Err(e) = discord::send_notification(
-                                    &scraper.site_config().webhook,
-                                    &listing,
-                                    &scraper.site_config(),
-                                ).await {
-                                    error!("Failed to send Discord notification: {}", e);
-                                }
-                                
-                                // Mark as seen
-                                storage.mark_seen(&scraper.site_key(), &watch_id).await?;
-                                new_items += 1;
-                                
-                                // Small delay between notifications
-                                tokio::time::sleep(Duration::from_secs(1)).await;
-                            }
-                        }
-                        
-                        if new_items == 0 {
-                            info!("No new items found on {}", site_name);
-                        } else {
-                            info!("Found {} new items on {}", new_items, site_name);
-                        }
-                        
-                        Ok::<(), anyhow::Error>(())
-                    }
-                    Err(e) => {
-                        error!("CRITICAL UNHANDLED ERROR in {} scraper: {}", site_name, e);
-                        Ok(())
-                    }
-                }
-            }
-        });
-        
-        // Execute all scrapers concurrently
-        let results = join_all(scraping_futures).await;
-        
-        // Log any errors
-        for result in results {
-            if let Err(e) = result {
-                error!("Error in scraping task: {}", e);
-            }
+        let new_config = tokio::select! {
+            new_config = reload::wait_for_new_config(config_path, &config, &mut reload_triggers) => new_config,
+            _ = shutdown::requested() => None,
+        };
+
+        let Some(new_config) = new_config else {
+            // Without reload triggers the current configuration runs until shutdown
+            shutdown::requested().await;
+            return shut_down(handles(site_tasks), outbox_worker, &dispatcher, storage.as_ref(), config.shutdown_timeout()).await;
+        };
+
+        // Let the changed sites finish their current cycle before swapping
+        let changed = config.changed_sites(&new_config);
+        if !changed.is_empty() {
+            info!("Applying new configuration after in-flight checks of {} complete", changed.join(", "));
+        }
+        let (stopped, stopped_tasks): (Vec<String>, Vec<_>) = changed
+            .iter()
+            .filter_map(|site_key| {
+                let task = site_tasks.remove(site_key)?;
+                let _ = task.stop.send(true);
+                Some((site_key.clone(), task.handle))
+            })
+            .unzip();
+        let last_checks: HashMap<String, Instant> = stopped
+            .into_iter()
+            .zip(join_site_tasks(stopped_tasks).await)
+            .filter_map(|(site_key, last_check)| Some((site_key, last_check.flatten()?)))
+            .collect();
+
+        if shutdown::is_requested() {
+            return shut_down(handles(site_tasks), outbox_worker, &dispatcher, storage.as_ref(), config.shutdown_timeout()).await;
+        }
+
+        if new_config.listen != config.listen {
+            warn!("A changed `listen` address takes effect after a restart");
+        }
+        if new_config.user_agent != config.user_agent {
+            client = Arc::new(utils::http::create_client(&new_config.user_agent, http_mode.clone())?);
+        }
+        if new_config.exchange_rate_api_url != config.exchange_rate_api_url
+            || new_config.exchange_rate_base != config.exchange_rate_base
+        {
+            exchange_rates = Arc::new(ExchangeRateClient::new(
+                &new_config.exchange_rate_api_url,
+                new_config.exchange_rate_base,
+                storage.clone(),
+            ));
+        }
+        config = Arc::new(new_config);
+        dispatcher.configure(&config);
+        config_tx.send_replace(config.clone());
+
+        // Restarted sites keep their schedule; disabled and removed ones stay stopped
+        for site_key in &changed {
+            let Some(site) = Site::from_key(site_key).filter(|_| config.sites.get(site_key).is_some_and(|site| site.enabled)) else {
+                continue;
+            };
+            let scraper = build_scraper(&site, config.clone());
+            let last_check = last_checks.get(site_key).copied();
+            site_tasks.insert(site_key.clone(), spawn_site(scraper, &config, &client, &exchange_rates, last_check));
+        }
+        info!("Configuration reloaded, monitoring {} sites", site_tasks.len());
+    }
+}
+
+/// A running `monitor::run_site` task
+struct SiteTask {
+    stop: watch::Sender<bool>,
+    handle: JoinHandle<Option<Instant>>,
+}
+
+fn handles(site_tasks: HashMap<String, SiteTask>) -> Vec<JoinHandle<Option<Instant>>> {
+    site_tasks.into_values().map(|task| task.handle).collect()
+}
+
+/// Give the site tasks `timeout` to finish their in-flight checks and the
+/// Discord messages they queued to go out, then abort what is left and
+/// flush the database
+async fn shut_down<T>(
+    site_tasks: Vec<JoinHandle<T>>,
+    outbox_worker: JoinHandle<()>,
+    dispatcher: &Dispatcher,
+    storage: &dyn Storage,
+    timeout: Duration,
+) -> Result<()> {
+    let mut aborts: Vec<AbortHandle> = site_tasks.iter().map(|task| task.abort_handle()).collect();
+    aborts.push(outbox_worker.abort_handle());
+
+    let in_flight = async {
+        join_site_tasks(site_tasks).await;
+        info!("In-flight checks finished");
+
+        // The worker stops after its current pass; deliver the rest here
+        if let Err(e) = outbox_worker.await {
+            error!("Discord delivery task terminated unexpectedly: {}", e);
+        }
+        if let Err(e) = dispatcher.drain_outbox().await {
+            error!("Failed to deliver queued Discord messages: {:#}", e);
+        }
+    };
+
+    if tokio::time::timeout(timeout, in_flight).await.is_err() {
+        warn!(
+            "In-flight checks and notifications did not finish within {} seconds and were aborted; a listing announced but not yet marked as seen may be announced again",
+            timeout.as_secs()
+        );
+        for abort in aborts {
+            abort.abort();
         }
-        
-        info!("Check cycle completed, waiting {} seconds", config.check_interval_seconds);
     }
-}
\ No newline at end of file
+
+    finish(storage).await
+}
+
+/// Flush the database and log how the process ended
+async fn finish(storage: &dyn Storage) -> Result<()> {
+    storage.close().await?;
+
+    if shutdown::is_requested() {
+        info!("Watch Monitor stopped");
+    }
+    Ok(())
+}
+
+/// What each task returned, or `None` if it failed
+async fn join_site_tasks<T>(site_tasks: Vec<JoinHandle<T>>) -> Vec<Option<T>> {
+    join_all(site_tasks)
+        .await
+        .into_iter()
+        .map(|result| {
+            result
+                .map_err(|e| error!("Site task terminated unexpectedly: {}", e))
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::WatchListing;
+    use crate::notify::Notification;
+    use serde_json::json;
+    use tokio::sync::oneshot;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Set in the process the shutdown test runs in
+    const ISOLATED_ENV: &str = "WATCH_MONITOR_SHUTDOWN_TEST";
+
+    #[tokio::test]
+    async fn delivers_what_in_flight_checks_queued_before_shutting_down() {
+        // Shutdown cannot be undone and would stop every other test, so the
+        // test runs again in a process of its own
+        if std::env::var_os(ISOLATED_ENV).is_none() {
+            let test = "tests::delivers_what_in_flight_checks_queued_before_shutting_down";
+            let status = tokio::process::Command::new(std::env::current_exe().unwrap())
+                .args([test, "--exact", "--nocapture"])
+                .env(ISOLATED_ENV, "1")
+                .status()
+                .await
+                .unwrap();
+            assert!(status.success());
+            return;
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/site"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let config: Arc<Config> = Arc::new(
+            serde_json::from_value(json!({
+                "sites": {
+                    "watch_out": {
+                        "url": "https://www.watch-out.shop/uhren",
+                        "webhook": format!("{}/site", server.uri()),
+                        "name": "Watch Out",
+                        "color": 0,
+                        "base_url": "https://www.watch-out.shop",
+                    }
+                },
+            }))
+            .unwrap(),
+        );
+        let listing = WatchListing {
+            title: "Omega Speedmaster".to_string(),
+            brand: "Omega".to_string(),
+            ..Default::default()
+        };
+
+        let storage = SqliteStorage::overlay(Path::new("/nonexistent/watch-monitor-shutdown-test.db"))
+            .await
+            .unwrap();
+        storage.migrate().await.unwrap();
+        let storage: Arc<dyn Storage> = Arc::new(storage);
+        let dispatcher = Dispatcher::new(Delivery::Webhook, storage.clone()).unwrap();
+        let outbox_worker = tokio::spawn(dispatcher.clone().run_outbox());
+
+        // A check that is about to announce a listing when shutdown is requested
+        let (started_tx, started_rx) = oneshot::channel();
+        let check = {
+            let (config, dispatcher, listing) = (config.clone(), dispatcher.clone(), listing.clone());
+            tokio::spawn(async move {
+                started_tx.send(()).unwrap();
+                shutdown::requested().await;
+
+                let site_config = &config.sites["watch_out"];
+                let notification = Notification::Listing { site: &Site::WatchOut, listing: &listing, rule: None };
+                dispatcher.send(&site_config.targets(), &notification, site_config).await;
+            })
+        };
+        started_rx.await.unwrap();
+
+        let started = Instant::now();
+        shutdown::request();
+        let timeout = Duration::from_secs(10);
+        shut_down(vec![check], outbox_worker, &dispatcher, storage.as_ref(), timeout).await.unwrap();
+
+        assert!(started.elapsed() < timeout);
+        assert_eq!(storage.outbox_len().await.unwrap(), 0);
+        assert!(storage.has_seen(&Site::WatchOut, &listing.generate_listing_id()).await.unwrap());
+        server.verify().await;
+    }
+}
@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_gauge, register_gauge_vec, register_histogram_vec, register_int_counter,
+    register_int_counter_vec, Encoder, Gauge, GaugeVec, HistogramTimer, HistogramVec, IntCounter,
+    IntCounterVec, TextEncoder,
+};
+
+/// Scrapes take from well under a second to several minutes with detail pages
+const SCRAPE_BUCKETS: &[f64] = &[0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+const DB_BUCKETS: &[f64] = &[0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0];
+
+pub static SCRAPE_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "watch_monitor_scrape_duration_seconds",
+        "Time taken to scrape a site, including detail pages",
+        &["site", "outcome"],
+        SCRAPE_BUCKETS.to_vec()
+    )
+    .expect("Invalid scrape duration metric")
+});
+
+pub static HTTP_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "watch_monitor_http_requests_total",
+        "HTTP requests sent to dealers by host and status code (`error` if no response)",
+        &["host", "status"]
+    )
+    .expect("Invalid HTTP request metric")
+});
+
+pub static HTTP_RETRIES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "watch_monitor_http_retries_total",
+        "HTTP requests retried after a failed attempt",
+        &["host"]
+    )
+    .expect("Invalid HTTP retry metric")
+});
+
+pub static LISTINGS_FOUND: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!(
+        "watch_monitor_listings_found",
+        "Listings found by the latest successful scrape of a site",
+        &["site"]
+    )
+    .expect("Invalid listings found metric")
+});
+
+pub static NEW_LISTINGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "watch_monitor_new_listings_total",
+        "Listings seen for the first time, not counting seeding",
+        &["site"]
+    )
+    .expect("Invalid new listings metric")
+});
+
+pub static NOTIFICATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "watch_monitor_notifications_total",
+        "Notifications by notifier, kind and outcome (`sent` or `failed`)",
+        &["notifier", "kind", "outcome"]
+    )
+    .expect("Invalid notification metric")
+});
+
+pub static DISCORD_RATE_LIMITS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "watch_monitor_discord_rate_limits_total",
+        "Discord webhook posts rejected with 429 Too Many Requests"
+    )
+    .expect("Invalid Discord rate limit metric")
+});
+
+pub static DISCORD_QUEUE_LENGTH: Lazy<Gauge> = Lazy::new(|| {
+    register_gauge!(
+        "watch_monitor_discord_queue_length",
+        "Discord messages waiting for delivery, including those waiting for a retry"
+    )
+    .expect("Invalid Discord queue length metric")
+});
+
+pub static EXCHANGE_RATES_FETCHED_AT: Lazy<Gauge> = Lazy::new(|| {
+    register_gauge!(
+        "watch_monitor_exchange_rates_fetched_timestamp_seconds",
+        "When the exchange rates in use were fetched, as a Unix timestamp"
+    )
+    .expect("Invalid exchange rate timestamp metric")
+});
+
+pub static EXCHANGE_RATES_AGE: Lazy<Gauge> = Lazy::new(|| {
+    register_gauge!(
+        "watch_monitor_exchange_rates_age_seconds",
+        "Age of the exchange rates in use"
+    )
+    .expect("Invalid exchange rate age metric")
+});
+
+pub static DB_OPERATION_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "watch_monitor_db_operation_duration_seconds",
+        "Latency of database operations",
+        &["operation"],
+        DB_BUCKETS.to_vec()
+    )
+    .expect("Invalid database latency metric")
+});
+
+/// Register every metric up front so that all of them are exported from
+/// the first scrape on, not only once something happened
+pub fn init() {
+    Lazy::force(&SCRAPE_DURATION);
+    Lazy::force(&HTTP_REQUESTS);
+    Lazy::force(&HTTP_RETRIES);
+    Lazy::force(&LISTINGS_FOUND);
+    Lazy::force(&NEW_LISTINGS);
+    Lazy::force(&NOTIFICATIONS);
+    Lazy::force(&DISCORD_RATE_LIMITS);
+    Lazy::force(&DISCORD_QUEUE_LENGTH);
+    Lazy::force(&EXCHANGE_RATES_FETCHED_AT);
+    Lazy::force(&EXCHANGE_RATES_AGE);
+    Lazy::force(&DB_OPERATION_DURATION);
+}
+
+/// Count an HTTP request to `url`; `status` is `None` when no response came back
+pub fn record_http_request(url: &str, status: Option<u16>) {
+    let status = status.map_or_else(|| "error".to_string(), |status| status.to_string());
+    HTTP_REQUESTS.with_label_values(&[&host(url), &status]).inc();
+}
+
+pub fn record_http_retry(url: &str) {
+    HTTP_RETRIES.with_label_values(&[&host(url)]).inc();
+}
+
+pub fn record_notification(notifier: &str, kind: &str, sent: bool) {
+    let outcome = if sent { "sent" } else { "failed" };
+    NOTIFICATIONS.with_label_values(&[notifier, kind, outcome]).inc();
+}
+
+pub fn record_exchange_rates(fetched_at: DateTime<Utc>) {
+    EXCHANGE_RATES_FETCHED_AT.set(fetched_at.timestamp() as f64);
+}
+
+/// Time a database operation until the returned timer is dropped
+pub fn time_db(operation: &str) -> HistogramTimer {
+    DB_OPERATION_DURATION.with_label_values(&[operation]).start_timer()
+}
+
+/// All metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    let fetched_at = EXCHANGE_RATES_FETCHED_AT.get();
+    if fetched_at > 0.0 {
+        EXCHANGE_RATES_AGE.set(Utc::now().timestamp() as f64 - fetched_at);
+    }
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .expect("Metrics are always encodable as text");
+    String::from_utf8(buffer).expect("Metrics text is always UTF-8")
+}
+
+/// Host label for a URL, so that label values stay bounded
+fn host(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_string()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_registered_metrics() {
+        init();
+        record_http_request("https://www.grimmeissen.de/de/uhren?page=2", Some(503));
+        record_http_request("not a url", None);
+
+        let rendered = render();
+        assert!(rendered.contains(r#"watch_monitor_http_requests_total{host="www.grimmeissen.de",status="503"} 1"#));
+        assert!(rendered.contains(r#"watch_monitor_http_requests_total{host="unknown",status="error"} 1"#));
+        assert!(rendered.contains("# TYPE watch_monitor_discord_rate_limits_total counter"));
+    }
+}
@@ -1,6 +1,8 @@
+pub mod money;
 pub mod site;
 pub mod watch;
 
+pub use money::*;
 pub use site::*;
 pub use watch::*;
 
@@ -14,6 +16,11 @@ pub const EMOJI_CONDITION: &str = "⭐";
 pub const EMOJI_MATERIAL: &str = "🔩";
 pub const EMOJI_DIAMETER: &str = "📏";
 pub const EMOJI_SEARCH: &str = "🔍";
+pub const EMOJI_PRICE_DROP: &str = "📉";
+pub const EMOJI_SOLD: &str = "🔴";
+pub const EMOJI_TIME: &str = "⏱️";
+pub const EMOJI_WARNING: &str = "⚠️";
 pub const EMOJI_CHECK: &str = "✅";
+#[allow(dead_code)]
 pub const EMOJI_CROSS: &str = "❌";
 pub const EMOJI_QUESTION: &str = "❓";
\ No newline at end of file
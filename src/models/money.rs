@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::EMOJI_QUESTION;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    Eur,
+    Usd,
+    Gbp,
+    Chf,
+    Jpy,
+    Hkd,
+}
+
+impl Currency {
+    pub fn all() -> &'static [Currency] {
+        &[
+            Currency::Eur,
+            Currency::Usd,
+            Currency::Gbp,
+            Currency::Chf,
+            Currency::Jpy,
+            Currency::Hkd,
+        ]
+    }
+
+    /// ISO 4217 code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Eur => "EUR",
+            Currency::Usd => "USD",
+            Currency::Gbp => "GBP",
+            Currency::Chf => "CHF",
+            Currency::Jpy => "JPY",
+            Currency::Hkd => "HKD",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_uppercase().as_str() {
+            "EUR" => Some(Currency::Eur),
+            "USD" => Some(Currency::Usd),
+            "GBP" => Some(Currency::Gbp),
+            "CHF" => Some(Currency::Chf),
+            "JPY" => Some(Currency::Jpy),
+            "HKD" => Some(Currency::Hkd),
+            _ => None,
+        }
+    }
+
+    /// Digits after the decimal separator in the minor unit
+    pub fn minor_digits(&self) -> u32 {
+        match self {
+            Currency::Jpy => 0,
+            _ => 2,
+        }
+    }
+
+    /// How amounts are written where the currency is used:
+    /// (prefix, suffix, thousands separator, decimal separator)
+    fn format(&self) -> (&'static str, &'static str, char, char) {
+        match self {
+            Currency::Eur => ("", " €", '.', ','),
+            Currency::Usd => ("$", "", ',', '.'),
+            Currency::Gbp => ("£", "", ',', '.'),
+            Currency::Chf => ("CHF ", "", '\'', '.'),
+            Currency::Jpy => ("¥", "", ',', '.'),
+            Currency::Hkd => ("HK$", "", ',', '.'),
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// An amount of money in the currency's minor unit (e.g. cents)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Money {
+    pub amount_minor: i64,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount_minor: i64, currency: Currency) -> Self {
+        Self { amount_minor, currency }
+    }
+
+    /// Round an amount in major units (e.g. euros) to the minor unit
+    pub fn from_major(amount: f64, currency: Currency) -> Self {
+        let scale = 10f64.powi(currency.minor_digits() as i32);
+        Self::new((amount * scale).round() as i64, currency)
+    }
+
+    pub fn to_major(self) -> f64 {
+        self.amount_minor as f64 / 10f64.powi(self.currency.minor_digits() as i32)
+    }
+
+    /// Convert at `rate` units of `to` per unit of this currency
+    pub fn convert(&self, rate: f64, to: Currency) -> Money {
+        Money::from_major(self.to_major() * rate, to)
+    }
+
+    /// Whole units and, if non-zero, the zero-padded minor part
+    fn split(&self) -> (i64, Option<String>) {
+        let digits = self.currency.minor_digits();
+        let scale = 10i64.pow(digits);
+        let fraction = self.amount_minor.abs() % scale;
+
+        let fraction = if fraction == 0 {
+            None
+        } else {
+            Some(format!("{:0width$}", fraction, width = digits as usize))
+        };
+        (self.amount_minor / scale, fraction)
+    }
+}
+
+/// Formats the amount the way the currency is written locally, e.g.
+/// `12.500 €`, `1.234,56 €`, `$12,500`, `CHF 12'500.50`. Minor units are
+/// only shown when non-zero.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (prefix, suffix, thousands, decimal) = self.currency.format();
+        let (whole, fraction) = self.split();
+
+        let digits = whole.abs().to_string();
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                grouped.push(thousands);
+            }
+            grouped.push(digit);
+        }
+
+        let sign = if self.amount_minor < 0 { "-" } else { "" };
+        write!(f, "{}{}{}", sign, prefix, grouped)?;
+        if let Some(fraction) = fraction {
+            write!(f, "{}{}", decimal, fraction)?;
+        }
+        write!(f, "{}", suffix)
+    }
+}
+
+/// EUR price for display, followed by the dealer's own price when it is in
+/// another currency, e.g. `11.500 € ($12,500)`
+pub fn format_price(price: Option<Money>, price_eur: Option<Money>) -> String {
+    match (price_eur, price) {
+        (Some(eur), Some(native)) if native.currency != eur.currency => format!("{} ({})", eur, native),
+        (Some(eur), _) => eur.to_string(),
+        (None, Some(native)) => native.to_string(),
+        (None, None) => EMOJI_QUESTION.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_amounts_the_local_way() {
+        let cases = [
+            (Money::new(1250000, Currency::Eur), "12.500 €"),
+            (Money::new(123456, Currency::Eur), "1.234,56 €"),
+            (Money::new(1250050, Currency::Chf), "CHF 12'500.50"),
+            (Money::new(1250000, Currency::Usd), "$12,500"),
+            (Money::new(99, Currency::Usd), "$0.99"),
+            (Money::new(150000000, Currency::Jpy), "¥150,000,000"),
+            (Money::new(-50000, Currency::Eur), "-500 €"),
+        ];
+
+        for (money, expected) in cases {
+            assert_eq!(money.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn shows_the_native_price_next_to_euros() {
+        let eur = Money::new(1150000, Currency::Eur);
+        let usd = Money::new(1250000, Currency::Usd);
+
+        assert_eq!(format_price(Some(usd), Some(eur)), "11.500 € ($12,500)");
+        assert_eq!(format_price(Some(eur), Some(eur)), "11.500 €");
+        assert_eq!(format_price(Some(usd), None), "$12,500");
+    }
+}
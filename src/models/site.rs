@@ -11,6 +11,17 @@ pub enum Site {
 }
 
 impl Site {
+    pub fn all() -> &'static [Site] {
+        &[
+            Site::WorldOfTime,
+            Site::Grimmeissen,
+            Site::TropicalWatch,
+            Site::JuwelierExchange,
+            Site::WatchOut,
+            Site::Rueschenbeck,
+        ]
+    }
+    
     pub fn key(&self) -> &'static str {
         match self {
             Site::WorldOfTime => "worldoftime",
@@ -1,4 +1,4 @@
-use super::EMOJI_QUESTION;
+use super::{format_price, Money, EMOJI_QUESTION};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -7,17 +7,9 @@ use std::fmt;
 pub struct WatchId(pub String);
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct Price(pub String);
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct Reference(pub String);
 
-impl fmt::Display for Price {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
 impl fmt::Display for Reference {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -31,6 +23,24 @@ pub enum BoxStatus {
     Unknown,
 }
 
+impl BoxStatus {
+    pub fn key(&self) -> &'static str {
+        match self {
+            BoxStatus::Yes => "yes",
+            BoxStatus::No => "no",
+            BoxStatus::Unknown => "unknown",
+        }
+    }
+    
+    pub fn from_key(key: &str) -> Self {
+        match key {
+            "yes" => BoxStatus::Yes,
+            "no" => BoxStatus::No,
+            _ => BoxStatus::Unknown,
+        }
+    }
+}
+
 impl fmt::Display for BoxStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -48,6 +58,24 @@ pub enum PapersStatus {
     Unknown,
 }
 
+impl PapersStatus {
+    pub fn key(&self) -> &'static str {
+        match self {
+            PapersStatus::Yes => "yes",
+            PapersStatus::No => "no",
+            PapersStatus::Unknown => "unknown",
+        }
+    }
+    
+    pub fn from_key(key: &str) -> Self {
+        match key {
+            "yes" => PapersStatus::Yes,
+            "no" => PapersStatus::No,
+            _ => PapersStatus::Unknown,
+        }
+    }
+}
+
 impl fmt::Display for PapersStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -59,6 +87,7 @@ impl fmt::Display for PapersStatus {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub enum Condition {
     Excellent,
     VeryGood,
@@ -79,15 +108,60 @@ impl fmt::Display for Condition {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Where a tracked listing is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListingStatus {
+    Active,
+    /// The dealer marked the watch as sold
+    Sold,
+    /// The listing disappeared from the dealer's page
+    Removed,
+}
+
+impl ListingStatus {
+    pub fn key(&self) -> &'static str {
+        match self {
+            ListingStatus::Active => "active",
+            ListingStatus::Sold => "sold",
+            ListingStatus::Removed => "removed",
+        }
+    }
+    
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "active" => Some(ListingStatus::Active),
+            "sold" => Some(ListingStatus::Sold),
+            "removed" => Some(ListingStatus::Removed),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ListingStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+/// Listing details a dealer may leave out (shown as ❓), by the names used
+/// in completeness metrics
+pub const DETAIL_FIELDS: [&str; 8] = ["brand", "reference", "year", "condition", "material", "diameter", "box", "papers"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WatchListing {
     pub brand: String,
     pub model: String,
     pub reference: String,
     pub year: String,
-    pub price_eur_display: String,
-    pub price_eur_raw_for_hash: String,
-    pub price_usd_raw_for_hash: Option<String>, // For TropicalWatch
+    /// Asking price in the dealer's currency
+    pub price: Option<Money>,
+    /// Asking price converted to EUR
+    pub price_eur: Option<Money>,
+    /// Price part of the composite id, taken from the price text the way
+    /// earlier releases did (see `legacy_price_key`)
+    #[serde(skip)]
+    pub legacy_price_key: String,
     pub papers_status: PapersStatus,
     pub box_status: BoxStatus,
     pub condition_display: String,
@@ -97,6 +171,9 @@ pub struct WatchListing {
     pub watch_url: String,
     pub image_url: String,
     pub site_name: String,
+    /// The dealer still lists the watch but marks it as sold
+    #[serde(default)]
+    pub sold: bool,
 }
 
 impl Default for WatchListing {
@@ -106,9 +183,9 @@ impl Default for WatchListing {
             model: EMOJI_QUESTION.to_string(),
             reference: EMOJI_QUESTION.to_string(),
             year: EMOJI_QUESTION.to_string(),
-            price_eur_display: EMOJI_QUESTION.to_string(),
-            price_eur_raw_for_hash: String::new(),
-            price_usd_raw_for_hash: None,
+            price: None,
+            price_eur: None,
+            legacy_price_key: String::new(),
             papers_status: PapersStatus::Unknown,
             box_status: BoxStatus::Unknown,
             condition_display: EMOJI_QUESTION.to_string(),
@@ -118,11 +195,43 @@ impl Default for WatchListing {
             watch_url: String::new(),
             image_url: String::new(),
             site_name: String::new(),
+            sold: false,
         }
     }
 }
 
 impl WatchListing {
+    /// Price-independent identity of a listing, derived from its normalized URL
+    /// so that a price change keeps the same id. Falls back to the composite
+    /// id for listings without a usable URL.
+    pub fn generate_listing_id(&self) -> WatchId {
+        match normalize_listing_url(&self.watch_url) {
+            Some(normalized) => WatchId(format!("{:x}", md5::compute(normalized.as_bytes()))),
+            None => self.generate_composite_id(),
+        }
+    }
+    
+    /// Whether each of `DETAIL_FIELDS` was found, in the same order
+    pub fn known_details(&self) -> [bool; DETAIL_FIELDS.len()] {
+        let known = |value: &str| !value.trim().is_empty() && value != EMOJI_QUESTION;
+        
+        [
+            known(&self.brand),
+            known(&self.reference),
+            known(&self.year),
+            known(&self.condition_display),
+            known(&self.case_material),
+            known(&self.diameter),
+            self.box_status != BoxStatus::Unknown,
+            self.papers_status != PapersStatus::Unknown,
+        ]
+    }
+    
+    /// Price for display, see `format_price`
+    pub fn price_display(&self) -> String {
+        format_price(self.price, self.price_eur)
+    }
+    
     pub fn generate_composite_id(&self) -> WatchId {
         use md5::Context;
         
@@ -131,11 +240,7 @@ impl WatchListing {
         let ref_norm = self.reference.to_lowercase().replace(' ', "");
         let year_norm = self.year.to_lowercase().trim().to_string();
         
-        let price_for_hash = if let Some(usd_price) = &self.price_usd_raw_for_hash {
-            usd_price.clone()
-        } else {
-            self.price_eur_raw_for_hash.clone()
-        };
+        let price_for_hash = self.legacy_price_key.clone();
         
         let case_material_norm = if self.case_material != EMOJI_QUESTION {
             self.case_material.to_lowercase().trim().to_string()
@@ -143,7 +248,7 @@ impl WatchListing {
             String::new()
         };
         
-        let components = vec![
+        let components = [
             brand_norm.clone(),
             model_norm.clone(),
             ref_norm.clone(),
@@ -191,4 +296,73 @@ impl WatchListing {
         
         WatchId(format!("{:x}", result))
     }
-}
\ No newline at end of file
+}
+
+/// Reduce a listing URL to `host/path`: no scheme, `www.`, query, fragment
+/// or trailing slash
+fn normalize_listing_url(watch_url: &str) -> Option<String> {
+    let parsed = url::Url::parse(watch_url.trim()).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.");
+    let path = parsed.path().trim_end_matches('/');
+    
+    Some(format!("{}{}", host, path).to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::legacy_price_key;
+
+    #[test]
+    fn composite_ids_match_earlier_releases() {
+        // Ids computed by the release that hashed the raw price text
+        let cases = [
+            (
+                WatchListing {
+                    brand: "Rolex".to_string(),
+                    model: "GMT-Master".to_string(),
+                    reference: "1675".to_string(),
+                    year: "1967".to_string(),
+                    legacy_price_key: legacy_price_key("$12,500"),
+                    ..Default::default()
+                },
+                "4226f9265dcb31fe7ad448d37a37507d",
+            ),
+            (
+                // Watch Out prices from Shopify's product JSON, in cents
+                WatchListing {
+                    brand: "Rolex".to_string(),
+                    model: "Explorer".to_string(),
+                    reference: "214270".to_string(),
+                    year: "2018".to_string(),
+                    case_material: "Stainless Steel".to_string(),
+                    legacy_price_key: "1290000".to_string(),
+                    ..Default::default()
+                },
+                "2ab2a7e59839abb38a26c1cd541e7d7c",
+            ),
+            (
+                WatchListing {
+                    brand: "Rolex".to_string(),
+                    model: "Daytona".to_string(),
+                    reference: "116500LN".to_string(),
+                    legacy_price_key: legacy_price_key("24.900 €"),
+                    ..Default::default()
+                },
+                "5e740c58964955f91a08872eba9259cf",
+            ),
+            (
+                WatchListing {
+                    title: "Omega Speedmaster".to_string(),
+                    legacy_price_key: legacy_price_key("6.450,00 €"),
+                    ..Default::default()
+                },
+                "ce3f771fa6e509a7e8d106db293df703",
+            ),
+        ];
+
+        for (listing, expected) in cases {
+            assert_eq!(listing.generate_composite_id(), WatchId(expected.to_string()), "{}", expected);
+        }
+    }
+}
@@ -0,0 +1,533 @@
+use anyhow::Result;
+use chrono::Local;
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Notify};
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+use crate::config::Config;
+use crate::health::{FieldCompleteness, HealthStatus, ScrapeOutcome};
+use crate::metrics;
+use crate::models::Site;
+use crate::notify::{Dispatcher, Notification, NotifierConfig};
+use crate::scrapers::WatchScraper;
+use crate::shutdown;
+use crate::storage::{PriceObservation, Storage};
+use crate::utils::exchange_rate::ExchangeRateClient;
+use crate::utils::http::HttpClient;
+
+/// Requests to check a site right away, one per site and kept across
+/// configuration reloads
+#[derive(Clone)]
+pub struct ScrapeTriggers(Arc<HashMap<Site, Arc<Notify>>>);
+
+impl Default for ScrapeTriggers {
+    fn default() -> Self {
+        Self(Arc::new(
+            Site::all().iter().map(|site| (site.clone(), Arc::new(Notify::new()))).collect(),
+        ))
+    }
+}
+
+impl ScrapeTriggers {
+    /// Check `site` now, or as soon as a check in progress completes
+    pub fn trigger(&self, site: &Site) {
+        self.0[site].notify_one();
+    }
+
+    pub fn get(&self, site: &Site) -> Arc<Notify> {
+        self.0[site].clone()
+    }
+}
+
+/// What decides when a site is checked next
+pub struct SiteSignals {
+    /// Set to stop polling once the current check completes
+    pub stop: watch::Receiver<bool>,
+    /// Notified to check the site right away, even outside its active hours
+    pub check_now: Arc<Notify>,
+    /// When the site was last checked before its task was restarted; the
+    /// first check then waits out the rest of the interval
+    pub last_check: Option<Instant>,
+}
+
+/// Poll a single site on its own interval, jitter and active hours until
+/// `stop` is set. A check that is already running is always completed.
+/// Returns when the site was last checked, to resume its schedule from.
+pub async fn run_site(
+    scraper: Arc<dyn WatchScraper>,
+    config: Arc<Config>,
+    client: Arc<HttpClient>,
+    storage: Arc<dyn Storage>,
+    exchange_rates: Arc<ExchangeRateClient>,
+    dispatcher: Dispatcher,
+    mut signals: SiteSignals,
+) -> Option<Instant> {
+    let site_config = scraper.site_config();
+    let interval = config.poll_interval(site_config);
+
+    info!(
+        "Scheduling {} every {} seconds (jitter up to {} seconds{})",
+        site_config.name,
+        interval.as_secs(),
+        site_config.jitter_seconds,
+        site_config
+            .active_hours
+            .as_ref()
+            .map(|hours| format!(", active {}", hours))
+            .unwrap_or_default()
+    );
+
+    let mut last_check = signals.last_check;
+    let mut triggered = false;
+
+    // A restarted site carries on with its schedule instead of checking at once
+    if let Some(last_check) = last_check {
+        let delay = next_delay(interval, site_config.jitter_seconds, last_check.elapsed());
+        if !delay.is_zero() {
+            info!("{} resumes its schedule, next check in {} seconds", site_config.name, delay.as_secs());
+            triggered = wait_for_next_check(delay, &mut signals).await;
+        }
+    }
+
+    while !*signals.stop.borrow() && !shutdown::is_requested() {
+        if triggered {
+            info!("Checking {} now as requested", site_config.name);
+        }
+        if let Some(hours) = site_config.active_hours.as_ref().filter(|_| !triggered) {
+            let wait = hours.until_open(Local::now().time());
+            if !wait.is_zero() {
+                info!(
+                    "{} is outside its active hours ({}), next check in {} minutes",
+                    site_config.name,
+                    hours,
+                    wait.as_secs() / 60
+                );
+                triggered = wait_for_next_check(wait, &mut signals).await;
+                continue;
+            }
+        }
+
+        let started = Instant::now();
+        last_check = Some(started);
+
+        if let Err(e) = check_site(scraper.as_ref(), &config, &client, storage.as_ref(), &exchange_rates, &dispatcher, true).await {
+            error!("Error in scraping task: {}", e);
+        }
+        if shutdown::is_requested() {
+            break;
+        }
+
+        let delay = next_delay(interval, site_config.jitter_seconds, started.elapsed());
+        info!("{} check completed, next check in {} seconds", site_config.name, delay.as_secs());
+        triggered = wait_for_next_check(delay, &mut signals).await;
+    }
+
+    info!("Stopped polling {}", site_config.name);
+    last_check
+}
+
+/// Sleep for `duration`, waking up early when the task is asked to stop,
+/// the process shuts down or a check is requested. Returns whether a check
+/// was requested.
+async fn wait_for_next_check(duration: Duration, signals: &mut SiteSignals) -> bool {
+    tokio::select! {
+        _ = sleep(duration) => false,
+        _ = signals.stop.wait_for(|stopped| *stopped) => false,
+        _ = shutdown::requested() => false,
+        _ = signals.check_now.notified() => true,
+    }
+}
+
+/// Scrape one site, notify about every listing not seen before and track
+/// which listings left the market. With `notify` unset everything is
+/// recorded the same way but no notifications are sent.
+pub async fn check_site(
+    scraper: &dyn WatchScraper,
+    config: &Config,
+    client: &HttpClient,
+    storage: &dyn Storage,
+    exchange_rates: &ExchangeRateClient,
+    dispatcher: &Dispatcher,
+    notify: bool,
+) -> Result<()> {
+    let site_name = scraper.site_config().name.clone();
+    info!(
+        "--- Checking {} at {} ---",
+        site_name.to_uppercase(),
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    );
+
+    let site_key = scraper.site_key().key();
+    let started = Instant::now();
+    // Nothing was sent yet, so an interrupted scrape loses nothing
+    let scraped = tokio::select! {
+        scraped = scraper.scrape(client) => scraped,
+        _ = shutdown::requested() => {
+            info!("Cancelled the check of {} for shutdown", site_name);
+            return Ok(());
+        }
+    };
+    metrics::SCRAPE_DURATION
+        .with_label_values(&[site_key, if scraped.is_ok() { "success" } else { "failure" }])
+        .observe(started.elapsed().as_secs_f64());
+
+    let mut listings = match scraped {
+        Ok(listings) => listings,
+        Err(e) => {
+            error!("CRITICAL UNHANDLED ERROR in {} scraper: {}", site_name, e);
+            track_health(scraper, config, storage, ScrapeOutcome::Failed(&e), dispatcher, notify).await?;
+            return Ok(());
+        }
+    };
+
+    track_health(scraper, config, storage, ScrapeOutcome::Scraped(&listings), dispatcher, notify).await?;
+
+    info!("Found {} watch items on {}", listings.len(), site_name);
+    metrics::LISTINGS_FOUND.with_label_values(&[site_key]).set(listings.len() as f64);
+
+    exchange_rates.convert_listing_prices(client, &mut listings).await;
+
+    let site = scraper.site_key();
+    let site_config = scraper.site_config();
+
+    let completeness = FieldCompleteness::from_listings(&listings);
+    if let Some(completeness) = &completeness {
+        storage.record_completeness(&site, completeness).await?;
+    }
+    let mut new_items = 0;
+    let mut price_drops = 0;
+    let mut present = Vec::new();
+    let mut ended = Vec::new();
+
+    let total = listings.len();
+    let mut interrupted = false;
+
+    for (index, listing) in listings.into_iter().enumerate() {
+        // Stop between listings, never between a notification and marking
+        // the listing as seen
+        if shutdown::is_requested() {
+            info!("Shutting down, leaving {} of {} listings on {} for the next check", total - index, total, site_name);
+            interrupted = true;
+            break;
+        }
+
+        let listing_id = listing.generate_listing_id();
+
+        if listing.sold {
+            if let Some(sold) = storage.mark_sold(&site, &listing_id).await? {
+                ended.push(sold);
+            }
+            continue;
+        }
+        storage.touch_listing(&site, &listing_id, &listing).await?;
+        present.push(listing_id.clone());
+
+        // Listings recorded before price-independent ids existed are only
+        // known by their composite id; adopt them without notifying
+        let seen = storage.has_seen(&site, &listing_id).await?;
+        let seen_legacy = !seen && storage.has_seen(&site, &listing.generate_composite_id()).await?;
+        // Already announced, waiting for the Discord outbox to deliver it
+        let announced = !seen && notify && storage.has_pending_outbox(&site, &listing_id).await?;
+
+        let price = PriceObservation::from_listing(&listing);
+        let previous_price = match &price {
+            Some(price) => storage.record_price(&site, &listing_id, price).await?,
+            None => None,
+        };
+
+        if seen_legacy {
+            storage.mark_seen(&site, &listing_id).await?;
+        } else if !seen && !notify {
+            storage.mark_seen(&site, &listing_id).await?;
+            new_items += 1;
+        } else if announced {
+            info!("{} on {} is still queued for delivery", listing.title, site_name);
+        } else if !seen {
+            dispatcher
+                .send(&site_config.targets(), &Notification::Listing { site: &site, listing: &listing, rule: None }, site_config)
+                .await;
+
+            for rule in config.rules.iter().filter(|rule| rule.matches(&site, &listing)) {
+                info!("{} on {} matches watchlist rule {}", listing.title, site_name, rule.name);
+                let notification = Notification::Listing { site: &site, listing: &listing, rule: Some(rule) };
+                dispatcher.send(&rule.targets(), &notification, site_config).await;
+            }
+
+            // Queued Discord messages mark the listing as seen once they
+            // are all delivered
+            if !storage.has_pending_outbox(&site, &listing_id).await? {
+                storage.mark_seen(&site, &listing_id).await?;
+            }
+            new_items += 1;
+        } else if let (Some(old_price), Some(new_price)) = (previous_price, price) {
+            if is_price_drop(&old_price, &new_price) && notify {
+                let notification = Notification::PriceDrop {
+                    listing: &listing,
+                    old_price: &old_price,
+                    new_price: &new_price,
+                };
+                dispatcher.send(&site_config.targets(), &notification, site_config).await;
+                price_drops += 1;
+            } else {
+                info!(
+                    "Price of {} on {} changed from {} to {}",
+                    listing.title, site_name, old_price.display(), new_price.display()
+                );
+            }
+        }
+    }
+
+    // An empty result is more likely a broken scraper than a sold-out dealer,
+    // and an interrupted check did not see every listing
+    if site_config.track_removals && !interrupted {
+        if present.is_empty() {
+            warn!("No listings found on {}, skipping removal detection", site_name);
+        } else {
+            ended.extend(
+                storage
+                    .record_missing(&site, &present, config.removed_after_missed_scrapes)
+                    .await?,
+            );
+        }
+    }
+
+    for ended_listing in &ended {
+        info!(
+            "{} on {} is {} after {} days on the market",
+            ended_listing.listing.title,
+            site_name,
+            ended_listing.status,
+            ended_listing.time_on_market.num_days()
+        );
+
+        if let Some(webhook) = config.sold_webhook.as_ref().filter(|_| notify) {
+            let targets = [NotifierConfig::Discord { webhook: webhook.clone() }];
+            dispatcher.send(&targets, &Notification::Ended(ended_listing), site_config).await;
+        }
+    }
+
+    // Listings adopted while seeding were not new to anyone
+    if notify {
+        metrics::NEW_LISTINGS.with_label_values(&[site_key]).inc_by(new_items);
+    }
+
+    if price_drops > 0 {
+        info!("Found {} price drops on {}", price_drops, site_name);
+    }
+
+    if !notify {
+        info!("Marked {} new items on {} as seen", new_items, site_name);
+    } else if new_items == 0 {
+        info!("No new items found on {}", site_name);
+    } else {
+        info!("Found {} new items on {}", new_items, site_name);
+    }
+
+    if let Some(completeness) = completeness {
+        info!(
+            "Field completeness on {} ({} listings for sale): {}",
+            site_name,
+            completeness.listings,
+            completeness.summary()
+        );
+    }
+
+    Ok(())
+}
+
+/// Update the site's health with the outcome of a scrape and alert the
+/// admin webhook when the site breaks or recovers
+async fn track_health(
+    scraper: &dyn WatchScraper,
+    config: &Config,
+    storage: &dyn Storage,
+    outcome: ScrapeOutcome<'_>,
+    dispatcher: &Dispatcher,
+    notify: bool,
+) -> Result<()> {
+    let site = scraper.site_key();
+    let site_config = scraper.site_config();
+
+    let mut health = storage.load_site_health(&site).await?.unwrap_or_default();
+    let change = health.record(outcome, &config.health);
+    storage.save_site_health(&site, &health).await?;
+
+    match change {
+        Some(HealthStatus::Broken) => {
+            warn!("{} scraper looks broken: {}", site_config.name, health.problems.join("; "));
+        }
+        Some(HealthStatus::Healthy) => info!("{} scraper recovered", site_config.name),
+        None => {
+            if health.status == HealthStatus::Broken {
+                warn!("{} scraper still looks broken: {}", site_config.name, health.problems.join("; "));
+            }
+            return Ok(());
+        }
+    }
+
+    if let Some(webhook) = config.admin_webhook.as_ref().filter(|_| notify) {
+        let targets = [NotifierConfig::Discord { webhook: webhook.clone() }];
+        dispatcher.send(&targets, &Notification::Health(&health), site_config).await;
+    }
+
+    Ok(())
+}
+
+/// Only prices in the same currency are compared, so exchange rate moves
+/// never look like a drop
+fn is_price_drop(old_price: &PriceObservation, new_price: &PriceObservation) -> bool {
+    let (old, new) = (old_price.price, new_price.price);
+    old.currency == new.currency && new.amount_minor < old.amount_minor
+}
+
+/// Delay until the next poll: the rest of the interval plus random jitter
+fn next_delay(interval: Duration, jitter_seconds: u64, elapsed: Duration) -> Duration {
+    let jitter = if jitter_seconds > 0 {
+        Duration::from_millis(rand::thread_rng().gen_range(0..=jitter_seconds * 1000))
+    } else {
+        Duration::ZERO
+    };
+
+    interval.saturating_sub(elapsed) + jitter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SiteConfig;
+    use crate::models::WatchListing;
+    use crate::notify::Delivery;
+    use crate::storage::SqliteStorage;
+    use crate::utils::http::{create_client, HttpMode};
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::time::timeout;
+
+    /// Finds nothing and counts how often it was asked to
+    struct CountingScraper {
+        config: Arc<Config>,
+        scrapes: AtomicUsize,
+        scraped: Notify,
+    }
+
+    #[async_trait]
+    impl WatchScraper for CountingScraper {
+        async fn scrape(&self, _client: &HttpClient) -> Result<Vec<WatchListing>> {
+            self.scrapes.fetch_add(1, Ordering::SeqCst);
+            self.scraped.notify_one();
+            Ok(Vec::new())
+        }
+
+        fn site_config(&self) -> &SiteConfig {
+            &self.config.sites["watch_out"]
+        }
+
+        fn site_key(&self) -> Site {
+            Site::WatchOut
+        }
+    }
+
+    /// Run the site until `until` completes and return how often it was
+    /// scraped and what `run_site` returned
+    async fn run_until(
+        last_check: Option<Instant>,
+        until: impl FnOnce(Arc<CountingScraper>) -> tokio::task::JoinHandle<()>,
+    ) -> (usize, Option<Instant>) {
+        let config: Arc<Config> = Arc::new(
+            serde_json::from_value(json!({
+                "check_interval_seconds": 600,
+                "sites": {
+                    "watch_out": {
+                        "url": "https://www.watch-out.shop/uhren",
+                        "webhook": "https://discord.com/api/webhooks/1/token",
+                        "name": "Watch Out",
+                        "color": 0,
+                        "base_url": "https://www.watch-out.shop",
+                    }
+                },
+            }))
+            .unwrap(),
+        );
+        let storage = SqliteStorage::overlay(Path::new("/nonexistent/watch-monitor-monitor-test.db"))
+            .await
+            .unwrap();
+        storage.migrate().await.unwrap();
+        let storage: Arc<dyn Storage> = Arc::new(storage);
+        let scraper = Arc::new(CountingScraper {
+            config: config.clone(),
+            scrapes: AtomicUsize::new(0),
+            scraped: Notify::new(),
+        });
+
+        let (stop_tx, stop) = watch::channel(false);
+        let task = tokio::spawn(run_site(
+            scraper.clone(),
+            config.clone(),
+            Arc::new(create_client("watch-monitor-tests", HttpMode::Live).unwrap()),
+            storage.clone(),
+            Arc::new(ExchangeRateClient::new(&config.exchange_rate_api_url, config.exchange_rate_base, storage.clone())),
+            Dispatcher::new(Delivery::Stdout, storage).unwrap(),
+            SiteSignals {
+                stop,
+                check_now: Arc::new(Notify::new()),
+                last_check,
+            },
+        ));
+
+        until(scraper.clone()).await.unwrap();
+        stop_tx.send(true).unwrap();
+        let last_check = timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+        (scraper.scrapes.load(Ordering::SeqCst), last_check)
+    }
+
+    #[tokio::test]
+    async fn checks_a_new_site_right_away() {
+        let started = Instant::now();
+        let (scrapes, last_check) = run_until(None, |scraper| {
+            tokio::spawn(async move { scraper.scraped.notified().await })
+        })
+        .await;
+
+        assert_eq!(scrapes, 1);
+        assert!(last_check.is_some_and(|last_check| last_check >= started));
+    }
+
+    #[tokio::test]
+    async fn resumes_the_schedule_of_a_restarted_site() {
+        let previous = Instant::now();
+        let (scrapes, last_check) = run_until(Some(previous), |_| {
+            tokio::spawn(sleep(Duration::from_millis(200)))
+        })
+        .await;
+
+        assert_eq!(scrapes, 0);
+        assert_eq!(last_check, Some(previous));
+    }
+
+    #[test]
+    fn waits_out_the_rest_of_the_interval() {
+        let interval = Duration::from_secs(600);
+
+        assert_eq!(next_delay(interval, 0, Duration::ZERO), interval);
+        assert_eq!(next_delay(interval, 0, Duration::from_secs(45)), Duration::from_secs(555));
+        // A check that took longer than the interval is followed right away
+        assert_eq!(next_delay(interval, 0, Duration::from_secs(900)), Duration::ZERO);
+    }
+
+    #[test]
+    fn adds_at_most_the_configured_jitter() {
+        let interval = Duration::from_secs(600);
+        let rest = Duration::from_secs(540);
+
+        for _ in 0..100 {
+            let delay = next_delay(interval, 30, Duration::from_secs(60));
+            assert!(delay >= rest && delay <= rest + Duration::from_secs(30), "{:?}", delay);
+        }
+    }
+}
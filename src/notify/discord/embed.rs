@@ -3,11 +3,14 @@ use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde_json::{json, Value};
 
 use crate::config::SiteConfig;
+use crate::health::{HealthStatus, SiteHealth};
 use crate::models::{
-    WatchListing, EMOJI_BOX, EMOJI_CONDITION, EMOJI_DIAMETER, EMOJI_MATERIAL,
-    EMOJI_PAPERS, EMOJI_PRICE, EMOJI_QUESTION, EMOJI_REFERENCE, EMOJI_SEARCH, EMOJI_YEAR,
+    ListingStatus, WatchListing, EMOJI_BOX, EMOJI_CHECK, EMOJI_CONDITION, EMOJI_DIAMETER,
+    EMOJI_MATERIAL, EMOJI_PAPERS, EMOJI_PRICE, EMOJI_PRICE_DROP, EMOJI_QUESTION, EMOJI_REFERENCE,
+    EMOJI_SEARCH, EMOJI_SOLD, EMOJI_TIME, EMOJI_WARNING, EMOJI_YEAR,
 };
 use crate::parsers::clean_text;
+use crate::storage::{EndedListing, PriceObservation};
 
 pub fn create_embed(listing: &WatchListing, site_config: &SiteConfig) -> Value {
     // Build embed title matching Python logic
@@ -22,7 +25,7 @@ pub fn create_embed(listing: &WatchListing, site_config: &SiteConfig) -> Value {
     // Price field (always shown)
     fields.push(json!({
         "name": format!("{} Price:", EMOJI_PRICE),
-        "value": format!("**{}**", listing.price_eur_display),
+        "value": format!("**{}**", listing.price_display()),
         "inline": false
     }));
     
@@ -124,7 +127,186 @@ pub fn create_embed(listing: &WatchListing, site_config: &SiteConfig) -> Value {
     })
 }
 
-fn build_embed_title(listing: &WatchListing) -> String {
+pub fn create_price_drop_embed(
+    listing: &WatchListing,
+    site_config: &SiteConfig,
+    old_price: &PriceObservation,
+    new_price: &PriceObservation,
+) -> Value {
+    let embed_title = format!("{} Price drop: {}", EMOJI_PRICE_DROP, build_embed_title(listing));
+    let chrono_link = build_chrono24_link(listing);
+    
+    let change = format_price_change(old_price, new_price)
+        .map(|change| format!("**{}**", change))
+        .unwrap_or_else(|| EMOJI_QUESTION.to_string());
+    
+    let fields = vec![
+        json!({
+            "name": format!("{} Old Price:", EMOJI_PRICE),
+            "value": format!("~~{}~~", old_price.display()),
+            "inline": true
+        }),
+        json!({
+            "name": format!("{} New Price:", EMOJI_PRICE),
+            "value": format!("**{}**", new_price.display()),
+            "inline": true
+        }),
+        json!({
+            "name": format!("{} Change:", EMOJI_PRICE_DROP),
+            "value": change,
+            "inline": true
+        }),
+        json!({
+            "name": format!("{} Chrono24 Search:", EMOJI_SEARCH),
+            "value": format!("[**Search similar**]({})", chrono_link),
+            "inline": false
+        }),
+    ];
+    
+    json!({
+        "title": embed_title,
+        "url": listing.watch_url,
+        "color": site_config.color,
+        "thumbnail": {
+            "url": listing.image_url
+        },
+        "fields": fields,
+        "footer": {
+            "text": format!("{} - Price drop detected: {}",
+                site_config.name,
+                Local::now().format("%Y-%m-%d %H:%M:%S")
+            )
+        }
+    })
+}
+
+pub fn create_sold_embed(ended: &EndedListing, site_config: &SiteConfig) -> Value {
+    let listing = &ended.listing;
+    let status = match ended.status {
+        ListingStatus::Sold => "Sold",
+        _ => "Removed",
+    };
+    let embed_title = format!("{} {}: {}", EMOJI_SOLD, status, build_embed_title(listing));
+    
+    let fields = vec![
+        json!({
+            "name": format!("{} Last Price:", EMOJI_PRICE),
+            "value": format!("**{}**", listing.price_display()),
+            "inline": true
+        }),
+        json!({
+            "name": format!("{} Time on Market:", EMOJI_TIME),
+            "value": format!("**{}**", format_time_on_market(ended.time_on_market)),
+            "inline": true
+        }),
+        json!({
+            "name": format!("{} Chrono24 Search:", EMOJI_SEARCH),
+            "value": format!("[**Search similar**]({})", build_chrono24_link(listing)),
+            "inline": false
+        }),
+    ];
+    
+    json!({
+        "title": embed_title,
+        "url": listing.watch_url,
+        "color": site_config.color,
+        "thumbnail": {
+            "url": listing.image_url
+        },
+        "fields": fields,
+        "footer": {
+            "text": format!("{} - {}: {}",
+                site_config.name,
+                status,
+                Local::now().format("%Y-%m-%d %H:%M:%S")
+            )
+        }
+    })
+}
+
+/// Alert that a site's scraper broke or recovered
+pub fn create_health_embed(health: &SiteHealth, site_config: &SiteConfig) -> Value {
+    let (title, color, description) = match health.status {
+        HealthStatus::Broken => (
+            format!("{} {} scraper looks broken", EMOJI_WARNING, site_config.name),
+            0xE74C3C,
+            health.problems.iter().map(|problem| format!("- {}", problem)).collect::<Vec<_>>().join("\n"),
+        ),
+        HealthStatus::Healthy => (
+            format!("{} {} scraper recovered", EMOJI_CHECK, site_config.name),
+            0x2ECC71,
+            "Listings are being found again.".to_string(),
+        ),
+    };
+    
+    let or_unknown = |value: Option<String>| value.unwrap_or_else(|| EMOJI_QUESTION.to_string());
+    let mut fields = vec![
+        json!({
+            "name": "Listings Found:",
+            "value": or_unknown(health.last_items.map(|items| items.to_string())),
+            "inline": true
+        }),
+        json!({
+            "name": "Average:",
+            "value": or_unknown(health.average_items.map(|average| format!("{:.0}", average))),
+            "inline": true
+        }),
+        json!({
+            "name": "Unknown Details:",
+            "value": or_unknown(health.unknown_share.map(|share| format!("{:.0}%", share * 100.0))),
+            "inline": true
+        }),
+    ];
+    
+    if let Some(error) = &health.last_error {
+        fields.push(json!({
+            "name": format!("Last Error ({} consecutive failures):", health.consecutive_failures),
+            "value": format!("```{}```", error.chars().take(1000).collect::<String>()),
+            "inline": false
+        }));
+    }
+    
+    json!({
+        "title": title,
+        "url": site_config.url,
+        "description": description,
+        "color": color,
+        "fields": fields,
+        "footer": {
+            "text": format!("{} - Health check: {}",
+                site_config.name,
+                Local::now().format("%Y-%m-%d %H:%M:%S")
+            )
+        }
+    })
+}
+
+/// Relative change such as "-4.2%", if both prices are in the same currency
+pub fn format_price_change(old_price: &PriceObservation, new_price: &PriceObservation) -> Option<String> {
+    let (old, new) = (old_price.price, new_price.price);
+    if old.currency != new.currency || old.amount_minor <= 0 {
+        return None;
+    }
+    
+    let change = (new.amount_minor - old.amount_minor) as f64 / old.amount_minor as f64;
+    Some(format!("{:+.1}%", change * 100.0))
+}
+
+/// Human-readable duration such as "12 days, 3 hours"
+pub fn format_time_on_market(duration: chrono::Duration) -> String {
+    let plural = |n: i64, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    let (days, hours) = (duration.num_days(), duration.num_hours() % 24);
+    
+    match (days, hours) {
+        (0, 0) => "less than an hour".to_string(),
+        (0, hours) => plural(hours, "hour"),
+        (days, 0) => plural(days, "day"),
+        (days, hours) => format!("{}, {}", plural(days, "day"), plural(hours, "hour")),
+    }
+}
+
+/// Title naming brand, model and reference without repeating any of them
+pub fn build_embed_title(listing: &WatchListing) -> String {
     let brand = clean_text(&listing.brand);
     let model = clean_text(&listing.model);
     let reference = clean_text(&listing.reference).replace(EMOJI_QUESTION, "");
@@ -214,7 +396,8 @@ fn build_embed_title(listing: &WatchListing) -> String {
     embed_title
 }
 
-fn build_chrono24_link(listing: &WatchListing) -> String {
+/// Chrono24 search for similar watches
+pub fn build_chrono24_link(listing: &WatchListing) -> String {
     let brand = if listing.brand != EMOJI_QUESTION { &listing.brand } else { "" };
     let model = if listing.model != EMOJI_QUESTION && 
                    listing.model.to_lowercase() != brand.to_lowercase() { 
@@ -0,0 +1,111 @@
+pub mod embed;
+pub mod outbox;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::config::SiteConfig;
+use crate::notify::{Delivery, Notification, Notifier};
+use crate::storage::OutboxMessage;
+use crate::utils::secret::Secret;
+use embed::{create_embed, create_health_embed, create_price_drop_embed, create_sold_embed};
+use outbox::{webhook_key, Outbox};
+
+/// Queues embeds for a Discord webhook; the [`Outbox`] delivers them
+pub struct DiscordNotifier {
+    outbox: Outbox,
+    delivery: Delivery,
+    webhook: Secret,
+}
+
+impl DiscordNotifier {
+    pub fn new(outbox: Outbox, delivery: Delivery, webhook: Secret) -> Self {
+        Self { outbox, delivery, webhook }
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    fn records_delivery(&self) -> bool {
+        true
+    }
+
+    async fn send(&self, notification: &Notification<'_>, site_config: &SiteConfig) -> Result<()> {
+        let kind = notification.kind();
+        let payload = create_payload(notification, site_config);
+        if self.delivery.render(kind, self.name(), &self.webhook, &payload)? {
+            return Ok(());
+        }
+
+        let listing = match notification {
+            Notification::Listing { site, listing, .. } => Some(((*site).clone(), listing.generate_listing_id())),
+            _ => None,
+        };
+        let message = OutboxMessage {
+            target: webhook_key(&self.webhook),
+            kind: kind.to_string(),
+            payload,
+            listing,
+        };
+        self.outbox.push(&self.webhook, &message).await?;
+
+        match notification {
+            Notification::Listing { listing, rule: None, .. } => {
+                info!("Queued Discord notification for {}", listing.title);
+            }
+            Notification::Listing { listing, rule: Some(rule), .. } => {
+                info!("Queued Discord notification for {} (rule {})", listing.title, rule.name);
+            }
+            Notification::PriceDrop { listing, old_price, new_price } => {
+                info!(
+                    "Queued Discord price drop notification for {} ({} -> {})",
+                    listing.title, old_price.display(), new_price.display()
+                );
+            }
+            Notification::Ended(ended) => {
+                info!("Queued Discord {} notification for {}", ended.status, ended.listing.title);
+            }
+            Notification::Health(health) => {
+                info!("Queued Discord health alert for {} ({})", site_config.name, health.status);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Webhook payload for a notification. Rule matches mention the rule's
+/// role, if one is configured.
+fn create_payload(notification: &Notification<'_>, site_config: &SiteConfig) -> Value {
+    let embed = match notification {
+        Notification::Listing { listing, rule: Some(rule), .. } => {
+            let mut payload = json!({
+                "content": format!("Watchlist match: **{}**", rule.name),
+                "embeds": [create_embed(listing, site_config)],
+                "allowed_mentions": { "parse": [] }
+            });
+
+            if let (Some(mention), Some(role)) = (rule.mention(), &rule.mention_role) {
+                payload["content"] = json!(format!("{} Watchlist match: **{}**", mention, rule.name));
+                payload["allowed_mentions"] = json!({ "roles": [role] });
+            }
+
+            return payload;
+        }
+        Notification::Listing { listing, rule: None, .. } => create_embed(listing, site_config),
+        Notification::PriceDrop { listing, old_price, new_price } => {
+            create_price_drop_embed(listing, site_config, old_price, new_price)
+        }
+        Notification::Ended(ended) => create_sold_embed(ended, site_config),
+        Notification::Health(health) => create_health_embed(health, site_config),
+    };
+
+    json!({
+        "embeds": [embed]
+    })
+}
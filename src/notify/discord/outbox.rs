@@ -0,0 +1,565 @@
+use anyhow::{Context, Result};
+use reqwest::header::HeaderMap;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::{sleep, sleep_until, Instant};
+use tracing::{error, info, warn};
+
+use crate::metrics;
+use crate::shutdown;
+use crate::storage::{OutboxMessage, QueuedOutboxMessage, Storage};
+use crate::utils::secret::Secret;
+
+/// Messages fetched from the queue per pass
+const BATCH_SIZE: u32 = 50;
+
+/// How often the worker looks for messages whose retry came due
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Delay after the first failed attempt, doubled for every further one
+const INITIAL_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(3600);
+
+/// Failed attempts before a message is given up on, about half a day
+const MAX_ATTEMPTS: u32 = 20;
+
+/// Discord webhook posts waiting for delivery, persisted so that an outage
+/// or a restart loses nothing. Notifiers queue messages; one worker
+/// delivers them in order, honoring Discord's rate limits per webhook and
+/// backing off exponentially when a webhook fails.
+///
+/// Queued messages name their webhook only by its key, as the URL contains
+/// the webhook's token; the outbox resolves keys through the webhooks it was
+/// told about.
+#[derive(Clone)]
+pub struct Outbox {
+    client: Client,
+    storage: Arc<dyn Storage>,
+    wake: Arc<Notify>,
+    webhooks: Arc<RwLock<HashMap<String, Secret>>>,
+}
+
+/// Why a webhook is not posted to for now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockReason {
+    RateLimit,
+    Backoff,
+}
+
+/// When each webhook (by key), or every webhook for a global rate limit,
+/// may be posted to again. Kept in memory: after a restart, Discord's next
+/// response tells us again.
+#[derive(Default)]
+struct Limits {
+    webhooks: HashMap<String, (Instant, BlockReason)>,
+    global: Option<Instant>,
+}
+
+/// What a pass over the due messages achieved
+#[derive(Default)]
+struct Pass {
+    delivered: usize,
+    /// Earliest end of a rate limit holding back a due message
+    rate_limited_until: Option<Instant>,
+}
+
+enum PostOutcome {
+    Delivered,
+    RateLimited { retry_after: Duration, global: bool },
+    /// `retry` is unset for errors that repeating the request cannot fix
+    Failed { error: String, retry: bool },
+}
+
+/// Body of a 429 response
+#[derive(Deserialize)]
+struct RateLimitBody {
+    retry_after: Option<f64>,
+    #[serde(default)]
+    global: bool,
+}
+
+impl Outbox {
+    pub fn new(client: Client, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            client,
+            storage,
+            wake: Arc::new(Notify::new()),
+            webhooks: Arc::default(),
+        }
+    }
+
+    /// Replace the webhooks queued messages can be delivered to. Messages
+    /// for any other webhook are given up on.
+    pub fn set_webhooks<'a>(&self, webhooks: impl IntoIterator<Item = &'a Secret>) {
+        let webhooks = webhooks.into_iter().map(|webhook| (webhook_key(webhook), webhook.clone())).collect();
+        *self.webhooks.write().unwrap() = webhooks;
+    }
+
+    /// Queue a message for `webhook`, whose key is the message's `target`,
+    /// and wake the worker
+    pub async fn push(&self, webhook: &Secret, message: &OutboxMessage) -> Result<()> {
+        self.webhooks
+            .write()
+            .unwrap()
+            .insert(message.target.clone(), webhook.clone());
+        self.storage.queue_outbox_message(message).await?;
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Deliver messages as they are queued or their retry comes due, until
+    /// shutdown. The current pass is finished first.
+    pub async fn run(self) {
+        let mut limits = Limits::default();
+
+        loop {
+            let pass = match self.deliver_due(&mut limits).await {
+                Ok(pass) => pass,
+                Err(e) => {
+                    error!("Failed to process the Discord delivery queue: {:#}", e);
+                    Pass::default()
+                }
+            };
+            if shutdown::is_requested() {
+                return;
+            }
+            if pass.delivered > 0 {
+                continue;
+            }
+
+            let wait = pass
+                .rate_limited_until
+                .map(|until| until.saturating_duration_since(Instant::now()).min(POLL_INTERVAL))
+                .unwrap_or(POLL_INTERVAL);
+            tokio::select! {
+                _ = sleep(wait) => {}
+                _ = self.wake.notified() => {}
+                _ = shutdown::requested() => return,
+            }
+        }
+    }
+
+    /// Deliver everything that can go out now, waiting out rate limits.
+    /// Messages backing off after a failure stay queued for the next run.
+    /// Keeps going after shutdown was requested, so that it can flush what
+    /// in-flight checks queued; the caller bounds how long that may take.
+    pub async fn drain(&self) -> Result<()> {
+        let mut limits = Limits::default();
+
+        loop {
+            let pass = self.deliver_due(&mut limits).await?;
+            if pass.delivered > 0 {
+                continue;
+            }
+
+            match pass.rate_limited_until {
+                Some(until) => sleep_until(until).await,
+                None => break,
+            }
+        }
+
+        let left = self.storage.outbox_len().await?;
+        metrics::DISCORD_QUEUE_LENGTH.set(left as f64);
+        if left > 0 {
+            warn!("{} Discord messages are still queued and will be retried on the next run", left);
+        }
+        Ok(())
+    }
+
+    /// Try each due message once, in queue order, skipping webhooks that
+    /// are rate limited or backing off
+    async fn deliver_due(&self, limits: &mut Limits) -> Result<Pass> {
+        let messages = self.storage.due_outbox_messages(BATCH_SIZE).await?;
+        let mut pass = Pass::default();
+
+        for queued in messages {
+            let target = &queued.message.target;
+            if let Some((until, reason)) = limits.blocked(target) {
+                if reason == BlockReason::RateLimit {
+                    pass.wait_for(until);
+                }
+                continue;
+            }
+
+            let webhook = self.webhooks.read().unwrap().get(target).cloned();
+            let Some(webhook) = webhook else {
+                error!(
+                    "Giving up on a Discord {} notification: its webhook is no longer configured",
+                    queued.message.kind
+                );
+                metrics::record_notification("discord", &queued.message.kind, false);
+                self.storage.finish_outbox_message(queued.id).await?;
+                continue;
+            };
+
+            match post(&self.client, webhook.expose(), target, &queued.message.payload, limits).await {
+                PostOutcome::Delivered => {
+                    self.storage.finish_outbox_message(queued.id).await?;
+                    metrics::record_notification("discord", &queued.message.kind, true);
+                    info!("Delivered Discord {} notification", queued.message.kind);
+                    pass.delivered += 1;
+                }
+                PostOutcome::RateLimited { retry_after, global } => {
+                    metrics::DISCORD_RATE_LIMITS.inc();
+                    let until = Instant::now() + retry_after;
+                    if global {
+                        limits.global = Some(until);
+                    } else {
+                        limits.webhooks.insert(target.clone(), (until, BlockReason::RateLimit));
+                    }
+                    warn!(
+                        "Discord rate limited a {} notification{}, retrying in {:.1} seconds",
+                        queued.message.kind,
+                        if global { " (global)" } else { "" },
+                        retry_after.as_secs_f64()
+                    );
+                    pass.wait_for(until);
+                }
+                PostOutcome::Failed { error, retry } => {
+                    self.record_failure(&queued, webhook.expose(), &error, retry, limits).await?
+                }
+            }
+        }
+
+        metrics::DISCORD_QUEUE_LENGTH.set(self.storage.outbox_len().await? as f64);
+        Ok(pass)
+    }
+
+    /// Schedule the next attempt with exponential backoff, or give up and
+    /// drop the message. Its listing then counts as seen like a delivered
+    /// one, as announcing it again would fail the same way.
+    async fn record_failure(
+        &self,
+        queued: &QueuedOutboxMessage,
+        webhook: &str,
+        error: &str,
+        retry: bool,
+        limits: &mut Limits,
+    ) -> Result<()> {
+        let attempts = queued.attempts + 1;
+        if !retry || attempts >= MAX_ATTEMPTS {
+            error!(
+                "Giving up on a Discord {} notification to {} after {} attempts: {}",
+                queued.message.kind,
+                redact(webhook),
+                attempts,
+                error
+            );
+            metrics::record_notification("discord", &queued.message.kind, false);
+            return self.storage.finish_outbox_message(queued.id).await;
+        }
+
+        let delay = backoff(attempts);
+        warn!(
+            "Failed to deliver a Discord {} notification (attempt {}), retrying in {} seconds: {}",
+            queued.message.kind,
+            attempts,
+            delay.as_secs(),
+            error
+        );
+        let next_attempt_at = chrono::Utc::now() + chrono::Duration::from_std(delay)?;
+        self.storage.retry_outbox_message(queued.id, next_attempt_at, error).await?;
+        // Keep later messages for the webhook in order behind this one
+        limits
+            .webhooks
+            .insert(queued.message.target.clone(), (Instant::now() + delay, BlockReason::Backoff));
+
+        Ok(())
+    }
+}
+
+impl Pass {
+    fn wait_for(&mut self, until: Instant) {
+        self.rate_limited_until = Some(self.rate_limited_until.map_or(until, |earliest| earliest.min(until)));
+    }
+}
+
+impl Limits {
+    fn blocked(&mut self, target: &str) -> Option<(Instant, BlockReason)> {
+        let now = Instant::now();
+        if let Some(until) = self.global.filter(|until| *until > now) {
+            return Some((until, BlockReason::RateLimit));
+        }
+
+        match self.webhooks.get(target) {
+            Some(&(until, reason)) if until > now => Some((until, reason)),
+            Some(_) => {
+                self.webhooks.remove(target);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Hold the webhook back once its current rate limit bucket is used up
+    fn update(&mut self, target: &str, headers: &HeaderMap) {
+        let header = |name: &str| headers.get(name)?.to_str().ok()?.parse::<f64>().ok();
+
+        if let (Some(remaining), Some(reset_after)) = (header("x-ratelimit-remaining"), header("x-ratelimit-reset-after")) {
+            if remaining <= 0.0 {
+                let until = Instant::now() + seconds(reset_after);
+                self.webhooks.insert(target.to_string(), (until, BlockReason::RateLimit));
+            }
+        }
+    }
+}
+
+/// Post to the `webhook` URL, whose key is `target`
+async fn post(client: &Client, webhook: &str, target: &str, payload: &Value, limits: &mut Limits) -> PostOutcome {
+    let response = match client
+        .post(webhook)
+        .json(payload)
+        .send()
+        .await
+        // The webhook URL contains its token, keep it out of error messages
+        .map_err(|e| e.without_url())
+        .context("Failed to send Discord webhook")
+    {
+        Ok(response) => response,
+        Err(e) => return PostOutcome::Failed { error: format!("{:#}", e), retry: true },
+    };
+
+    let status = response.status();
+    let headers = response.headers().clone();
+    limits.update(target, &headers);
+
+    if status.is_success() {
+        return PostOutcome::Delivered;
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let parsed: Option<RateLimitBody> = serde_json::from_str(&body).ok();
+        let header = |name: &str| headers.get(name)?.to_str().ok()?.parse::<f64>().ok();
+        let retry_after = parsed
+            .as_ref()
+            .and_then(|body| body.retry_after)
+            .or_else(|| header("retry-after"))
+            .or_else(|| header("x-ratelimit-reset-after"))
+            .unwrap_or(1.0);
+        let global = parsed.is_some_and(|body| body.global)
+            || headers.get("x-ratelimit-global").is_some_and(|value| value == "true");
+
+        return PostOutcome::RateLimited { retry_after: seconds(retry_after), global };
+    }
+
+    let body: String = body.chars().take(200).collect();
+    PostOutcome::Failed {
+        error: format!("Discord webhook failed: {} - {}", status, body),
+        // Any other client error is permanent: 400 for a payload Discord
+        // rejects, 401/403 for a revoked token, 404 for a deleted webhook
+        retry: status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT,
+    }
+}
+
+/// Key a webhook is queued under: the SHA-256 of its URL, which identifies
+/// the webhook without revealing its token
+pub fn webhook_key(webhook: &Secret) -> String {
+    hex::encode(Sha256::digest(webhook.expose().as_bytes()))
+}
+
+/// The webhook URL without its token, which is the last path segment
+fn redact(webhook: &str) -> String {
+    match webhook.trim_end_matches('/').rsplit_once('/') {
+        Some((base, _token)) => format!("{}/<redacted>", base),
+        None => "<redacted>".to_string(),
+    }
+}
+
+/// Delay before attempt `attempts + 1`
+fn backoff(attempts: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+/// A `retry_after` or `reset_after` value, with a small margin
+fn seconds(value: f64) -> Duration {
+    Duration::from_secs_f64(value.clamp(0.0, 3600.0)) + Duration::from_millis(50)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Site, WatchId};
+    use crate::storage::SqliteStorage;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn backoff_doubles_up_to_an_hour() {
+        assert_eq!(backoff(1), Duration::from_secs(5));
+        assert_eq!(backoff(2), Duration::from_secs(10));
+        assert_eq!(backoff(5), Duration::from_secs(80));
+        assert_eq!(backoff(15), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn redacts_the_webhook_token() {
+        assert_eq!(
+            redact("https://discord.com/api/webhooks/123/s3cr3t-t0ken"),
+            "https://discord.com/api/webhooks/123/<redacted>"
+        );
+        assert_eq!(redact("s3cr3t"), "<redacted>");
+    }
+
+    async fn migrated_storage(name: &str) -> Arc<dyn Storage> {
+        let path = format!("/nonexistent/watch-monitor-{}-test.db", name);
+        let storage = SqliteStorage::overlay(std::path::Path::new(&path)).await.unwrap();
+        storage.migrate().await.unwrap();
+        Arc::new(storage)
+    }
+
+    fn webhook(server: &MockServer, path: &str) -> Secret {
+        Secret::resolve(&format!("{}/{}", server.uri(), path)).unwrap()
+    }
+
+    fn listing_message(webhook: &Secret, site: &Site, listing_id: &WatchId) -> OutboxMessage {
+        OutboxMessage {
+            target: webhook_key(webhook),
+            kind: "listing".to_string(),
+            payload: json!({ "content": "Speedmaster" }),
+            listing: Some((site.clone(), listing_id.clone())),
+        }
+    }
+
+    #[tokio::test]
+    async fn waits_out_rate_limits_and_marks_listings_seen_after_delivery() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/limited"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .set_body_json(json!({ "message": "You are being rate limited.", "retry_after": 0.2, "global": false })),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/limited"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/down"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let storage = migrated_storage("outbox").await;
+        let outbox = Outbox::new(Client::new(), storage.clone());
+
+        let (site, listing_id) = (Site::WatchOut, WatchId("speedmaster".to_string()));
+        let (limited, down) = (webhook(&server, "limited"), webhook(&server, "down"));
+        let message = listing_message(&limited, &site, &listing_id);
+        outbox.push(&limited, &message).await.unwrap();
+        outbox.push(&limited, &message).await.unwrap();
+        outbox.push(&down, &OutboxMessage { listing: None, ..listing_message(&down, &site, &listing_id) }).await.unwrap();
+        assert!(storage.has_pending_outbox(&site, &listing_id).await.unwrap());
+
+        outbox.drain().await.unwrap();
+
+        assert!(!storage.has_pending_outbox(&site, &listing_id).await.unwrap());
+        assert!(storage.has_seen(&site, &listing_id).await.unwrap());
+        // The failed message waits for its retry
+        assert_eq!(storage.outbox_len().await.unwrap(), 1);
+        assert!(storage.due_outbox_messages(BATCH_SIZE).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn drops_rejected_messages_and_marks_listings_seen() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/rejecting"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({ "message": "Invalid Form Body" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let storage = migrated_storage("outbox-rejected").await;
+        let outbox = Outbox::new(Client::new(), storage.clone());
+        let (site, listing_id) = (Site::WatchOut, WatchId("speedmaster".to_string()));
+        let rejecting = webhook(&server, "rejecting");
+        outbox.push(&rejecting, &listing_message(&rejecting, &site, &listing_id)).await.unwrap();
+
+        outbox.drain().await.unwrap();
+
+        assert_eq!(storage.outbox_len().await.unwrap(), 0);
+        assert!(!storage.has_pending_outbox(&site, &listing_id).await.unwrap());
+        // Not announced again on the next check
+        assert!(storage.has_seen(&site, &listing_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_last_attempt_and_marks_listings_seen() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/down"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let storage = migrated_storage("outbox-exhausted").await;
+        let outbox = Outbox::new(Client::new(), storage.clone());
+        let (site, listing_id) = (Site::WatchOut, WatchId("speedmaster".to_string()));
+        let down = webhook(&server, "down");
+        outbox.push(&down, &listing_message(&down, &site, &listing_id)).await.unwrap();
+
+        // Fail every attempt but the last one, keeping the message due
+        let id = storage.due_outbox_messages(BATCH_SIZE).await.unwrap()[0].id;
+        let due = chrono::Utc::now() - chrono::Duration::minutes(1);
+        for _ in 1..MAX_ATTEMPTS {
+            storage.retry_outbox_message(id, due, "503 Service Unavailable").await.unwrap();
+        }
+        assert_eq!(storage.due_outbox_messages(BATCH_SIZE).await.unwrap()[0].attempts, MAX_ATTEMPTS - 1);
+
+        outbox.drain().await.unwrap();
+
+        assert_eq!(storage.outbox_len().await.unwrap(), 0);
+        assert!(!storage.has_pending_outbox(&site, &listing_id).await.unwrap());
+        // Not announced again on the next check
+        assert!(storage.has_seen(&site, &listing_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn resolves_webhooks_by_key_after_a_restart() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/s3cr3t-t0ken"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let storage = migrated_storage("outbox-restart").await;
+        let (site, listing_id) = (Site::WatchOut, WatchId("speedmaster".to_string()));
+        let (configured, removed) = (webhook(&server, "s3cr3t-t0ken"), webhook(&server, "removed-t0ken"));
+        let outbox = Outbox::new(Client::new(), storage.clone());
+        outbox.push(&configured, &listing_message(&configured, &site, &listing_id)).await.unwrap();
+        outbox.push(&removed, &OutboxMessage { listing: None, ..listing_message(&removed, &site, &listing_id) }).await.unwrap();
+
+        // Only a key of the webhook is stored, never its token
+        for queued in storage.due_outbox_messages(BATCH_SIZE).await.unwrap() {
+            assert!(!queued.message.target.contains("t0ken"));
+        }
+
+        // A new worker knows the configured webhook only
+        let outbox = Outbox::new(Client::new(), storage.clone());
+        outbox.set_webhooks([&configured]);
+        outbox.drain().await.unwrap();
+
+        assert_eq!(storage.outbox_len().await.unwrap(), 0);
+        assert!(storage.has_seen(&site, &listing_id).await.unwrap());
+    }
+}
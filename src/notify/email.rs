@@ -0,0 +1,593 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveTime, TimeZone, Timelike, Utc};
+use html_escape::{encode_double_quoted_attribute as attr, encode_text as text};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::sleep;
+use tracing::{error, info};
+
+use crate::config::{Config, SiteConfig, TimeOfDay};
+use crate::metrics;
+use crate::models::{WatchListing, EMOJI_QUESTION};
+use crate::notify::discord::embed::{build_chrono24_link, build_embed_title, format_price_change};
+use crate::notify::{Delivery, Notification, Notifier, NotifierConfig};
+use crate::shutdown;
+use crate::storage::{DigestEntry, PriceObservation, QueuedDigestEntry, Storage};
+use crate::utils::secret::Secret;
+
+/// How often the digest worker looks for digests that are due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// An SMTP server and the recipients of one digest, e.g.
+/// `{ type = "email", smtp_host = "smtp.example.com", from = "...", to = ["..."] }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    #[serde(default)]
+    pub tls: SmtpTls,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Inline or as an `env:`/`file:` reference
+    #[serde(default)]
+    pub password: Option<Secret>,
+    /// Sender, e.g. `Watch Monitor <watches@example.com>`
+    pub from: String,
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub schedule: DigestSchedule,
+    /// Local time daily digests go out
+    #[serde(default = "default_send_at")]
+    pub send_at: TimeOfDay,
+    /// Include price drops besides new listings
+    #[serde(default)]
+    pub price_drops: bool,
+}
+
+/// How the connection to the SMTP server is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// Upgrade a plain connection with STARTTLS
+    #[default]
+    Starttls,
+    /// TLS from the start, usually on port 465
+    Tls,
+    /// Unencrypted, for a local SMTP sink
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestSchedule {
+    /// At the top of every hour
+    Hourly,
+    /// Once a day at `send_at`
+    #[default]
+    Daily,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_send_at() -> TimeOfDay {
+    TimeOfDay(NaiveTime::from_hms_opt(8, 0, 0).unwrap())
+}
+
+impl EmailConfig {
+    /// The offending key and why, if the digest cannot be sent
+    pub fn validate(&self) -> Result<(), (&'static str, String)> {
+        if self.smtp_host.trim().is_empty() {
+            return Err(("smtp_host", "must not be empty".to_string()));
+        }
+        if self.username.is_some() != self.password.is_some() {
+            return Err(("password", "set both `username` and `password`, or neither".to_string()));
+        }
+        self.from.parse::<Mailbox>().map_err(|e| ("from", e.to_string()))?;
+        if self.to.is_empty() {
+            return Err(("to", "add at least one recipient".to_string()));
+        }
+        for recipient in &self.to {
+            recipient
+                .parse::<Mailbox>()
+                .map_err(|e| ("to", format!("`{}`: {}", recipient, e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Name entries are queued under; targets with the same SMTP server,
+    /// schedule and recipients share one digest
+    pub fn digest_key(&self) -> String {
+        let mut recipients: Vec<String> = self.to.iter().map(|to| to.trim().to_lowercase()).collect();
+        recipients.sort();
+        recipients.dedup();
+
+        let schedule = match self.schedule {
+            DigestSchedule::Hourly => "hourly".to_string(),
+            DigestSchedule::Daily => format!("daily@{}", self.send_at),
+        };
+        format!(
+            "{}:{}|{}|{}",
+            self.smtp_host.trim().to_lowercase(),
+            self.smtp_port,
+            schedule,
+            recipients.join(",")
+        )
+    }
+
+    /// Latest time at or before `now` a digest was due
+    pub fn last_due<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> DateTime<Utc> {
+        let date = now.date_naive();
+        let due = match self.schedule {
+            DigestSchedule::Hourly => date.and_hms_opt(now.hour(), 0, 0).unwrap(),
+            DigestSchedule::Daily => {
+                let today = date.and_time(self.send_at.0);
+                if today <= now.naive_local() {
+                    today
+                } else {
+                    today - chrono::Duration::days(1)
+                }
+            }
+        };
+
+        // A time skipped by a DST change counts as now
+        now.timezone()
+            .from_local_datetime(&due)
+            .earliest()
+            .map(|due| due.with_timezone(&Utc))
+            .unwrap_or_else(|| now.with_timezone(&Utc))
+    }
+}
+
+/// Queues new listings, and price drops if enabled, for the next digest;
+/// other notifications are not part of digests
+pub struct EmailNotifier {
+    storage: Arc<dyn Storage>,
+    config: EmailConfig,
+}
+
+impl EmailNotifier {
+    pub fn new(storage: Arc<dyn Storage>, config: EmailConfig) -> Self {
+        Self { storage, config }
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    /// Entries are only queued here; sent digests are counted when they go out
+    fn records_delivery(&self) -> bool {
+        true
+    }
+
+    async fn send(&self, notification: &Notification<'_>, site_config: &SiteConfig) -> Result<()> {
+        let entry = match notification {
+            Notification::Listing { listing, rule, .. } => DigestEntry {
+                site: site_config.name.clone(),
+                rule: rule.map(|rule| rule.name.clone()),
+                listing: (*listing).clone(),
+                old_price: None,
+            },
+            Notification::PriceDrop { listing, old_price, .. } if self.config.price_drops => DigestEntry {
+                site: site_config.name.clone(),
+                rule: None,
+                listing: (*listing).clone(),
+                old_price: Some((*old_price).clone()),
+            },
+            _ => return Ok(()),
+        };
+
+        self.storage.queue_digest_entry(&self.config.digest_key(), &entry).await
+    }
+}
+
+/// Send every email digest that is due, checking once a minute until
+/// shutdown. Entries stay queued until their digest was sent, so digests
+/// missed while the process was down go out on the next start.
+pub async fn run_digests(mut config: watch::Receiver<Arc<Config>>, storage: Arc<dyn Storage>, delivery: Delivery) {
+    loop {
+        let current = config.borrow_and_update().clone();
+        for target in digest_targets(&current) {
+            if let Err(e) = send_if_due(&target, storage.as_ref(), &delivery, Local::now()).await {
+                metrics::record_notification("email", "digest", false);
+                error!("Failed to send email digest to {}: {:#}", target.to.join(", "), e);
+            }
+        }
+
+        tokio::select! {
+            _ = sleep(CHECK_INTERVAL) => {}
+            _ = shutdown::requested() => return,
+        }
+    }
+}
+
+/// Every configured email target, once per digest
+fn digest_targets(config: &Config) -> Vec<EmailConfig> {
+    let targets = config
+        .sites
+        .values()
+        .filter(|site| site.enabled)
+        .flat_map(|site| site.targets())
+        .chain(config.rules.iter().flat_map(|rule| rule.targets()));
+
+    let mut digests = HashSet::new();
+    targets
+        .filter_map(|target| match target {
+            NotifierConfig::Email(email) => Some(email),
+            _ => None,
+        })
+        .filter(|email| digests.insert(email.digest_key()))
+        .collect()
+}
+
+/// Send the digest of `target` if its oldest entry was queued before the
+/// digest was last due
+async fn send_if_due(target: &EmailConfig, storage: &dyn Storage, delivery: &Delivery, now: DateTime<Local>) -> Result<()> {
+    let entries = storage.pending_digest_entries(&target.digest_key()).await?;
+    match entries.first() {
+        Some(oldest) if oldest.queued_at < target.last_due(&now) => {}
+        _ => return Ok(()),
+    }
+
+    let digest = Digest::new(&entries);
+    let (subject, html, plain) = (digest.subject(), digest.render_html(), digest.render_text());
+
+    let payload = json!({ "subject": subject, "html": html });
+    if !delivery.render("digest", "email", &target.to, &payload)? {
+        send_email(target, &subject, html, plain).await?;
+        metrics::record_notification("email", "digest", true);
+        info!("Sent \"{}\" to {}", subject, target.to.join(", "));
+    }
+
+    let ids: Vec<i64> = entries.iter().map(|queued| queued.id).collect();
+    storage.remove_digest_entries(&ids).await
+}
+
+async fn send_email(target: &EmailConfig, subject: &str, html: String, plain: String) -> Result<()> {
+    let mut message = Message::builder().from(target.from.parse()?).subject(subject);
+    for recipient in &target.to {
+        message = message.to(recipient.parse()?);
+    }
+    let message = message
+        .multipart(MultiPart::alternative_plain_html(plain, html))
+        .context("Failed to build digest email")?;
+
+    let host = target.smtp_host.as_str();
+    let mut transport = match target.tls {
+        SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+        SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+        SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+    }
+    .port(target.smtp_port)
+    .timeout(Some(Duration::from_secs(30)));
+    if let (Some(username), Some(password)) = (&target.username, &target.password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password.expose().to_string()));
+    }
+
+    transport
+        .build()
+        .send(message)
+        .await
+        .with_context(|| format!("Failed to send digest via {}:{}", host, target.smtp_port))?;
+
+    Ok(())
+}
+
+/// Queued entries grouped by site; a listing queued for several rules
+/// appears once, naming all of them
+pub struct Digest {
+    sites: BTreeMap<String, Vec<DigestItem>>,
+}
+
+struct DigestItem {
+    listing: WatchListing,
+    rules: Vec<String>,
+    /// Previous and current price, for price drops
+    price_drop: Option<(PriceObservation, Option<PriceObservation>)>,
+}
+
+impl Digest {
+    pub fn new(entries: &[QueuedDigestEntry]) -> Self {
+        let mut sites: BTreeMap<String, Vec<DigestItem>> = BTreeMap::new();
+
+        for DigestEntry { site, rule, listing, old_price } in entries.iter().map(|queued| &queued.entry) {
+            let items = sites.entry(site.clone()).or_default();
+            let listing_id = listing.generate_listing_id();
+            let existing = items.iter_mut().find(|item| {
+                item.price_drop.is_some() == old_price.is_some() && item.listing.generate_listing_id() == listing_id
+            });
+
+            match existing {
+                Some(item) => {
+                    // Keep the latest snapshot, and the earliest price of a drop
+                    item.listing = listing.clone();
+                    if let Some((_, new_price)) = &mut item.price_drop {
+                        *new_price = PriceObservation::from_listing(listing);
+                    }
+                    item.rules.extend(rule.clone().filter(|rule| !item.rules.contains(rule)));
+                }
+                None => items.push(DigestItem {
+                    listing: listing.clone(),
+                    rules: rule.clone().into_iter().collect(),
+                    price_drop: old_price
+                        .clone()
+                        .map(|old_price| (old_price, PriceObservation::from_listing(listing))),
+                }),
+            }
+        }
+
+        Self { sites }
+    }
+
+    fn count(&self, price_drops: bool) -> usize {
+        self.sites
+            .values()
+            .flatten()
+            .filter(|item| item.price_drop.is_some() == price_drops)
+            .count()
+    }
+
+    /// e.g. "Watch digest: 3 new listings, 1 price drop"
+    pub fn subject(&self) -> String {
+        let plural = |n: usize, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+        let mut parts = vec![plural(self.count(false), "new listing")];
+        let price_drops = self.count(true);
+        if price_drops > 0 {
+            parts.push(plural(price_drops, "price drop"));
+        }
+
+        format!("Watch digest: {}", parts.join(", "))
+    }
+
+    pub fn render_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html><body style=\"font-family: sans-serif; color: #222; max-width: 640px\">\n",
+        );
+        html.push_str(&format!("<h1 style=\"font-size: 20px\">{}</h1>\n", text(&self.subject())));
+
+        for (site, items) in &self.sites {
+            html.push_str(&format!(
+                "<h2 style=\"font-size: 17px; border-bottom: 1px solid #ddd\">{} ({})</h2>\n",
+                text(site),
+                items.len()
+            ));
+            for item in items {
+                render_item(&mut html, site, item);
+            }
+        }
+
+        html.push_str("</body></html>\n");
+        html
+    }
+
+    /// Plain-text alternative for mail clients that do not show HTML
+    pub fn render_text(&self) -> String {
+        let mut lines = vec![self.subject()];
+        for (site, items) in &self.sites {
+            lines.push(String::new());
+            lines.push(format!("{} ({})", site, items.len()));
+            for item in items {
+                let price = match &item.price_drop {
+                    Some((old_price, _)) => format!("{} (was {})", item.listing.price_display(), old_price.display()),
+                    None => item.listing.price_display(),
+                };
+                lines.push(format!("- {} – {}", build_embed_title(&item.listing), price));
+                if !item.listing.watch_url.is_empty() {
+                    lines.push(format!("  {}", item.listing.watch_url));
+                }
+            }
+        }
+
+        lines.join("\n") + "\n"
+    }
+}
+
+fn render_item(html: &mut String, site: &str, item: &DigestItem) {
+    let listing = &item.listing;
+    let title = build_embed_title(listing);
+
+    html.push_str("<table role=\"presentation\" style=\"margin: 12px 0; border-collapse: collapse\"><tr>\n");
+    if listing.image_url.starts_with("http") {
+        html.push_str(&format!(
+            "<td style=\"vertical-align: top; padding-right: 12px\"><img src=\"{}\" alt=\"{}\" width=\"120\"></td>\n",
+            attr(&listing.image_url),
+            attr(&title)
+        ));
+    }
+    html.push_str("<td style=\"vertical-align: top\">\n");
+
+    if !item.rules.is_empty() {
+        html.push_str(&format!(
+            "<div style=\"font-size: 12px; color: #8a5a00\">Watchlist match: {}</div>\n",
+            text(&item.rules.join(", "))
+        ));
+    }
+    html.push_str(&format!("<div style=\"font-weight: bold\">{}</div>\n", link(&listing.watch_url, &title)));
+
+    let price = match &item.price_drop {
+        Some((old_price, new_price)) => {
+            let change = new_price
+                .as_ref()
+                .and_then(|new_price| format_price_change(old_price, new_price))
+                .map(|change| format!(" ({})", change))
+                .unwrap_or_default();
+            format!(
+                "Price drop: <s>{}</s> → <b>{}</b>{}",
+                text(&old_price.display()),
+                text(&listing.price_display()),
+                text(&change)
+            )
+        }
+        None => format!("<b>{}</b>", text(&listing.price_display())),
+    };
+    html.push_str(&format!("<div>{}</div>\n", price));
+
+    let reference_clean = listing.reference.replace(EMOJI_QUESTION, "");
+    let reference = if listing.reference != EMOJI_QUESTION && !title.contains(&reference_clean) {
+        listing.reference.clone()
+    } else {
+        EMOJI_QUESTION.to_string()
+    };
+    let details: Vec<String> = [
+        ("Reference", reference),
+        ("Year", listing.year.clone()),
+        ("Condition", listing.condition_display.clone()),
+        ("Box", listing.box_status.to_string()),
+        ("Papers", listing.papers_status.to_string()),
+        ("Case", listing.case_material.clone()),
+        ("Diameter", listing.diameter.clone()),
+    ]
+    .into_iter()
+    .filter(|(_, value)| value != EMOJI_QUESTION)
+    .map(|(name, value)| format!("{}: {}", name, text(&value)))
+    .collect();
+    if !details.is_empty() {
+        html.push_str(&format!("<div style=\"font-size: 13px; color: #555\">{}</div>\n", details.join(" · ")));
+    }
+
+    let mut links = Vec::new();
+    if listing.watch_url.starts_with("http") {
+        links.push(link(&listing.watch_url, &format!("View at {}", site)));
+    }
+    links.push(link(&build_chrono24_link(listing), "Search Chrono24"));
+    html.push_str(&format!("<div style=\"font-size: 13px\">{}</div>\n", links.join(" · ")));
+
+    html.push_str("</td></tr></table>\n");
+}
+
+fn link(url: &str, label: &str) -> String {
+    if url.starts_with("http") {
+        format!("<a href=\"{}\">{}</a>", attr(url), text(label))
+    } else {
+        text(label).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Currency, Money};
+    use chrono::{FixedOffset, NaiveDate};
+
+    fn queued(id: i64, site: &str, rule: Option<&str>, listing: &WatchListing, old_price: Option<i64>) -> QueuedDigestEntry {
+        QueuedDigestEntry {
+            id,
+            queued_at: Utc::now(),
+            entry: DigestEntry {
+                site: site.to_string(),
+                rule: rule.map(str::to_string),
+                listing: listing.clone(),
+                old_price: old_price.map(|amount| PriceObservation {
+                    price: Money::new(amount, Currency::Eur),
+                    price_eur: None,
+                }),
+            },
+        }
+    }
+
+    #[test]
+    fn groups_entries_by_site_and_merges_rule_matches() {
+        let daytona = WatchListing {
+            title: "Rolex Daytona".to_string(),
+            brand: "Rolex".to_string(),
+            model: "Daytona <16520>".to_string(),
+            year: "1995".to_string(),
+            price: Some(Money::new(2_500_000, Currency::Eur)),
+            watch_url: "https://www.grimmeissen.de/uhren/daytona".to_string(),
+            image_url: "https://www.grimmeissen.de/images/daytona.jpg".to_string(),
+            ..Default::default()
+        };
+        let speedmaster = WatchListing {
+            title: "Omega Speedmaster".to_string(),
+            brand: "Omega".to_string(),
+            model: "Speedmaster".to_string(),
+            price: Some(Money::new(450_000, Currency::Eur)),
+            watch_url: "https://www.watch-out.shop/uhren/speedmaster".to_string(),
+            ..Default::default()
+        };
+
+        let digest = Digest::new(&[
+            queued(1, "Watch Out", None, &speedmaster, Some(500_000)),
+            queued(2, "Grimmeissen", None, &daytona, None),
+            queued(3, "Grimmeissen", Some("Rolex"), &daytona, None),
+        ]);
+
+        assert_eq!(digest.subject(), "Watch digest: 1 new listing, 1 price drop");
+
+        let html = digest.render_html();
+        assert!(html.find("Grimmeissen (1)").unwrap() < html.find("Watch Out (1)").unwrap());
+        assert!(html.contains("Watchlist match: Rolex"));
+        assert!(html.contains("Rolex Daytona &lt;16520&gt;"));
+        assert!(html.contains("<img src=\"https://www.grimmeissen.de/images/daytona.jpg\""));
+        assert!(html.contains("<a href=\"https://www.grimmeissen.de/uhren/daytona\">View at Grimmeissen</a>"));
+        assert!(html.contains("Price drop: <s>5.000 €</s> → <b>4.500 €</b> (-10.0%)"));
+        assert!(!html.contains("Year: ❓"));
+    }
+
+    #[test]
+    fn digests_are_due_at_the_last_scheduled_time() {
+        let config: EmailConfig = serde_json::from_value(json!({
+            "smtp_host": "localhost",
+            "from": "Watch Monitor <watches@example.com>",
+            "to": ["collector@example.com"],
+            "send_at": "08:30",
+        }))
+        .unwrap();
+        config.validate().unwrap();
+
+        let zone = FixedOffset::east_opt(2 * 3600).unwrap();
+        let at = |day: u32, hour: u32, minute: u32| {
+            let time = NaiveDate::from_ymd_opt(2026, 10, day).unwrap().and_hms_opt(hour, minute, 0).unwrap();
+            zone.from_local_datetime(&time).unwrap()
+        };
+
+        assert_eq!(config.last_due(&at(16, 9, 0)), at(16, 8, 30).with_timezone(&Utc));
+        assert_eq!(config.last_due(&at(16, 8, 0)), at(15, 8, 30).with_timezone(&Utc));
+
+        let hourly = EmailConfig { schedule: DigestSchedule::Hourly, ..config };
+        assert_eq!(hourly.last_due(&at(16, 8, 45)), at(16, 8, 0).with_timezone(&Utc));
+    }
+
+    #[test]
+    fn digests_are_shared_by_targets_with_the_same_server_schedule_and_recipients() {
+        let config: EmailConfig = serde_json::from_value(json!({
+            "smtp_host": "smtp.example.com",
+            "from": "Watch Monitor <watches@example.com>",
+            "to": ["b@example.com", "a@example.com"],
+        }))
+        .unwrap();
+
+        let same = EmailConfig {
+            smtp_host: "SMTP.example.com ".to_string(),
+            to: vec!["A@example.com".to_string(), "b@example.com".to_string(), "a@example.com".to_string()],
+            ..config.clone()
+        };
+        assert_eq!(config.digest_key(), "smtp.example.com:587|daily@08:00|a@example.com,b@example.com");
+        assert_eq!(same.digest_key(), config.digest_key());
+
+        let others = [
+            EmailConfig { smtp_host: "mail.example.com".to_string(), ..config.clone() },
+            EmailConfig { smtp_port: 465, ..config.clone() },
+            EmailConfig { schedule: DigestSchedule::Hourly, ..config.clone() },
+            EmailConfig { send_at: TimeOfDay(NaiveTime::from_hms_opt(18, 0, 0).unwrap()), ..config.clone() },
+            EmailConfig { to: vec!["a@example.com".to_string()], ..config.clone() },
+        ];
+        for other in others {
+            assert_ne!(other.digest_key(), config.digest_key(), "{:?}", other);
+        }
+    }
+}
@@ -0,0 +1,385 @@
+pub mod discord;
+pub mod email;
+pub mod slack;
+pub mod telegram;
+pub mod webhook;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::error;
+
+use crate::config::{validate_http_url, Config, SiteConfig};
+use crate::health::SiteHealth;
+use crate::metrics;
+use crate::models::{Site, WatchListing};
+use crate::rules::WatchRule;
+use crate::storage::{EndedListing, PriceObservation, Storage};
+use crate::utils::secret::Secret;
+use discord::outbox::Outbox;
+use discord::DiscordNotifier;
+use email::{EmailConfig, EmailNotifier};
+use slack::SlackNotifier;
+use telegram::TelegramNotifier;
+use webhook::WebhookNotifier;
+
+/// Where notifications go
+#[derive(Debug, Clone)]
+pub enum Delivery {
+    /// Send to the configured targets
+    Webhook,
+    /// Dry run: print each rendered payload as a JSON line on stdout
+    Stdout,
+    /// Dry run: write each rendered payload to a JSON file in this directory
+    Directory(PathBuf),
+}
+
+impl Delivery {
+    pub fn is_dry_run(&self) -> bool {
+        !matches!(self, Delivery::Webhook)
+    }
+
+    /// Render `payload` instead of sending it if this is a dry run, naming
+    /// the target only by its (redacted) configuration value. Returns
+    /// whether the payload was rendered.
+    pub fn render(&self, kind: &str, notifier: &str, target: &impl Serialize, payload: &Value) -> Result<bool> {
+        let rendered = json!({
+            "kind": kind,
+            "notifier": notifier,
+            "target": target,
+            "payload": payload,
+        });
+
+        match self {
+            Delivery::Webhook => return Ok(false),
+            Delivery::Stdout => {
+                writeln!(std::io::stdout().lock(), "{}", rendered)?;
+            }
+            Delivery::Directory(dir) => {
+                // Named after the content so that runs can be compared file by file
+                let path = dir.join(format!("{}-{}-{:x}.json", kind, notifier, md5::compute(rendered.to_string())));
+                std::fs::write(&path, serde_json::to_string_pretty(&rendered)? + "\n")
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Something to tell the targets of a site or rule about
+pub enum Notification<'a> {
+    /// A listing of `site` seen for the first time, sent on behalf of
+    /// `rule` if it matched a watchlist rule
+    Listing {
+        site: &'a Site,
+        listing: &'a WatchListing,
+        rule: Option<&'a WatchRule>,
+    },
+    PriceDrop {
+        listing: &'a WatchListing,
+        old_price: &'a PriceObservation,
+        new_price: &'a PriceObservation,
+    },
+    /// A listing that sold or was removed
+    Ended(&'a EndedListing),
+    /// A site's scraper broke or recovered
+    Health(&'a SiteHealth),
+}
+
+impl Notification<'_> {
+    /// Label used in logs, metrics and dry-run output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Notification::Listing { rule: None, .. } => "listing",
+            Notification::Listing { rule: Some(_), .. } => "rule",
+            Notification::PriceDrop { .. } => "price-drop",
+            Notification::Ended(_) => "sold",
+            Notification::Health(_) => "health",
+        }
+    }
+}
+
+/// A notification backend such as a Discord webhook or a Telegram chat
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Backend name used in logs, metrics and dry-run output
+    fn name(&self) -> &'static str;
+
+    /// Whether the backend records delivery metrics itself, because `send`
+    /// only queues the notification
+    fn records_delivery(&self) -> bool {
+        false
+    }
+
+    async fn send(&self, notification: &Notification<'_>, site_config: &SiteConfig) -> Result<()>;
+}
+
+/// One configured notification target, e.g.
+/// `{ type = "discord", webhook = "env:DISCORD_WEBHOOK" }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotifierConfig {
+    Discord {
+        /// Webhook URL, inline or as an `env:`/`file:` reference
+        webhook: Secret,
+    },
+    Telegram {
+        /// Bot token from @BotFather, inline or as an `env:`/`file:` reference
+        bot_token: Secret,
+        /// Numeric chat ID or `@channelname`
+        chat_id: String,
+        /// Bot API base URL
+        #[serde(default = "default_telegram_api_url")]
+        api_url: String,
+    },
+    Slack {
+        /// Incoming webhook URL, inline or as an `env:`/`file:` reference
+        webhook: Secret,
+    },
+    /// Versioned JSON document for any HTTP endpoint, see `webhook::Document`
+    Webhook {
+        url: Secret,
+        /// Key for the HMAC-SHA256 signature header; requests are unsigned if unset
+        #[serde(default)]
+        secret: Option<Secret>,
+    },
+    /// Hourly or daily digest sent over SMTP, see `email::EmailConfig`
+    Email(EmailConfig),
+}
+
+fn default_telegram_api_url() -> String {
+    "https://api.telegram.org".to_string()
+}
+
+impl NotifierConfig {
+    /// The offending key and why, if the target cannot work
+    pub fn validate(&self) -> Result<(), (&'static str, String)> {
+        match self {
+            NotifierConfig::Discord { webhook } | NotifierConfig::Slack { webhook } => {
+                validate_http_url(webhook.expose()).map_err(|reason| ("webhook", reason))
+            }
+            NotifierConfig::Webhook { url, .. } => validate_http_url(url.expose()).map_err(|reason| ("url", reason)),
+            NotifierConfig::Telegram { chat_id, api_url, .. } => {
+                if chat_id.trim().is_empty() {
+                    return Err(("chat_id", "must not be empty".to_string()));
+                }
+                validate_http_url(api_url).map_err(|reason| ("api_url", reason))
+            }
+            NotifierConfig::Email(email) => email.validate(),
+        }
+    }
+}
+
+/// `notifiers`, preceded by a Discord target for the `webhook` shorthand
+pub fn targets(webhook: Option<&Secret>, notifiers: &[NotifierConfig]) -> Vec<NotifierConfig> {
+    webhook
+        .map(|webhook| NotifierConfig::Discord { webhook: webhook.clone() })
+        .into_iter()
+        .chain(notifiers.iter().cloned())
+        .collect()
+}
+
+/// Sends notifications to configured targets, sharing one HTTP client
+/// between all of them; Discord targets queue into the outbox and email
+/// targets into `storage` for their digest
+#[derive(Clone)]
+pub struct Dispatcher {
+    client: Client,
+    delivery: Delivery,
+    storage: Arc<dyn Storage>,
+    outbox: Outbox,
+}
+
+impl Dispatcher {
+    pub fn new(delivery: Delivery, storage: Arc<dyn Storage>) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("Failed to build notification HTTP client")?;
+
+        let outbox = Outbox::new(client.clone(), storage.clone());
+        Ok(Self { client, delivery, storage, outbox })
+    }
+
+    /// Let the outbox deliver to the Discord webhooks of `config`, including
+    /// messages queued for them before a restart; see [`Outbox::set_webhooks`]
+    pub fn configure(&self, config: &Config) {
+        self.outbox.set_webhooks(&discord_webhooks(config));
+    }
+
+    pub fn notifier(&self, target: &NotifierConfig) -> Box<dyn Notifier> {
+        match target {
+            NotifierConfig::Discord { webhook } => {
+                Box::new(DiscordNotifier::new(self.outbox.clone(), self.delivery.clone(), webhook.clone()))
+            }
+            NotifierConfig::Telegram { bot_token, chat_id, api_url } => Box::new(TelegramNotifier::new(
+                self.client.clone(),
+                self.delivery.clone(),
+                api_url,
+                bot_token.clone(),
+                chat_id.clone(),
+            )),
+            NotifierConfig::Slack { webhook } => {
+                Box::new(SlackNotifier::new(self.client.clone(), self.delivery.clone(), webhook.clone()))
+            }
+            NotifierConfig::Webhook { url, secret } => Box::new(WebhookNotifier::new(
+                self.client.clone(),
+                self.delivery.clone(),
+                url.clone(),
+                secret.clone(),
+            )),
+            NotifierConfig::Email(email) => Box::new(EmailNotifier::new(self.storage.clone(), email.clone())),
+        }
+    }
+
+    /// Send `notification` to every target in turn. A failing target is
+    /// logged and does not keep the others from being notified.
+    pub async fn send(&self, targets: &[NotifierConfig], notification: &Notification<'_>, site_config: &SiteConfig) {
+        for target in targets {
+            let notifier = self.notifier(target);
+            let result = notifier.send(notification, site_config).await;
+
+            if !self.delivery.is_dry_run() && !notifier.records_delivery() {
+                metrics::record_notification(notifier.name(), notification.kind(), result.is_ok());
+            }
+            if let Err(e) = result {
+                error!(
+                    "Failed to send {} {} notification for {}: {:#}",
+                    notifier.name(),
+                    notification.kind(),
+                    site_config.name,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Send email digests as they come due until shutdown; see
+    /// [`email::run_digests`]
+    pub async fn run_digests(self, config: watch::Receiver<Arc<Config>>) {
+        email::run_digests(config, self.storage, self.delivery).await;
+    }
+
+    /// Deliver queued Discord messages until shutdown; see [`Outbox::run`].
+    /// Dry runs queue nothing and never deliver what a previous run queued.
+    pub async fn run_outbox(self) {
+        if !self.delivery.is_dry_run() {
+            self.outbox.run().await;
+        }
+    }
+
+    /// Deliver the queued Discord messages that can go out now; see
+    /// [`Outbox::drain`]
+    pub async fn drain_outbox(&self) -> Result<()> {
+        if self.delivery.is_dry_run() {
+            return Ok(());
+        }
+        self.outbox.drain().await
+    }
+}
+
+/// Every Discord webhook `config` posts to, including the ones for sold
+/// listings and health alerts
+fn discord_webhooks(config: &Config) -> Vec<Secret> {
+    let targets = config
+        .sites
+        .values()
+        .flat_map(|site| site.targets())
+        .chain(config.rules.iter().flat_map(|rule| rule.targets()));
+
+    targets
+        .filter_map(|target| match target {
+            NotifierConfig::Discord { webhook } => Some(webhook),
+            _ => None,
+        })
+        .chain(config.sold_webhook.clone())
+        .chain(config.admin_webhook.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::storage::SqliteStorage;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn fans_out_to_every_target_despite_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/broken"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/site"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/rule"))
+            .and(body_partial_json(json!({
+                "content": "<@&42> Watchlist match: **Rolex**",
+                "allowed_mentions": { "roles": ["42"] },
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config: Config = serde_json::from_value(json!({
+            "sites": {
+                "watch_out": {
+                    "url": "https://www.watch-out.shop/uhren",
+                    "webhook": format!("{}/broken", server.uri()),
+                    "notifiers": [{ "type": "discord", "webhook": format!("{}/site", server.uri()) }],
+                    "name": "Watch Out",
+                    "color": 0,
+                    "base_url": "https://www.watch-out.shop",
+                }
+            },
+            "rules": [{
+                "name": "Rolex",
+                "notifiers": [{ "type": "discord", "webhook": format!("{}/rule", server.uri()) }],
+                "mention_role": "42",
+                "brands": ["Rolex"],
+            }],
+        }))
+        .unwrap();
+        config.validate().unwrap();
+
+        let site_config = &config.sites["watch_out"];
+        let rule = &config.rules[0];
+        let listing = WatchListing {
+            title: "Rolex Submariner".to_string(),
+            brand: "Rolex".to_string(),
+            ..Default::default()
+        };
+
+        let storage = SqliteStorage::overlay(std::path::Path::new("/nonexistent/watch-monitor-notify-test.db"))
+            .await
+            .unwrap();
+        storage.migrate().await.unwrap();
+        let dispatcher = Dispatcher::new(Delivery::Webhook, Arc::new(storage)).unwrap();
+        assert_eq!(site_config.targets().len(), 2);
+        dispatcher
+            .send(&site_config.targets(), &Notification::Listing { site: &Site::WatchOut, listing: &listing, rule: None }, site_config)
+            .await;
+        dispatcher
+            .send(&rule.targets(), &Notification::Listing { site: &Site::WatchOut, listing: &listing, rule: Some(rule) }, site_config)
+            .await;
+        dispatcher.drain_outbox().await.unwrap();
+    }
+}
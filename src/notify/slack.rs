@@ -0,0 +1,299 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::config::SiteConfig;
+use crate::health::HealthStatus;
+use crate::models::{
+    ListingStatus, WatchListing, EMOJI_BOX, EMOJI_CHECK, EMOJI_CONDITION, EMOJI_DIAMETER, EMOJI_MATERIAL,
+    EMOJI_PAPERS, EMOJI_PRICE, EMOJI_PRICE_DROP, EMOJI_QUESTION, EMOJI_REFERENCE, EMOJI_SOLD, EMOJI_TIME,
+    EMOJI_WARNING, EMOJI_YEAR,
+};
+use crate::notify::discord::embed::{
+    build_chrono24_link, build_embed_title, format_price_change, format_time_on_market,
+};
+use crate::notify::{Delivery, Notification, Notifier};
+use crate::utils::secret::Secret;
+
+/// Posts Block Kit messages to a Slack incoming webhook
+pub struct SlackNotifier {
+    client: Client,
+    delivery: Delivery,
+    webhook: Secret,
+}
+
+impl SlackNotifier {
+    pub fn new(client: Client, delivery: Delivery, webhook: Secret) -> Self {
+        Self { client, delivery, webhook }
+    }
+
+    async fn post(&self, kind: &str, payload: &Value) -> Result<()> {
+        if self.delivery.render(kind, self.name(), &self.webhook, payload)? {
+            return Ok(());
+        }
+
+        let response = self
+            .client
+            .post(self.webhook.expose())
+            .json(payload)
+            .send()
+            .await
+            // The webhook URL contains its token, keep it out of error messages
+            .map_err(|e| e.without_url())
+            .context("Failed to send Slack webhook")?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            Err(anyhow::anyhow!("Slack webhook failed: {} - {}", status, error_text))
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    async fn send(&self, notification: &Notification<'_>, site_config: &SiteConfig) -> Result<()> {
+        let payload = create_message(notification, site_config);
+        self.post(notification.kind(), &payload).await?;
+
+        info!("Successfully sent Slack {} notification for {}", notification.kind(), site_config.name);
+        Ok(())
+    }
+}
+
+/// Block Kit message for a notification, with a plain `text` fallback for
+/// push notifications
+pub fn create_message(notification: &Notification<'_>, site_config: &SiteConfig) -> Value {
+    match notification {
+        Notification::Listing { listing, rule, .. } => {
+            let mut blocks = Vec::new();
+            if let Some(rule) = rule {
+                blocks.push(section(&format!("Watchlist match: *{}*", escape(&rule.name))));
+            }
+            blocks.extend(listing_blocks(listing, site_config, None));
+
+            json!({
+                "text": format!("{} – {}", build_embed_title(listing), listing.price_display()),
+                "blocks": blocks,
+            })
+        }
+        Notification::PriceDrop { listing, old_price, new_price } => {
+            let change = format_price_change(old_price, new_price)
+                .map(|change| format!(" ({})", change))
+                .unwrap_or_default();
+            let headline = format!(
+                "{} *Price drop:* ~{}~ → *{}*{}",
+                EMOJI_PRICE_DROP,
+                escape(&old_price.display()),
+                escape(&new_price.display()),
+                change
+            );
+
+            json!({
+                "text": format!("Price drop: {} – {}", build_embed_title(listing), new_price.display()),
+                "blocks": listing_blocks(listing, site_config, Some(headline)),
+            })
+        }
+        Notification::Ended(ended) => {
+            let listing = &ended.listing;
+            let status = match ended.status {
+                ListingStatus::Sold => "Sold",
+                _ => "Removed",
+            };
+            let title = build_embed_title(listing);
+
+            json!({
+                "text": format!("{}: {}", status, title),
+                "blocks": [
+                    section(&format!("{} *{}:* {}", EMOJI_SOLD, status, link(&listing.watch_url, &title))),
+                    {
+                        "type": "section",
+                        "fields": [
+                            field(EMOJI_PRICE, "Last Price", &listing.price_display()),
+                            field(EMOJI_TIME, "Time on Market", &format_time_on_market(ended.time_on_market)),
+                        ],
+                    },
+                    context(site_config),
+                ],
+            })
+        }
+        Notification::Health(health) => {
+            let (title, description) = match health.status {
+                HealthStatus::Broken => (
+                    format!("{} {} scraper looks broken", EMOJI_WARNING, site_config.name),
+                    health.problems.iter().map(|problem| format!("• {}", escape(problem))).collect::<Vec<_>>().join("\n"),
+                ),
+                HealthStatus::Healthy => (
+                    format!("{} {} scraper recovered", EMOJI_CHECK, site_config.name),
+                    "Listings are being found again.".to_string(),
+                ),
+            };
+
+            json!({
+                "text": title,
+                "blocks": [section(&format!("*{}*\n{}", escape(&title), description))],
+            })
+        }
+    }
+}
+
+/// Title with price, the photo, the embed's detail fields and buttons to
+/// the dealer and Chrono24
+fn listing_blocks(listing: &WatchListing, site_config: &SiteConfig, headline: Option<String>) -> Vec<Value> {
+    let title = build_embed_title(listing);
+    let mut blocks = Vec::new();
+
+    if let Some(headline) = headline {
+        blocks.push(section(&headline));
+    }
+    blocks.push(section(&format!(
+        "*{}*\n{} *{}*",
+        link(&listing.watch_url, &title),
+        EMOJI_PRICE,
+        escape(&listing.price_display())
+    )));
+
+    if listing.image_url.starts_with("http") {
+        blocks.push(json!({
+            "type": "image",
+            "image_url": listing.image_url,
+            "alt_text": title,
+        }));
+    }
+
+    let reference_clean = listing.reference.replace(EMOJI_QUESTION, "");
+    let reference = if listing.reference != EMOJI_QUESTION && !title.contains(&reference_clean) {
+        listing.reference.clone()
+    } else {
+        EMOJI_QUESTION.to_string()
+    };
+    let details = [
+        (EMOJI_REFERENCE, "Reference", reference),
+        (EMOJI_YEAR, "Year", listing.year.clone()),
+        (EMOJI_CONDITION, "Condition", listing.condition_display.clone()),
+        (EMOJI_BOX, "Box", listing.box_status.to_string()),
+        (EMOJI_PAPERS, "Papers", listing.papers_status.to_string()),
+        (EMOJI_MATERIAL, "Case Material", listing.case_material.clone()),
+        (EMOJI_DIAMETER, "Diameter", listing.diameter.clone()),
+    ];
+    let fields: Vec<Value> = details
+        .iter()
+        .filter(|(_, _, value)| value != EMOJI_QUESTION)
+        .map(|(emoji, name, value)| field(emoji, name, value))
+        .collect();
+    if !fields.is_empty() {
+        blocks.push(json!({ "type": "section", "fields": fields }));
+    }
+
+    let mut buttons = Vec::new();
+    if listing.watch_url.starts_with("http") {
+        buttons.push(json!({
+            "type": "button",
+            "text": { "type": "plain_text", "text": format!("View at {}", site_config.name) },
+            "url": listing.watch_url,
+            "style": "primary",
+        }));
+    }
+    buttons.push(json!({
+        "type": "button",
+        "text": { "type": "plain_text", "text": "Search Chrono24" },
+        "url": build_chrono24_link(listing),
+    }));
+    blocks.push(json!({ "type": "actions", "elements": buttons }));
+
+    blocks.push(context(site_config));
+    blocks
+}
+
+fn section(text: &str) -> Value {
+    json!({
+        "type": "section",
+        "text": { "type": "mrkdwn", "text": text },
+    })
+}
+
+fn field(emoji: &str, name: &str, value: &str) -> Value {
+    json!({ "type": "mrkdwn", "text": format!("{} *{}:*\n{}", emoji, name, escape(value)) })
+}
+
+fn context(site_config: &SiteConfig) -> Value {
+    json!({
+        "type": "context",
+        "elements": [{ "type": "mrkdwn", "text": escape(&site_config.name) }],
+    })
+}
+
+fn link(url: &str, label: &str) -> String {
+    if url.starts_with("http") {
+        format!("<{}|{}>", url, escape(label).replace('|', "¦"))
+    } else {
+        escape(label)
+    }
+}
+
+/// Slack only requires these three characters to be escaped in mrkdwn
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::models::{BoxStatus, PapersStatus, Site};
+
+    #[test]
+    fn listing_message_has_image_fields_and_dealer_button() {
+        let config: Config = serde_json::from_value(json!({
+            "sites": {
+                "watch_out": {
+                    "url": "https://www.watch-out.shop/uhren",
+                    "notifiers": [{ "type": "slack", "webhook": "https://hooks.slack.com/services/T0/B0/test" }],
+                    "name": "Watch Out",
+                    "color": 0,
+                    "base_url": "https://www.watch-out.shop",
+                }
+            }
+        }))
+        .unwrap();
+        config.validate().unwrap();
+
+        let listing = WatchListing {
+            title: "Omega Speedmaster".to_string(),
+            brand: "Omega".to_string(),
+            model: "Speedmaster <Moonwatch>".to_string(),
+            year: "1969".to_string(),
+            box_status: BoxStatus::Yes,
+            papers_status: PapersStatus::No,
+            watch_url: "https://www.watch-out.shop/uhren/speedmaster".to_string(),
+            image_url: "https://www.watch-out.shop/images/speedmaster.jpg".to_string(),
+            ..Default::default()
+        };
+        let message = create_message(
+            &Notification::Listing { site: &Site::WatchOut, listing: &listing, rule: None },
+            &config.sites["watch_out"],
+        );
+        let blocks = message["blocks"].as_array().unwrap();
+
+        assert_eq!(
+            blocks[0]["text"]["text"],
+            "*<https://www.watch-out.shop/uhren/speedmaster|Omega Speedmaster &lt;Moonwatch&gt;>*\n💰 *❓*"
+        );
+        assert_eq!(blocks[1]["type"], "image");
+        assert_eq!(blocks[1]["image_url"], "https://www.watch-out.shop/images/speedmaster.jpg");
+        let fields: Vec<&str> = blocks[2]["fields"].as_array().unwrap().iter().map(|f| f["text"].as_str().unwrap()).collect();
+        assert_eq!(fields.len(), 3);
+        assert!(fields[0].ends_with("*Year:*\n1969"));
+        assert_eq!(blocks[3]["elements"][0]["url"], "https://www.watch-out.shop/uhren/speedmaster");
+        assert_eq!(blocks[3]["elements"][0]["text"]["text"], "View at Watch Out");
+        assert_eq!(blocks[4]["type"], "context");
+    }
+}
@@ -0,0 +1,343 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use html_escape::{encode_double_quoted_attribute as attr, encode_text as text};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::config::SiteConfig;
+use crate::health::HealthStatus;
+use crate::models::{
+    ListingStatus, WatchListing, EMOJI_BOX, EMOJI_CHECK, EMOJI_CONDITION, EMOJI_DIAMETER, EMOJI_MATERIAL,
+    EMOJI_PAPERS, EMOJI_PRICE, EMOJI_PRICE_DROP, EMOJI_QUESTION, EMOJI_REFERENCE, EMOJI_SEARCH, EMOJI_SOLD,
+    EMOJI_TIME, EMOJI_WARNING, EMOJI_YEAR,
+};
+use crate::notify::discord::embed::{
+    build_chrono24_link, build_embed_title, format_price_change, format_time_on_market,
+};
+use crate::notify::{Delivery, Notification, Notifier};
+use crate::utils::secret::Secret;
+
+/// Telegram rejects photo captions longer than this
+const MAX_CAPTION_CHARS: usize = 1024;
+
+/// Sends listings as photo messages through the Telegram Bot API
+pub struct TelegramNotifier {
+    client: Client,
+    delivery: Delivery,
+    api_url: String,
+    bot_token: Secret,
+    chat_id: String,
+}
+
+/// The envelope of every Bot API response
+#[derive(Debug, Deserialize)]
+struct ApiResponse {
+    ok: bool,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+impl TelegramNotifier {
+    pub fn new(client: Client, delivery: Delivery, api_url: &str, bot_token: Secret, chat_id: String) -> Self {
+        Self {
+            client,
+            delivery,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            bot_token,
+            chat_id,
+        }
+    }
+
+    /// Send `caption` with the listing's photo, or as a plain message if
+    /// there is no usable photo or Telegram cannot fetch it
+    async fn send_html(&self, kind: &str, image_url: Option<&str>, caption: String) -> Result<()> {
+        let image_url = image_url.filter(|url| url.starts_with("http") && caption.chars().count() <= MAX_CAPTION_CHARS);
+
+        if let Some(image_url) = image_url {
+            let payload = json!({
+                "chat_id": self.chat_id,
+                "photo": image_url,
+                "caption": caption,
+                "parse_mode": "HTML",
+            });
+            match self.call(kind, "sendPhoto", &payload).await {
+                Ok(()) => return Ok(()),
+                Err(e) => warn!("Telegram could not send the photo, sending text only: {:#}", e),
+            }
+        }
+
+        let payload = json!({
+            "chat_id": self.chat_id,
+            "text": caption,
+            "parse_mode": "HTML",
+        });
+        self.call(kind, "sendMessage", &payload).await
+    }
+
+    async fn call(&self, kind: &str, method: &str, payload: &Value) -> Result<()> {
+        if self.delivery.render(kind, self.name(), &self.chat_id, payload)? {
+            return Ok(());
+        }
+
+        let url = format!("{}/bot{}/{}", self.api_url, self.bot_token.expose(), method);
+        let response = self
+            .client
+            .post(&url)
+            .json(payload)
+            .send()
+            .await
+            // The URL contains the bot token, keep it out of error messages
+            .map_err(|e| e.without_url())
+            .with_context(|| format!("Failed to call Telegram {}", method))?;
+
+        let status = response.status();
+        let body: ApiResponse = response
+            .json()
+            .await
+            .map_err(|e| e.without_url())
+            .with_context(|| format!("Invalid Telegram {} response ({})", method, status))?;
+
+        if status.is_success() && body.ok {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Telegram {} failed: {} - {}",
+                method,
+                status,
+                body.description.unwrap_or_else(|| "Unknown error".to_string())
+            ))
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    async fn send(&self, notification: &Notification<'_>, site_config: &SiteConfig) -> Result<()> {
+        let kind = notification.kind();
+
+        match notification {
+            Notification::Listing { listing, rule, .. } => {
+                let mut caption = create_caption(listing, site_config);
+                if let Some(rule) = rule {
+                    caption = format!("Watchlist match: <b>{}</b>\n\n{}", text(&rule.name), caption);
+                }
+                self.send_html(kind, Some(&listing.image_url), caption).await?;
+                info!("Successfully sent Telegram notification for {}", listing.title);
+            }
+            Notification::PriceDrop { listing, old_price, new_price } => {
+                let change = format_price_change(old_price, new_price)
+                    .map(|change| format!(" ({})", change))
+                    .unwrap_or_default();
+                let caption = format!(
+                    "{} <b>Price drop:</b> <s>{}</s> → <b>{}</b>{}\n\n{}",
+                    EMOJI_PRICE_DROP,
+                    text(&old_price.display()),
+                    text(&new_price.display()),
+                    change,
+                    create_caption(listing, site_config)
+                );
+                self.send_html(kind, Some(&listing.image_url), caption).await?;
+                info!("Successfully sent Telegram price drop notification for {}", listing.title);
+            }
+            Notification::Ended(ended) => {
+                let listing = &ended.listing;
+                let status = match ended.status {
+                    ListingStatus::Sold => "Sold",
+                    _ => "Removed",
+                };
+                let caption = format!(
+                    "{} <b>{}:</b> {}\n\n{} Last Price: <b>{}</b>\n{} Time on Market: <b>{}</b>\n\n<i>{}</i>",
+                    EMOJI_SOLD,
+                    status,
+                    link(&listing.watch_url, &build_embed_title(listing)),
+                    EMOJI_PRICE,
+                    text(&listing.price_display()),
+                    EMOJI_TIME,
+                    format_time_on_market(ended.time_on_market),
+                    text(&site_config.name)
+                );
+                self.send_html(kind, Some(&listing.image_url), caption).await?;
+                info!("Successfully sent Telegram {} notification for {}", ended.status, listing.title);
+            }
+            Notification::Health(health) => {
+                let message = match health.status {
+                    HealthStatus::Broken => format!(
+                        "{} <b>{} scraper looks broken</b>\n{}",
+                        EMOJI_WARNING,
+                        text(&site_config.name),
+                        health.problems.iter().map(|problem| format!("- {}", text(problem))).collect::<Vec<_>>().join("\n")
+                    ),
+                    HealthStatus::Healthy => format!(
+                        "{} <b>{} scraper recovered</b>\nListings are being found again.",
+                        EMOJI_CHECK,
+                        text(&site_config.name)
+                    ),
+                };
+                self.send_html(kind, None, message).await?;
+                info!("Successfully sent Telegram health alert for {} ({})", site_config.name, health.status);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// HTML caption with the same details as the Discord embed; details that
+/// could not be scraped are left out
+pub fn create_caption(listing: &WatchListing, site_config: &SiteConfig) -> String {
+    let title = build_embed_title(listing);
+    let mut lines = vec![
+        format!("<b>{}</b>", link(&listing.watch_url, &title)),
+        String::new(),
+        format!("{} Price: <b>{}</b>", EMOJI_PRICE, text(&listing.price_display())),
+    ];
+
+    let reference_clean = listing.reference.replace(EMOJI_QUESTION, "");
+    if listing.reference != EMOJI_QUESTION && !title.contains(&reference_clean) {
+        lines.push(format!("{} Reference: <b>{}</b>", EMOJI_REFERENCE, text(&listing.reference)));
+    }
+
+    lines.push(format!(
+        "{} Chrono24: {}",
+        EMOJI_SEARCH,
+        link(&build_chrono24_link(listing), "Search similar")
+    ));
+
+    let details = [
+        (EMOJI_YEAR, "Year", listing.year.clone()),
+        (EMOJI_CONDITION, "Condition", listing.condition_display.clone()),
+        (EMOJI_BOX, "Box", listing.box_status.to_string()),
+        (EMOJI_PAPERS, "Papers", listing.papers_status.to_string()),
+        (EMOJI_MATERIAL, "Case Material", listing.case_material.clone()),
+        (EMOJI_DIAMETER, "Diameter", listing.diameter.clone()),
+    ];
+    let details: Vec<String> = details
+        .into_iter()
+        .filter(|(_, _, value)| value != EMOJI_QUESTION)
+        .map(|(emoji, name, value)| format!("{} {}: <b>{}</b>", emoji, name, text(&value)))
+        .collect();
+    if !details.is_empty() {
+        lines.push(String::new());
+        lines.extend(details);
+    }
+
+    lines.push(String::new());
+    lines.push(format!("<i>{}</i>", text(&site_config.name)));
+
+    lines.join("\n")
+}
+
+fn link(url: &str, label: &str) -> String {
+    if url.starts_with("http") {
+        format!("<a href=\"{}\">{}</a>", attr(url), text(label))
+    } else {
+        text(label).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::models::{BoxStatus, PapersStatus, Site};
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn site_config() -> SiteConfig {
+        let config: Config = serde_json::from_value(json!({
+            "sites": {
+                "grimmeissen": {
+                    "url": "https://www.grimmeissen.de/de/uhren",
+                    "webhook": "https://discord.com/api/webhooks/0/test",
+                    "name": "Grimmeissen",
+                    "color": 0,
+                    "base_url": "https://www.grimmeissen.de",
+                }
+            }
+        }))
+        .unwrap();
+
+        config.sites["grimmeissen"].clone()
+    }
+
+    fn listing() -> WatchListing {
+        WatchListing {
+            title: "Rolex Submariner <Date>".to_string(),
+            brand: "Rolex".to_string(),
+            model: "Submariner <Date>".to_string(),
+            reference: "126610LN".to_string(),
+            year: "2021".to_string(),
+            condition_display: "Sehr gut".to_string(),
+            box_status: BoxStatus::Yes,
+            papers_status: PapersStatus::Yes,
+            case_material: EMOJI_QUESTION.to_string(),
+            diameter: "41 mm".to_string(),
+            watch_url: "https://www.grimmeissen.de/de/uhren/1?a=1&b=2".to_string(),
+            image_url: "https://www.grimmeissen.de/images/1.jpg".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn caption_carries_the_embed_fields() {
+        let caption = create_caption(&listing(), &site_config());
+
+        assert!(caption.starts_with(
+            "<b><a href=\"https://www.grimmeissen.de/de/uhren/1?a=1&amp;b=2\">Rolex Submariner &lt;Date&gt; | 126610LN</a></b>"
+        ));
+        assert!(caption.contains("Year: <b>2021</b>"));
+        assert!(caption.contains("Condition: <b>Sehr gut</b>"));
+        assert!(caption.contains("Diameter: <b>41 mm</b>"));
+        assert!(caption.contains("https://www.chrono24.de/search/index.htm?dosearch=true&amp;query=Rolex"));
+        // Unknown details and a reference already in the title are left out
+        assert!(!caption.contains("Case Material"));
+        assert!(!caption.contains("Reference:"));
+        assert!(caption.ends_with("<i>Grimmeissen</i>"));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_text_when_the_photo_is_rejected() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:secret/sendPhoto"))
+            .and(body_partial_json(json!({
+                "chat_id": "-100200",
+                "photo": "https://www.grimmeissen.de/images/1.jpg",
+                "parse_mode": "HTML",
+            })))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "ok": false,
+                "error_code": 400,
+                "description": "Bad Request: wrong file identifier/HTTP URL specified",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:secret/sendMessage"))
+            .and(body_partial_json(json!({ "chat_id": "-100200", "parse_mode": "HTML" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true, "result": {} })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = TelegramNotifier::new(
+            Client::new(),
+            Delivery::Webhook,
+            &format!("{}/", server.uri()),
+            Secret::resolve("123:secret").unwrap(),
+            "-100200".to_string(),
+        );
+        let listing = listing();
+        notifier
+            .send(&Notification::Listing { site: &Site::Grimmeissen, listing: &listing, rule: None }, &site_config())
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,324 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use serde::Serialize;
+use sha2::Sha256;
+use tracing::info;
+
+use crate::config::SiteConfig;
+use crate::models::{BoxStatus, Money, PapersStatus, WatchListing, EMOJI_QUESTION};
+use crate::notify::{Delivery, Notification, Notifier};
+use crate::storage::PriceObservation;
+use crate::utils::secret::Secret;
+
+/// Version of the JSON document; bumped only for incompatible changes
+pub const DOCUMENT_VERSION: u32 = 1;
+
+pub const SIGNATURE_HEADER: &str = "X-Watch-Monitor-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Watch-Monitor-Timestamp";
+pub const EVENT_HEADER: &str = "X-Watch-Monitor-Event";
+
+/// Posts a versioned JSON document to an arbitrary HTTP endpoint, signed
+/// with HMAC-SHA256 if a secret is configured
+pub struct WebhookNotifier {
+    client: Client,
+    delivery: Delivery,
+    url: Secret,
+    secret: Option<Secret>,
+}
+
+/// The body of every request
+#[derive(Debug, Serialize)]
+pub struct Document {
+    pub version: u32,
+    /// `listing`, `rule`, `price-drop`, `sold` or `health`
+    pub event: String,
+    pub sent_at: DateTime<Utc>,
+    /// Dealer name as configured
+    pub site: String,
+    /// Name of the watchlist rule that matched, for `rule` events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listing: Option<ListingDocument>,
+    /// Previous price, for `price-drop` events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_price: Option<PriceDocument>,
+    /// `sold` or `removed`, for `sold` events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_on_market_seconds: Option<i64>,
+    /// Scraper health, for `health` events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<HealthDocument>,
+}
+
+/// A listing; details the dealer did not show are `null`
+#[derive(Debug, Serialize)]
+pub struct ListingDocument {
+    /// Stable across price changes
+    pub id: String,
+    pub title: Option<String>,
+    pub brand: Option<String>,
+    pub model: Option<String>,
+    pub reference: Option<String>,
+    pub year: Option<String>,
+    pub price: Option<PriceDocument>,
+    pub condition: Option<String>,
+    pub case_material: Option<String>,
+    pub diameter: Option<String>,
+    /// `yes`, `no` or `null`
+    #[serde(rename = "box")]
+    pub box_status: Option<&'static str>,
+    /// `yes`, `no` or `null`
+    pub papers: Option<&'static str>,
+    pub url: String,
+    pub image_url: Option<String>,
+}
+
+/// A price in the dealer's currency, with its EUR conversion if known
+#[derive(Debug, Serialize)]
+pub struct PriceDocument {
+    pub amount_minor: i64,
+    pub currency: &'static str,
+    pub eur_minor: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthDocument {
+    /// `healthy` or `broken`
+    pub status: &'static str,
+    pub problems: Vec<String>,
+}
+
+impl WebhookNotifier {
+    pub fn new(client: Client, delivery: Delivery, url: Secret, secret: Option<Secret>) -> Self {
+        Self { client, delivery, url, secret }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn send(&self, notification: &Notification<'_>, site_config: &SiteConfig) -> Result<()> {
+        let document = Document::new(notification, site_config, Utc::now());
+        let payload = serde_json::to_value(&document)?;
+        if self.delivery.render(&document.event, self.name(), &self.url, &payload)? {
+            return Ok(());
+        }
+
+        let body = serde_json::to_vec(&document)?;
+        let timestamp = document.sent_at.timestamp();
+        let mut request = self
+            .client
+            .post(self.url.expose())
+            .header(CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, &document.event)
+            .header(TIMESTAMP_HEADER, timestamp.to_string());
+        if let Some(secret) = &self.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret.expose(), timestamp, &body));
+        }
+
+        let response = request
+            .body(body)
+            .send()
+            .await
+            // The URL may carry a token, keep it out of error messages
+            .map_err(|e| e.without_url())
+            .context("Failed to send webhook")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            let error_text: String = error_text.chars().take(200).collect();
+            anyhow::bail!("Webhook failed: {} - {}", status, error_text);
+        }
+
+        info!("Successfully sent {} webhook for {}", document.event, site_config.name);
+        Ok(())
+    }
+}
+
+/// `sha256=` followed by the hex HMAC-SHA256 of `<timestamp>.<body>`
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+impl Document {
+    pub fn new(notification: &Notification<'_>, site_config: &SiteConfig, sent_at: DateTime<Utc>) -> Self {
+        let mut document = Document {
+            version: DOCUMENT_VERSION,
+            event: notification.kind().to_string(),
+            sent_at,
+            site: site_config.name.clone(),
+            rule: None,
+            listing: None,
+            old_price: None,
+            status: None,
+            time_on_market_seconds: None,
+            health: None,
+        };
+
+        match notification {
+            Notification::Listing { listing, rule, .. } => {
+                document.rule = rule.map(|rule| rule.name.clone());
+                document.listing = Some(ListingDocument::from(*listing));
+            }
+            Notification::PriceDrop { listing, old_price, .. } => {
+                document.listing = Some(ListingDocument::from(*listing));
+                document.old_price = Some(PriceDocument::from(*old_price));
+            }
+            Notification::Ended(ended) => {
+                document.listing = Some(ListingDocument::from(&ended.listing));
+                document.status = Some(ended.status.key().to_string());
+                document.time_on_market_seconds = Some(ended.time_on_market.num_seconds());
+            }
+            Notification::Health(health) => {
+                document.health = Some(HealthDocument {
+                    status: health.status.key(),
+                    problems: health.problems.clone(),
+                });
+            }
+        }
+
+        document
+    }
+}
+
+impl From<&WatchListing> for ListingDocument {
+    fn from(listing: &WatchListing) -> Self {
+        let known = |value: &str| {
+            let value = value.trim();
+            (!value.is_empty() && value != EMOJI_QUESTION).then(|| value.to_string())
+        };
+
+        Self {
+            id: listing.generate_listing_id().0,
+            title: known(&listing.title),
+            brand: known(&listing.brand),
+            model: known(&listing.model),
+            reference: known(&listing.reference),
+            year: known(&listing.year),
+            price: listing.price.map(|price| PriceDocument::new(price, listing.price_eur)),
+            condition: known(&listing.condition_display),
+            case_material: known(&listing.case_material),
+            diameter: known(&listing.diameter),
+            box_status: match &listing.box_status {
+                BoxStatus::Unknown => None,
+                status => Some(status.key()),
+            },
+            papers: match &listing.papers_status {
+                PapersStatus::Unknown => None,
+                status => Some(status.key()),
+            },
+            url: listing.watch_url.clone(),
+            image_url: known(&listing.image_url),
+        }
+    }
+}
+
+impl PriceDocument {
+    fn new(price: Money, price_eur: Option<Money>) -> Self {
+        Self {
+            amount_minor: price.amount_minor,
+            currency: price.currency.code(),
+            eur_minor: price_eur.map(|eur| eur.amount_minor),
+        }
+    }
+}
+
+impl From<&PriceObservation> for PriceDocument {
+    fn from(observation: &PriceObservation) -> Self {
+        Self::new(observation.price, observation.price_eur)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::models::{Currency, Site};
+    use serde_json::json;
+    use wiremock::matchers::{header, header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn posts_a_signed_listing_document() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/feed"))
+            .and(header(EVENT_HEADER, "listing"))
+            .and(header_exists(SIGNATURE_HEADER))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config: Config = serde_json::from_value(json!({
+            "sites": {
+                "tropicalwatch": {
+                    "url": "https://tropicalwatch.com/watches",
+                    "notifiers": [{ "type": "webhook", "url": format!("{}/feed", server.uri()), "secret": "s3cret" }],
+                    "name": "Tropical Watch",
+                    "color": 0,
+                    "base_url": "https://tropicalwatch.com",
+                }
+            }
+        }))
+        .unwrap();
+        config.validate().unwrap();
+
+        let listing = WatchListing {
+            title: "Rolex GMT-Master 1675".to_string(),
+            brand: "Rolex".to_string(),
+            reference: "1675".to_string(),
+            price: Some(Money::new(1_250_000, Currency::Usd)),
+            price_eur: Some(Money::new(1_150_000, Currency::Eur)),
+            box_status: BoxStatus::No,
+            watch_url: "https://tropicalwatch.com/watches/1675".to_string(),
+            ..Default::default()
+        };
+        let site_config = &config.sites["tropicalwatch"];
+        let storage = crate::storage::SqliteStorage::overlay(std::path::Path::new("/nonexistent/watch-monitor-webhook-test.db"))
+            .await
+            .unwrap();
+        let dispatcher = crate::notify::Dispatcher::new(Delivery::Webhook, std::sync::Arc::new(storage)).unwrap();
+        let notification = Notification::Listing { site: &Site::TropicalWatch, listing: &listing, rule: None };
+        dispatcher.send(&site_config.targets(), &notification, site_config).await;
+
+        let requests = server.received_requests().await.unwrap();
+        let request = &requests[0];
+        let header = |name: &str| {
+            let (_, values) = request.headers.iter().find(|(key, _)| key.as_str().eq_ignore_ascii_case(name)).unwrap();
+            values.last().as_str().to_string()
+        };
+        let timestamp: i64 = header(TIMESTAMP_HEADER).parse().unwrap();
+        assert_eq!(header(SIGNATURE_HEADER), sign("s3cret", timestamp, &request.body));
+
+        let document: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(document["version"], 1);
+        assert_eq!(document["event"], "listing");
+        assert_eq!(document["site"], "Tropical Watch");
+        assert_eq!(document["listing"]["reference"], "1675");
+        assert_eq!(document["listing"]["year"], json!(null));
+        assert_eq!(document["listing"]["box"], "no");
+        assert_eq!(document["listing"]["papers"], json!(null));
+        assert_eq!(
+            document["listing"]["price"],
+            json!({ "amount_minor": 1_250_000, "currency": "USD", "eur_minor": 1_150_000 })
+        );
+        assert!(document.get("rule").is_none());
+    }
+}
@@ -53,7 +53,7 @@ pub fn get_condition_display(condition_raw: &str, site: Site, description_parts:
         }
         _ => {
             // Generic condition mapping for other sites
-            if condition_raw != EMOJI_QUESTION {
+            if !condition_raw.trim().is_empty() && condition_raw != EMOJI_QUESTION {
                 condition_raw.to_string()
             } else {
                 EMOJI_QUESTION.to_string()
@@ -1,83 +1,178 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
-use crate::models::EMOJI_QUESTION;
+use crate::models::{Currency, Money};
 
-static PRICE_REGEX: Lazy<Regex> = Lazy::new(|| {
+/// Digit groups separated by single thousands/decimal separators,
+/// e.g. `12.500`, `1.234,56`, `12,500.00`, `12'500` or `12 500`
+static AMOUNT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\d+(?:[.,'’\u{a0} ]\d+)*")
+        .expect("Invalid price amount regex")
+});
+
+/// Amounts as earlier releases matched them for listing ids: up to three
+/// digits, `.`/`,` thousands groups and optional two decimals
+static LEGACY_AMOUNT_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"(\d{1,3}(?:[.,]\d{3})*(?:[.,]\d{2})?)")
-        .expect("Invalid price regex")
+        .expect("Invalid legacy price regex")
 });
 
-/// Extract price string for hashing (normalized format)
-pub fn get_price_string_for_hash(price_text: &str) -> String {
-    if let Some(captures) = PRICE_REGEX.find(price_text) {
-        let price_str = captures.as_str();
-        // Normalize to use dots for thousands and comma for decimal
-        price_str
-            .replace('.', "")
-            .replace(',', ".")
-            .trim()
-            .to_string()
-    } else {
-        String::new()
+/// Phrases dealers use instead of a price
+const PRICE_ON_REQUEST: &[&str] = &["auf anfrage", "on request", "price upon request", "p.o.a", "poa"];
+
+/// Parse a price text such as `1.234,56 €`, `$12,500`, `CHF 12'500.–` or
+/// `12.500,- EUR`. The currency is taken from the text, falling back to
+/// `default_currency`. Returns `None` for prices on request and texts
+/// without an amount.
+pub fn parse_money(price_text: &str, default_currency: Currency) -> Option<Money> {
+    let lower = price_text.to_lowercase();
+    if PRICE_ON_REQUEST.iter().any(|phrase| lower.contains(phrase)) {
+        return None;
     }
+
+    let amount = AMOUNT_REGEX.find(price_text)?.as_str();
+    let currency = detect_currency(price_text).unwrap_or(default_currency);
+
+    Some(Money::from_major(parse_amount(amount)?, currency))
 }
 
-/// Format price for display with EUR symbol
-pub fn format_price_eur_display(price_text: &str) -> String {
-    if price_text.trim().is_empty() {
-        return EMOJI_QUESTION.to_string();
+/// Price part of composite listing ids as earlier releases derived it from
+/// the price text: the first amount with `.` dropped and `,` turned into
+/// `.`, so `12.500 €` gives `12500` but `$12,500` gives `12.500`. Kept as is
+/// so that listings seen under those ids are still recognised.
+pub fn legacy_price_key(price_text: &str) -> String {
+    match LEGACY_AMOUNT_REGEX.find(price_text) {
+        Some(amount) => amount.as_str().replace('.', "").replace(',', ".").trim().to_string(),
+        None => String::new(),
     }
-    
-    // Extract numeric part
-    if let Some(captures) = PRICE_REGEX.find(price_text) {
-        let price_str = captures.as_str();
-        
-        // Check if already has EUR symbol
-        if price_text.contains('€') || price_text.contains("EUR") {
-            // Keep original formatting but ensure EUR symbol
-            if price_text.contains('€') {
-                price_text.to_string()
-            } else {
-                format!("{} €", price_str)
-            }
-        } else {
-            // Add EUR symbol
-            format!("{} €", price_str)
-        }
+}
+
+fn detect_currency(price_text: &str) -> Option<Currency> {
+    let upper = price_text.to_uppercase();
+
+    // Check prefixed dollars before plain `$`
+    if upper.contains("HK$") || upper.contains("HKD") {
+        Some(Currency::Hkd)
+    } else if upper.contains("CHF") || upper.contains("SFR") {
+        Some(Currency::Chf)
+    } else if upper.contains('$') || upper.contains("USD") {
+        Some(Currency::Usd)
+    } else if upper.contains('£') || upper.contains("GBP") {
+        Some(Currency::Gbp)
+    } else if upper.contains('¥') || upper.contains('円') || upper.contains("JPY") {
+        Some(Currency::Jpy)
+    } else if upper.contains('€') || upper.contains("EUR") {
+        Some(Currency::Eur)
     } else {
-        EMOJI_QUESTION.to_string()
+        None
     }
 }
 
-/// Convert USD price to EUR and format for display
-pub fn convert_usd_to_eur_display(usd_price: f64, exchange_rate: f64) -> String {
-    let eur_price = usd_price * exchange_rate;
-    
-    // Format with thousands separator
-    let formatted = if eur_price >= 1000.0 {
-        let thousands = (eur_price / 1000.0) as i32;
-        let remainder = (eur_price % 1000.0) as i32;
-        if remainder == 0 {
-            format!("{}.000", thousands)
-        } else {
-            format!("{}.{:03}", thousands, remainder)
+/// A final separator followed by one or two digits marks decimals; every
+/// other separator groups thousands
+fn parse_amount(amount: &str) -> Option<f64> {
+    let (integer_part, decimal_part) = match amount.rfind(|c: char| !c.is_ascii_digit()) {
+        Some(idx) if (1..=2).contains(&amount[idx..].chars().skip(1).count()) => {
+            let separator_len = amount[idx..].chars().next()?.len_utf8();
+            (&amount[..idx], &amount[idx + separator_len..])
         }
-    } else {
-        format!("{:.0}", eur_price)
+        _ => (amount, "0"),
     };
-    
-    format!("{} €", formatted)
+
+    let digits: String = integer_part.chars().filter(|c| c.is_ascii_digit()).collect();
+    format!("{}.{}", digits, decimal_part).parse().ok()
 }
 
-/// Parse USD price from text
-pub fn parse_usd_price(price_text: &str) -> Option<f64> {
-    // Remove currency symbols and clean
-    let cleaned = price_text
-        .replace('$', "")
-        .replace("USD", "")
-        .replace(',', "")
-        .trim()
-        .to_string();
-    
-    cleaned.parse::<f64>().ok()
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_prices_in_local_formats() {
+        let cases = [
+            ("1.234,56 €", Some(Money::new(123456, Currency::Eur))),
+            ("€ 1,234.56", Some(Money::new(123456, Currency::Eur))),
+            ("$12,500", Some(Money::new(1250000, Currency::Usd))),
+            ("CHF 12'500.–", Some(Money::new(1250000, Currency::Chf))),
+            ("12\u{a0}500 €", Some(Money::new(1250000, Currency::Eur))),
+            ("12.500,- EUR", Some(Money::new(1250000, Currency::Eur))),
+            ("HK$ 98,000", Some(Money::new(9800000, Currency::Hkd))),
+            ("1.250", Some(Money::new(125000, Currency::Gbp))),
+            ("Preis auf Anfrage", None),
+            ("Price on request", None),
+            ("Sold", None),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(parse_money(text, Currency::Gbp), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn keeps_the_legacy_price_key_format() {
+        let cases = [
+            ("12.500 €", "12500"),
+            ("1.234,56 €", "1234.56"),
+            ("12.500,- EUR", "12500"),
+            ("$12,500", "12.500"),
+            ("$38,000", "38.000"),
+            ("Preis auf Anfrage", ""),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(legacy_price_key(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn detects_currencies_by_symbol_or_code() {
+        let cases = [
+            ("1.234,56 €", Some(Currency::Eur)),
+            ("12500 EUR", Some(Currency::Eur)),
+            ("$12,500", Some(Currency::Usd)),
+            ("HK$98,000", Some(Currency::Hkd)),
+            ("CHF 12'500.–", Some(Currency::Chf)),
+            ("sFr. 12'500", Some(Currency::Chf)),
+            ("£9,995", Some(Currency::Gbp)),
+            ("¥1,500,000", Some(Currency::Jpy)),
+            ("12.500", None),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(detect_currency(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn separates_decimals_from_thousands() {
+        let cases = [
+            ("1.234,56", 1234.56),
+            ("1,234.56", 1234.56),
+            ("12,500", 12500.0),
+            ("12'500", 12500.0),
+            ("12\u{a0}500", 12500.0),
+            ("1.250", 1250.0),
+            ("1.250.000", 1250000.0),
+            ("99,5", 99.5),
+            ("12500", 12500.0),
+        ];
+
+        for (amount, expected) in cases {
+            assert_eq!(parse_amount(amount), Some(expected), "{:?}", amount);
+        }
+    }
+
+    #[test]
+    fn parses_formatted_money_back() {
+        let cases = [
+            Money::new(1250000, Currency::Eur),
+            Money::new(123456, Currency::Eur),
+            Money::new(1250050, Currency::Chf),
+            Money::new(1250000, Currency::Usd),
+            Money::new(99, Currency::Usd),
+        ];
+
+        for money in cases {
+            assert_eq!(parse_money(&money.to_string(), Currency::Gbp), Some(money), "{}", money);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::config::Config;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use tokio::time::interval;
+use tracing::{error, info};
+
+/// How often the configuration file's modification time is checked
+const FILE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadTrigger {
+    FileChanged,
+    Signal,
+}
+
+impl std::fmt::Display for ReloadTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReloadTrigger::FileChanged => write!(f, "configuration file changed"),
+            ReloadTrigger::Signal => write!(f, "SIGHUP received"),
+        }
+    }
+}
+
+/// Start watching the configuration file (and SIGHUP on Unix) and report
+/// every event that should cause the configuration to be reloaded
+pub fn spawn_reload_triggers(path: PathBuf) -> mpsc::Receiver<ReloadTrigger> {
+    let (tx, rx) = mpsc::channel(4);
+
+    let last_modified = modified_time(&path);
+    tokio::spawn(watch_file(path, last_modified, tx.clone()));
+
+    // Installed before returning so that no SIGHUP is missed (or, without a
+    // handler, terminates the process) once the caller relies on it
+    #[cfg(unix)]
+    match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(hangup) => {
+            info!("Send SIGHUP to reload the configuration");
+            tokio::spawn(watch_sighup(hangup, tx));
+        }
+        Err(e) => error!("Failed to install SIGHUP handler: {}", e),
+    }
+
+    rx
+}
+
+/// Wait for a reload trigger that yields a valid, changed configuration.
+/// Invalid configurations are logged and the current one stays active.
+pub async fn wait_for_new_config(
+    path: &Path,
+    current: &Config,
+    triggers: &mut mpsc::Receiver<ReloadTrigger>,
+) -> Option<Config> {
+    while let Some(trigger) = triggers.recv().await {
+        info!("Reloading configuration from {} ({})", path.display(), trigger);
+
+        let new_config = match Config::load(path) {
+            Ok(new_config) => new_config,
+            Err(e) => {
+                error!("Rejected new configuration, keeping the current one: {:#}", e);
+                continue;
+            }
+        };
+
+        let changes = current.diff(&new_config);
+        if changes.is_empty() {
+            info!("Configuration unchanged");
+            continue;
+        }
+
+        for change in &changes {
+            info!("Config change: {}", change);
+        }
+        return Some(new_config);
+    }
+
+    None
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+async fn watch_file(path: PathBuf, mut last_modified: Option<SystemTime>, tx: mpsc::Sender<ReloadTrigger>) {
+    let mut ticker = interval(FILE_POLL_INTERVAL);
+
+    loop {
+        ticker.tick().await;
+
+        let current = modified_time(&path);
+        if current.is_some() && current != last_modified {
+            last_modified = current;
+            if tx.send(ReloadTrigger::FileChanged).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn watch_sighup(mut hangup: tokio::signal::unix::Signal, tx: mpsc::Sender<ReloadTrigger>) {
+    while hangup.recv().await.is_some() {
+        if tx.send(ReloadTrigger::Signal).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tokio::time::timeout;
+
+    const CONFIG: &str = r#"
+check_interval_seconds = 60
+
+[sites.watch_out]
+url = "https://www.watch-out.shop/collections/uhren"
+webhook = "https://discord.com/api/webhooks/1/token"
+name = "Watch Out"
+color = 3092790
+base_url = "https://www.watch-out.shop"
+"#;
+
+    fn config_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("watch-monitor-reload-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn reports_changes_to_the_file() {
+        let path = config_file("watch", CONFIG);
+        let (tx, mut rx) = mpsc::channel(4);
+        tokio::spawn(watch_file(path.clone(), modified_time(&path), tx));
+
+        // Unchanged on the first poll
+        assert!(timeout(Duration::from_millis(100), rx.recv()).await.is_err());
+
+        let later = modified_time(&path).unwrap() + Duration::from_secs(10);
+        File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+        let trigger = timeout(FILE_POLL_INTERVAL * 3, rx.recv()).await.unwrap();
+        assert_eq!(trigger, Some(ReloadTrigger::FileChanged));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn reports_sighup() {
+        let path = config_file("sighup", CONFIG);
+        let mut triggers = spawn_reload_triggers(path.clone());
+
+        let status = std::process::Command::new("kill")
+            .args(["-HUP", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let trigger = timeout(Duration::from_secs(5), triggers.recv()).await.unwrap();
+        assert_eq!(trigger, Some(ReloadTrigger::Signal));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn keeps_the_current_config_until_a_valid_change_arrives() {
+        let path = config_file("load", CONFIG);
+        let current = Config::load(&path).unwrap();
+        let reload = |contents: &str| {
+            std::fs::write(&path, contents).unwrap();
+            let (tx, rx) = mpsc::channel(1);
+            tx.try_send(ReloadTrigger::FileChanged).unwrap();
+            rx
+        };
+
+        let mut triggers = reload(&CONFIG.replace("60", "\"soon\""));
+        assert!(wait_for_new_config(&path, &current, &mut triggers).await.is_none());
+
+        let mut triggers = reload(CONFIG);
+        assert!(wait_for_new_config(&path, &current, &mut triggers).await.is_none());
+
+        let mut triggers = reload(&CONFIG.replace("60", "120"));
+        let new_config = wait_for_new_config(&path, &current, &mut triggers).await.unwrap();
+        assert_eq!(new_config.check_interval_seconds, 120);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
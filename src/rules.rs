@@ -0,0 +1,345 @@
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::config::{validate_http_url, ConfigError};
+use crate::models::{BoxStatus, PapersStatus, Site, WatchListing, EMOJI_QUESTION};
+use crate::notify::{self, NotifierConfig};
+use crate::utils::secret::Secret;
+
+static DIAMETER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(\d{1,2}(?:[.,]\d{1,2})?)\s*mm")
+        .expect("Invalid diameter regex")
+});
+
+/// A watchlist entry: listings matching every configured criterion are sent
+/// to the rule's own targets. Criteria left out match anything; a criterion
+/// that is set never matches a listing where that detail is unknown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchRule {
+    pub name: String,
+    /// Discord webhook URL, inline or as an `env:`/`file:` reference;
+    /// shorthand for a Discord entry in `notifiers`
+    #[serde(default)]
+    pub webhook: Option<Secret>,
+    /// Where matches are sent, besides `webhook`
+    #[serde(default)]
+    pub notifiers: Vec<NotifierConfig>,
+    /// Discord role ID to mention with every match
+    #[serde(default)]
+    pub mention_role: Option<String>,
+    /// Any of these brands (case-insensitive)
+    #[serde(default)]
+    pub brands: Vec<String>,
+    /// Case-insensitive regex matched against the reference
+    #[serde(default)]
+    pub reference: Option<Pattern>,
+    /// Any of these words in the model or title (case-insensitive)
+    #[serde(default)]
+    pub model_keywords: Vec<String>,
+    #[serde(default)]
+    pub min_price_eur: Option<f64>,
+    #[serde(default)]
+    pub max_price_eur: Option<f64>,
+    #[serde(default)]
+    pub min_year: Option<u16>,
+    #[serde(default)]
+    pub max_year: Option<u16>,
+    #[serde(default)]
+    pub box_required: bool,
+    #[serde(default)]
+    pub papers_required: bool,
+    /// Any of these words in the condition, e.g. "mint" or "★★★★"
+    #[serde(default)]
+    pub conditions: Vec<String>,
+    #[serde(default)]
+    pub min_diameter_mm: Option<f64>,
+    #[serde(default)]
+    pub max_diameter_mm: Option<f64>,
+    /// Site keys the rule applies to; all sites if empty
+    #[serde(default)]
+    pub sites: Vec<String>,
+}
+
+/// Regex written as a string in the configuration file
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern(Regex);
+
+impl TryFrom<String> for Pattern {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        RegexBuilder::new(&value)
+            .case_insensitive(true)
+            .build()
+            .map(Pattern)
+            .map_err(|e| format!("invalid regex `{}`: {}", value, e))
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.0.as_str().to_string()
+    }
+}
+
+impl fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pattern({:?})", self.0.as_str())
+    }
+}
+
+impl WatchRule {
+    pub fn matches(&self, site: &Site, listing: &WatchListing) -> bool {
+        if !self.sites.is_empty() && !self.sites.iter().any(|key| key == site.key()) {
+            return false;
+        }
+
+        if !self.brands.is_empty() && !self.brands.iter().any(|brand| brand.eq_ignore_ascii_case(listing.brand.trim())) {
+            return false;
+        }
+
+        if let Some(pattern) = &self.reference {
+            if !known(&listing.reference) || !pattern.0.is_match(&listing.reference) {
+                return false;
+            }
+        }
+
+        if !self.model_keywords.is_empty() {
+            let text = format!("{} {}", listing.model, listing.title).to_lowercase();
+            if !self.model_keywords.iter().any(|keyword| text.contains(&keyword.to_lowercase())) {
+                return false;
+            }
+        }
+
+        if self.min_price_eur.is_some() || self.max_price_eur.is_some() {
+            match listing.price_eur.map(|price| price.to_major()) {
+                Some(price) if in_range(price, self.min_price_eur, self.max_price_eur) => {}
+                _ => return false,
+            }
+        }
+
+        if self.min_year.is_some() || self.max_year.is_some() {
+            match listing.year.trim().parse::<u16>() {
+                Ok(year) if in_range(year, self.min_year, self.max_year) => {}
+                _ => return false,
+            }
+        }
+
+        if self.box_required && listing.box_status != BoxStatus::Yes {
+            return false;
+        }
+
+        if self.papers_required && listing.papers_status != PapersStatus::Yes {
+            return false;
+        }
+
+        if !self.conditions.is_empty() {
+            let condition = listing.condition_display.to_lowercase();
+            if !known(&condition) || !self.conditions.iter().any(|c| condition.contains(&c.to_lowercase())) {
+                return false;
+            }
+        }
+
+        if self.min_diameter_mm.is_some() || self.max_diameter_mm.is_some() {
+            match parse_diameter_mm(&listing.diameter) {
+                Some(diameter) if in_range(diameter, self.min_diameter_mm, self.max_diameter_mm) => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+
+    /// Every target matches of this rule go to
+    pub fn targets(&self) -> Vec<NotifierConfig> {
+        notify::targets(self.webhook.as_ref(), &self.notifiers)
+    }
+
+    /// Discord mention for the rule's role, if any
+    pub fn mention(&self) -> Option<String> {
+        self.mention_role.as_ref().map(|role| format!("<@&{}>", role))
+    }
+
+    pub fn validate(&self, index: usize) -> Result<(), ConfigError> {
+        let invalid = |key: &str, reason: String| ConfigError::InvalidRuleField {
+            rule: if self.name.trim().is_empty() { format!("#{}", index + 1) } else { self.name.clone() },
+            key: key.to_string(),
+            reason,
+        };
+
+        if self.name.trim().is_empty() {
+            return Err(invalid("name", "must not be empty".to_string()));
+        }
+
+        if let Some(role) = &self.mention_role {
+            if role.is_empty() || !role.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid("mention_role", format!("`{}` is not a Discord role ID", role)));
+            }
+        }
+
+        if let Some(webhook) = &self.webhook {
+            validate_http_url(webhook.expose()).map_err(|reason| invalid("webhook", reason))?;
+        }
+
+        if self.webhook.is_none() && self.notifiers.is_empty() {
+            return Err(invalid("notifiers", "set `webhook` or at least one notifier".to_string()));
+        }
+
+        for (index, notifier) in self.notifiers.iter().enumerate() {
+            notifier
+                .validate()
+                .map_err(|(key, reason)| invalid(&format!("notifiers[{}].{}", index, key), reason))?;
+        }
+
+        for site_key in &self.sites {
+            if Site::from_key(site_key).is_none() {
+                return Err(invalid("sites", format!("unknown site `{}`", site_key)));
+            }
+        }
+
+        let ranges = [
+            ("price_eur", self.min_price_eur, self.max_price_eur),
+            ("year", self.min_year.map(f64::from), self.max_year.map(f64::from)),
+            ("diameter_mm", self.min_diameter_mm, self.max_diameter_mm),
+        ];
+        for (key, min, max) in ranges {
+            if let (Some(min), Some(max)) = (min, max) {
+                if min > max {
+                    return Err(invalid(&format!("min_{}", key), format!("{} is greater than max_{} ({})", min, key, max)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn known(value: &str) -> bool {
+    let value = value.trim();
+    !value.is_empty() && value != EMOJI_QUESTION
+}
+
+fn in_range<T: PartialOrd>(value: T, min: Option<T>, max: Option<T>) -> bool {
+    min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+}
+
+fn parse_diameter_mm(diameter: &str) -> Option<f64> {
+    DIAMETER_REGEX
+        .captures(diameter)
+        .and_then(|cap| cap[1].replace(',', ".").parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Currency, Money};
+    use serde_json::json;
+
+    fn rule(criteria: serde_json::Value) -> WatchRule {
+        let mut rule = json!({ "name": "Sports", "webhook": "https://discord.com/api/webhooks/1/token" });
+        rule.as_object_mut().unwrap().extend(criteria.as_object().unwrap().clone());
+        serde_json::from_value(rule).unwrap()
+    }
+
+    fn listing() -> WatchListing {
+        WatchListing {
+            title: "Rolex Submariner Date".to_string(),
+            brand: "Rolex".to_string(),
+            model: "Submariner Date".to_string(),
+            reference: "126610LN".to_string(),
+            year: "2021".to_string(),
+            condition_display: "Sehr gut".to_string(),
+            diameter: "41 mm".to_string(),
+            box_status: BoxStatus::Yes,
+            papers_status: PapersStatus::No,
+            price_eur: Some(Money::new(1250000, Currency::Eur)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matches_listings_on_every_criterion() {
+        let cases = [
+            (json!({}), true),
+            (json!({ "brands": ["omega", "ROLEX"] }), true),
+            (json!({ "brands": ["Omega"] }), false),
+            (json!({ "reference": "^1266\\d0ln$" }), true),
+            (json!({ "reference": "^116610" }), false),
+            (json!({ "model_keywords": ["SUBMARINER"] }), true),
+            (json!({ "model_keywords": ["daytona", "gmt"] }), false),
+            (json!({ "min_price_eur": 10000.0, "max_price_eur": 12500.0 }), true),
+            (json!({ "min_price_eur": 12500.01 }), false),
+            (json!({ "max_price_eur": 9999.0 }), false),
+            (json!({ "min_year": 2020, "max_year": 2021 }), true),
+            (json!({ "max_year": 2019 }), false),
+            (json!({ "box_required": true }), true),
+            (json!({ "papers_required": true }), false),
+            (json!({ "conditions": ["SEHR GUT"] }), true),
+            (json!({ "min_diameter_mm": 40.0, "max_diameter_mm": 41.0 }), true),
+            (json!({ "max_diameter_mm": 40.0 }), false),
+            (json!({ "sites": ["grimmeissen"] }), true),
+            (json!({ "sites": ["watchout"] }), false),
+        ];
+
+        for (criteria, expected) in cases {
+            assert_eq!(rule(criteria.clone()).matches(&Site::Grimmeissen, &listing()), expected, "{}", criteria);
+        }
+    }
+
+    #[test]
+    fn unknown_details_never_match_a_set_criterion() {
+        let unknown = WatchListing {
+            reference: EMOJI_QUESTION.to_string(),
+            year: EMOJI_QUESTION.to_string(),
+            price_eur: None,
+            ..listing()
+        };
+
+        assert!(!rule(json!({ "reference": ".*" })).matches(&Site::Grimmeissen, &unknown));
+        assert!(!rule(json!({ "min_year": 1900 })).matches(&Site::Grimmeissen, &unknown));
+        assert!(!rule(json!({ "max_price_eur": 1000000.0 })).matches(&Site::Grimmeissen, &unknown));
+    }
+
+    #[test]
+    fn validation_rejects_inverted_ranges() {
+        for (criteria, key) in [
+            (json!({ "min_price_eur": 20000.0, "max_price_eur": 10000.0 }), "min_price_eur"),
+            (json!({ "min_year": 2021, "max_year": 1990 }), "min_year"),
+            (json!({ "min_diameter_mm": 42.0, "max_diameter_mm": 36.5 }), "min_diameter_mm"),
+        ] {
+            match rule(criteria).validate(0) {
+                Err(ConfigError::InvalidRuleField { rule, key: field, .. }) => {
+                    assert_eq!(rule, "Sports");
+                    assert_eq!(field, key);
+                }
+                other => panic!("expected an invalid {}, got {:?}", key, other),
+            }
+        }
+
+        // A single value is a valid range
+        assert!(rule(json!({ "min_year": 2021, "max_year": 2021 })).validate(0).is_ok());
+    }
+
+    #[test]
+    fn validation_rejects_incomplete_rules() {
+        assert!(matches!(
+            rule(json!({ "name": " " })).validate(2),
+            Err(ConfigError::InvalidRuleField { rule, key, .. }) if rule == "#3" && key == "name"
+        ));
+        assert!(matches!(
+            rule(json!({ "webhook": null })).validate(0),
+            Err(ConfigError::InvalidRuleField { key, .. }) if key == "notifiers"
+        ));
+        assert!(matches!(
+            rule(json!({ "sites": ["nowhere"] })).validate(0),
+            Err(ConfigError::InvalidRuleField { key, .. }) if key == "sites"
+        ));
+        assert!(matches!(
+            rule(json!({ "mention_role": "@here" })).validate(0),
+            Err(ConfigError::InvalidRuleField { key, .. }) if key == "mention_role"
+        ));
+    }
+}
@@ -1,18 +1,17 @@
 use async_trait::async_trait;
 use anyhow::Result;
-use reqwest::Client;
 use scraper::{Html, Selector};
 use std::sync::Arc;
 use tracing::{error, info};
 use url::Url;
 
 use crate::config::{Config, SiteConfig};
-use crate::models::{Site, WatchListing};
-use crate::parsers::{clean_text, format_price_eur_display, get_price_string_for_hash, 
+use crate::models::{Currency, Money, Site, WatchListing};
+use crate::parsers::{clean_text, legacy_price_key, parse_money, 
                       parse_year_from_string, parse_box_papers_status, get_condition_display,
                       extract_reference, parse_table_th_td};
 use crate::scrapers::WatchScraper;
-use crate::utils::http::fetch_with_retry;
+use crate::utils::http::{fetch_with_retry, HttpClient};
 
 pub struct GrimmeissenScraper {
     config: Arc<Config>,
@@ -24,20 +23,20 @@ impl GrimmeissenScraper {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 struct WatchData {
     url: String,
     title: String,
     brand: String,
     model: String,
-    price_raw: String,
-    price_display: String,
+    price: Option<Money>,
+    legacy_price_key: String,
     image_url: String,
 }
 
 #[async_trait]
 impl WatchScraper for GrimmeissenScraper {
-    async fn scrape(&self, client: &Client) -> Result<Vec<WatchListing>> {
+    async fn scrape(&self, client: &HttpClient) -> Result<Vec<WatchListing>> {
         let site_config = self.site_config();
         info!("Scraping Grimmeissen...");
         
@@ -45,7 +44,7 @@ impl WatchScraper for GrimmeissenScraper {
         let html = response.text().await?;
         
         // Extract all data synchronously
-        let watch_data = extract_watch_data(&html, &site_config.base_url)?;
+        let watch_data = extract_watch_data(&html, &site_config.base_url, site_config.currency)?;
         
         info!("Found {} watch items on Grimmeissen listing page", watch_data.len());
         
@@ -73,7 +72,7 @@ impl WatchScraper for GrimmeissenScraper {
     }
 }
 
-fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
+fn extract_watch_data(html: &str, base_url: &str, currency: Currency) -> Result<Vec<WatchData>> {
     let document = Html::parse_document(html);
     let watch_selector = Selector::parse("article.watch")
         .map_err(|_| anyhow::anyhow!("Failed to parse watch selector"))?;
@@ -86,8 +85,8 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
             title: String::new(),
             brand: String::new(),
             model: String::new(),
-            price_raw: String::new(),
-            price_display: String::new(),
+            price: None,
+            legacy_price_key: String::new(),
             image_url: String::new(),
         };
         
@@ -137,8 +136,8 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
         if let Ok(price_selector) = Selector::parse("section.fh p") {
             if let Some(price_elem) = element.select(&price_selector).next() {
                 let price_text = clean_text(&price_elem.text().collect::<String>());
-                data.price_raw = get_price_string_for_hash(&price_text);
-                data.price_display = format_price_eur_display(&price_text);
+                data.price = parse_money(&price_text, currency);
+                data.legacy_price_key = legacy_price_key(&price_text);
             }
         }
         
@@ -152,7 +151,7 @@ impl GrimmeissenScraper {
     async fn process_watch(
         &self,
         data: WatchData,
-        client: &Client,
+        client: &HttpClient,
         site_config: &SiteConfig,
     ) -> Result<WatchListing> {
         let mut watch = WatchListing {
@@ -162,8 +161,8 @@ impl GrimmeissenScraper {
             title: data.title,
             brand: data.brand,
             model: data.model,
-            price_eur_raw_for_hash: data.price_raw,
-            price_eur_display: data.price_display,
+            price: data.price,
+            legacy_price_key: data.legacy_price_key,
             ..Default::default()
         };
         
@@ -277,4 +276,129 @@ fn parse_detail_page(html: &str, watch: &mut WatchListing) {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::MockServer;
+
+    use crate::models::{BoxStatus, PapersStatus};
+    use crate::scrapers::test_support::{client, config_for, mount_page};
+
+    const LISTING: &str = include_str!("../../tests/fixtures/grimmeissen/listing.html");
+    const DETAIL_DAYTONA: &str = include_str!("../../tests/fixtures/grimmeissen/detail-daytona.html");
+    const DETAIL_SPEEDMASTER: &str = include_str!("../../tests/fixtures/grimmeissen/detail-speedmaster.html");
+
+    const BASE_URL: &str = "https://www.grimmeissen.de";
+
+    #[test]
+    fn extracts_listing_cards() {
+        let data = extract_watch_data(LISTING, BASE_URL, Currency::Eur).unwrap();
+
+        assert_eq!(
+            data,
+            vec![
+                WatchData {
+                    url: format!("{}/de/uhren/rolex-daytona-116500ln", BASE_URL),
+                    title: "Rolex Daytona".to_string(),
+                    brand: "Rolex".to_string(),
+                    model: "Daytona".to_string(),
+                    price: Some(Money::new(2_490_000, Currency::Eur)),
+                    legacy_price_key: "24900".to_string(),
+                    image_url: format!("{}/media/uhren/daytona-116500ln-thumb.jpg", BASE_URL),
+                },
+                WatchData {
+                    url: format!("{}/de/uhren/omega-speedmaster-professional", BASE_URL),
+                    title: "Omega Speedmaster Professional".to_string(),
+                    brand: "Omega".to_string(),
+                    model: "Speedmaster Professional".to_string(),
+                    price: None,
+                    legacy_price_key: String::new(),
+                    image_url: format!("{}/media/uhren/speedmaster-thumb.jpg", BASE_URL),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_detail_page() {
+        let mut watch = WatchListing::default();
+        parse_detail_page(DETAIL_DAYTONA, &mut watch);
+
+        assert_eq!(watch, daytona(""));
+    }
+
+    #[test]
+    fn parses_detail_page_without_year() {
+        let mut watch = WatchListing::default();
+        parse_detail_page(DETAIL_SPEEDMASTER, &mut watch);
+
+        assert_eq!(watch, speedmaster(""));
+    }
+
+    #[tokio::test]
+    async fn scrapes_listing_and_detail_pages() {
+        let server = MockServer::start().await;
+        mount_page(&server, "/de/uhren", LISTING).await;
+        mount_page(&server, "/de/uhren/rolex-daytona-116500ln", DETAIL_DAYTONA).await;
+        mount_page(&server, "/de/uhren/omega-speedmaster-professional", DETAIL_SPEEDMASTER).await;
+
+        let config = config_for(&Site::Grimmeissen, "Grimmeissen", &server, "/de/uhren", Currency::Eur);
+        let listings = GrimmeissenScraper::new(config).scrape(&client()).await.unwrap();
+
+        let base_url = server.uri();
+        assert_eq!(
+            listings,
+            vec![
+                WatchListing {
+                    price: Some(Money::new(2_490_000, Currency::Eur)),
+                    legacy_price_key: "24900".to_string(),
+                    watch_url: format!("{}/de/uhren/rolex-daytona-116500ln", base_url),
+                    image_url: format!("{}/media/uhren/daytona-116500ln-thumb.jpg", base_url),
+                    ..daytona("Grimmeissen")
+                },
+                WatchListing {
+                    watch_url: format!("{}/de/uhren/omega-speedmaster-professional", base_url),
+                    image_url: format!("{}/media/uhren/speedmaster-thumb.jpg", base_url),
+                    ..speedmaster("Grimmeissen")
+                },
+            ]
+        );
+    }
+
+    /// What the Daytona detail page contributes to a listing
+    fn daytona(site_name: &str) -> WatchListing {
+        WatchListing {
+            brand: "Rolex".to_string(),
+            model: "Cosmograph Daytona".to_string(),
+            reference: "116500LN".to_string(),
+            year: "2019".to_string(),
+            papers_status: PapersStatus::Yes,
+            box_status: BoxStatus::Yes,
+            condition_display: "Very Good".to_string(),
+            case_material: "Edelstahl".to_string(),
+            diameter: "40 mm".to_string(),
+            title: "Rolex Cosmograph Daytona".to_string(),
+            site_name: site_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// What the Speedmaster detail page contributes to a listing
+    fn speedmaster(site_name: &str) -> WatchListing {
+        WatchListing {
+            brand: "Omega".to_string(),
+            model: "Speedmaster Professional Moonwatch".to_string(),
+            reference: "3570.50.00".to_string(),
+            papers_status: PapersStatus::Yes,
+            box_status: BoxStatus::No,
+            condition_display: "Like New".to_string(),
+            case_material: "Stahl".to_string(),
+            diameter: "42 mm".to_string(),
+            title: "Omega Speedmaster Professional Moonwatch".to_string(),
+            site_name: site_name.to_string(),
+            ..Default::default()
+        }
+    }
+}
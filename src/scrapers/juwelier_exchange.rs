@@ -1,7 +1,6 @@
 use async_trait::async_trait;
 use anyhow::Result;
 use regex::Regex;
-use reqwest::Client;
 use scraper::{Html, Selector};
 use serde_json::Value;
 use std::sync::Arc;
@@ -9,11 +8,11 @@ use tracing::{error, info};
 use url::Url;
 
 use crate::config::{Config, SiteConfig};
-use crate::models::{Site, WatchListing, BoxStatus, PapersStatus};
-use crate::parsers::{clean_text, format_price_eur_display, get_price_string_for_hash, 
+use crate::models::{Currency, Money, Site, WatchListing, BoxStatus, PapersStatus};
+use crate::parsers::{clean_text, legacy_price_key, parse_money, 
                       parse_year_from_string, parse_box_papers_status, get_condition_display};
 use crate::scrapers::WatchScraper;
-use crate::utils::http::fetch_with_retry;
+use crate::utils::http::{fetch_with_retry, HttpClient};
 
 pub struct JuwelierExchangeScraper {
     config: Arc<Config>,
@@ -25,15 +24,15 @@ impl JuwelierExchangeScraper {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 struct WatchData {
     url: String,
     image_url: String,
-    price_raw: String,
-    price_display: String,
+    price: Option<Money>,
+    legacy_price_key: String,
 }
 
-#[derive(Default)]
+#[derive(Debug, Default, PartialEq)]
 struct DetailPageData {
     title: String,
     brand: String,
@@ -50,7 +49,7 @@ struct DetailPageData {
 
 #[async_trait]
 impl WatchScraper for JuwelierExchangeScraper {
-    async fn scrape(&self, client: &Client) -> Result<Vec<WatchListing>> {
+    async fn scrape(&self, client: &HttpClient) -> Result<Vec<WatchListing>> {
         let site_config = self.site_config();
         info!("Scraping Juwelier Exchange...");
         
@@ -58,7 +57,7 @@ impl WatchScraper for JuwelierExchangeScraper {
         let html = response.text().await?;
         
         // Extract all data synchronously
-        let watch_data = extract_watch_data(&html, &site_config.base_url)?;
+        let watch_data = extract_watch_data(&html, &site_config.base_url, site_config.currency)?;
         
         info!("Found {} watch items (product cards) on Juwelier Exchange listing page", watch_data.len());
         
@@ -86,7 +85,7 @@ impl WatchScraper for JuwelierExchangeScraper {
     }
 }
 
-fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
+fn extract_watch_data(html: &str, base_url: &str, currency: Currency) -> Result<Vec<WatchData>> {
     let document = Html::parse_document(html);
     let card_selector = Selector::parse("div.card.product-box[data-product-information]")
         .map_err(|_| anyhow::anyhow!("Failed to parse card selector"))?;
@@ -97,8 +96,8 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
         let mut data = WatchData {
             url: String::new(),
             image_url: String::new(),
-            price_raw: String::new(),
-            price_display: String::new(),
+            price: None,
+            legacy_price_key: String::new(),
         };
         
         // Extract link
@@ -121,7 +120,7 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
                 if !srcset.is_empty() {
                     // Parse srcset and prefer higher resolution webp
                     let potential_srcs: Vec<&str> = srcset.split(',')
-                        .map(|s| s.trim().split_whitespace().next().unwrap_or(""))
+                        .map(|s| s.split_whitespace().next().unwrap_or(""))
                         .collect();
                     
                     let mut best_src = img.value().attr("src").unwrap_or("");
@@ -159,8 +158,8 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
         if let Ok(price_selector) = Selector::parse("span.product-price") {
             if let Some(price_elem) = element.select(&price_selector).next() {
                 let price_text = clean_text(&price_elem.text().collect::<String>());
-                data.price_raw = get_price_string_for_hash(&price_text);
-                data.price_display = format_price_eur_display(&price_text);
+                data.price = parse_money(&price_text, currency);
+                data.legacy_price_key = legacy_price_key(&price_text);
             }
         }
         
@@ -174,15 +173,15 @@ impl JuwelierExchangeScraper {
     async fn process_watch(
         &self,
         data: WatchData,
-        client: &Client,
+        client: &HttpClient,
         site_config: &SiteConfig,
     ) -> Result<WatchListing> {
         let mut watch = WatchListing {
             site_name: site_config.name.clone(),
             watch_url: data.url.clone(),
             image_url: data.image_url,
-            price_eur_raw_for_hash: data.price_raw,
-            price_eur_display: data.price_display,
+            price: data.price,
+            legacy_price_key: data.legacy_price_key,
             ..Default::default()
         };
         
@@ -309,33 +308,28 @@ fn parse_detail_page(html: &str) -> DetailPageData {
                             let value = clean_text(&value_elem.text().collect::<String>());
                             
                             match label.as_str() {
-                                "artikelnummer" => {
-                                    if details.reference.is_empty() || details.reference == "❓" {
-                                        details.reference = value;
-                                    }
+                                "artikelnummer"
+                                    if details.reference.is_empty() || details.reference == "❓" => {
+                                    details.reference = value;
                                 }
-                                "marke" => {
-                                    if details.brand.is_empty() || details.brand == "❓" {
-                                        details.brand = value;
-                                    }
+                                "marke"
+                                    if details.brand.is_empty() || details.brand == "❓" => {
+                                    details.brand = value;
                                 }
-                                "zustand" => {
-                                    if details.condition_text.is_empty() || details.condition_text == "❓" {
-                                        details.condition_text = value;
-                                    }
+                                "zustand"
+                                    if details.condition_text.is_empty() || details.condition_text == "❓" => {
+                                    details.condition_text = value;
                                 }
                                 "art der legierung" => {
                                     details.case_material = value;
                                 }
-                                "legierung" => {
-                                    if value.chars().all(|c| c.is_numeric()) && !details.case_material.is_empty() {
-                                        details.case_material = format!("{} {}", value, details.case_material);
-                                    }
+                                "legierung"
+                                    if value.chars().all(|c| c.is_numeric()) && !details.case_material.is_empty() => {
+                                    details.case_material = format!("{} {}", value, details.case_material);
                                 }
-                                "material" => {
-                                    if details.case_material.is_empty() || details.case_material == "❓" {
-                                        details.case_material = value;
-                                    }
+                                "material"
+                                    if details.case_material.is_empty() || details.case_material == "❓" => {
+                                    details.case_material = value;
                                 }
                                 _ => {}
                             }
@@ -392,4 +386,141 @@ fn parse_detail_page(html: &str) -> DetailPageData {
     }
     
     details
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::MockServer;
+
+    use crate::scrapers::test_support::{client, config_for, mount_page};
+
+    const LISTING: &str = include_str!("../../tests/fixtures/juwelier_exchange/listing.html");
+    const DETAIL_DATEJUST: &str = include_str!("../../tests/fixtures/juwelier_exchange/detail-datejust.html");
+    const DETAIL_BALLON_BLEU: &str = include_str!("../../tests/fixtures/juwelier_exchange/detail-ballon-bleu.html");
+
+    const BASE_URL: &str = "https://www.juwelier-exchange.de";
+
+    #[test]
+    fn extracts_product_cards() {
+        let data = extract_watch_data(LISTING, BASE_URL, Currency::Eur).unwrap();
+
+        assert_eq!(
+            data,
+            vec![
+                WatchData {
+                    url: format!("{}/rolex-datejust-41-126334/JE10234", BASE_URL),
+                    image_url: format!("{}/thumbnail/3c/4d/rolex-datejust-41_1920x1920.webp", BASE_URL),
+                    price: Some(Money::new(1_395_000, Currency::Eur)),
+                    legacy_price_key: "13950.00".to_string(),
+                },
+                WatchData {
+                    url: format!("{}/cartier-ballon-bleu-36-gelbgold/JE10311", BASE_URL),
+                    image_url: format!("{}/media/9a/8b/cartier-ballon-bleu.jpg", BASE_URL),
+                    price: Some(Money::new(745_000, Currency::Eur)),
+                    legacy_price_key: "7450.00".to_string(),
+                },
+                WatchData {
+                    url: String::new(),
+                    image_url: String::new(),
+                    price: None,
+                    legacy_price_key: String::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_json_ld_detail_page() {
+        assert_eq!(
+            parse_detail_page(DETAIL_DATEJUST),
+            DetailPageData {
+                title: "Rolex Datejust 41 126334".to_string(),
+                brand: "Rolex".to_string(),
+                model: "Datejust 41 126334".to_string(),
+                reference: "126334".to_string(),
+                year: "2021".to_string(),
+                condition_text: "Gebraucht".to_string(),
+                case_material: "Edelstahl".to_string(),
+                diameter: "41 mm".to_string(),
+                box_status: "✅".to_string(),
+                papers_status: "✅".to_string(),
+                description_main: "Rolex Datejust 41 mit blauem Zifferblatt, Box und Papiere.".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_properties_table_without_json_ld() {
+        assert_eq!(
+            parse_detail_page(DETAIL_BALLON_BLEU),
+            DetailPageData {
+                title: "Cartier Ballon Bleu 36 mm Gelbgold".to_string(),
+                brand: "Cartier".to_string(),
+                model: "Ballon Bleu 36".to_string(),
+                reference: "WGBB0043".to_string(),
+                year: "❓".to_string(),
+                condition_text: "Neu".to_string(),
+                case_material: "750 Gelbgold".to_string(),
+                diameter: "36 mm".to_string(),
+                box_status: "❌".to_string(),
+                papers_status: "✅".to_string(),
+                description_main: "Cartier Ballon Bleu in 750 Gelbgold mit Automatikwerk. Ohne Box, mit Papieren. Durchmesser 36 mm."
+                    .to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn scrapes_listing_and_detail_pages() {
+        let server = MockServer::start().await;
+        mount_page(&server, "/uhren/", LISTING).await;
+        mount_page(&server, "/rolex-datejust-41-126334/JE10234", DETAIL_DATEJUST).await;
+        mount_page(&server, "/cartier-ballon-bleu-36-gelbgold/JE10311", DETAIL_BALLON_BLEU).await;
+
+        let config = config_for(&Site::JuwelierExchange, "Juwelier Exchange", &server, "/uhren/", Currency::Eur);
+        let listings = JuwelierExchangeScraper::new(config).scrape(&client()).await.unwrap();
+
+        let base_url = server.uri();
+        assert_eq!(
+            listings,
+            vec![
+                WatchListing {
+                    brand: "Rolex".to_string(),
+                    model: "Datejust 41 126334".to_string(),
+                    reference: "126334".to_string(),
+                    year: "2021".to_string(),
+                    price: Some(Money::new(1_395_000, Currency::Eur)),
+                    legacy_price_key: "13950.00".to_string(),
+                    papers_status: PapersStatus::Yes,
+                    box_status: BoxStatus::Yes,
+                    condition_display: "Gebraucht".to_string(),
+                    case_material: "Edelstahl".to_string(),
+                    diameter: "41 mm".to_string(),
+                    title: "Rolex Datejust 41 126334".to_string(),
+                    watch_url: format!("{}/rolex-datejust-41-126334/JE10234", base_url),
+                    image_url: format!("{}/thumbnail/3c/4d/rolex-datejust-41_1920x1920.webp", base_url),
+                    site_name: "Juwelier Exchange".to_string(),
+                    ..Default::default()
+                },
+                WatchListing {
+                    brand: "Cartier".to_string(),
+                    model: "Ballon Bleu 36".to_string(),
+                    reference: "WGBB0043".to_string(),
+                    price: Some(Money::new(745_000, Currency::Eur)),
+                    legacy_price_key: "7450.00".to_string(),
+                    papers_status: PapersStatus::Yes,
+                    box_status: BoxStatus::No,
+                    condition_display: "Neu".to_string(),
+                    case_material: "750 Gelbgold".to_string(),
+                    diameter: "36 mm".to_string(),
+                    title: "Cartier Ballon Bleu 36 mm Gelbgold".to_string(),
+                    watch_url: format!("{}/cartier-ballon-bleu-36-gelbgold/JE10311", base_url),
+                    image_url: format!("{}/media/9a/8b/cartier-ballon-bleu.jpg", base_url),
+                    site_name: "Juwelier Exchange".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+}
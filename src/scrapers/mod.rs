@@ -1,8 +1,9 @@
 use async_trait::async_trait;
 use anyhow::Result;
-use reqwest::Client;
-use crate::config::SiteConfig;
+use std::sync::Arc;
+use crate::config::{Config, SiteConfig};
 use crate::models::{Site, WatchListing};
+use crate::utils::http::HttpClient;
 
 mod worldoftime;
 mod grimmeissen;
@@ -10,6 +11,8 @@ mod tropicalwatch;
 mod juwelier_exchange;
 mod watch_out;
 mod rueschenbeck;
+#[cfg(test)]
+mod test_support;
 
 pub use worldoftime::WorldOfTimeScraper;
 pub use grimmeissen::GrimmeissenScraper;
@@ -20,7 +23,28 @@ pub use rueschenbeck::RueschenbeckScraper;
 
 #[async_trait]
 pub trait WatchScraper: Send + Sync {
-    async fn scrape(&self, client: &Client) -> Result<Vec<WatchListing>>;
+    async fn scrape(&self, client: &HttpClient) -> Result<Vec<WatchListing>>;
     fn site_config(&self) -> &SiteConfig;
     fn site_key(&self) -> Site;
-}
\ No newline at end of file
+}
+
+/// Build a scraper for every enabled site in the configuration
+pub fn build_scrapers(config: Arc<Config>) -> Vec<Arc<dyn WatchScraper>> {
+    Site::all()
+        .iter()
+        .filter(|site| config.sites.get(site.key()).is_some_and(|s| s.enabled))
+        .map(|site| build_scraper(site, config.clone()))
+        .collect()
+}
+
+/// Build the scraper for `site`, which must be configured under `[sites]`
+pub fn build_scraper(site: &Site, config: Arc<Config>) -> Arc<dyn WatchScraper> {
+    match site {
+        Site::WorldOfTime => Arc::new(WorldOfTimeScraper::new(config)),
+        Site::Grimmeissen => Arc::new(GrimmeissenScraper::new(config)),
+        Site::TropicalWatch => Arc::new(TropicalWatchScraper::new(config)),
+        Site::JuwelierExchange => Arc::new(JuwelierExchangeScraper::new(config)),
+        Site::WatchOut => Arc::new(WatchOutScraper::new(config)),
+        Site::Rueschenbeck => Arc::new(RueschenbeckScraper::new(config)),
+    }
+}
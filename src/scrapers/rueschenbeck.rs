@@ -1,18 +1,23 @@
 use async_trait::async_trait;
 use anyhow::Result;
+use once_cell::sync::Lazy;
 use regex::Regex;
-use reqwest::Client;
 use scraper::{Html, Selector};
 use std::sync::Arc;
 use tracing::{error, info};
 use url::Url;
 
 use crate::config::{Config, SiteConfig};
-use crate::models::{Site, WatchListing, PapersStatus};
-use crate::parsers::{clean_text, format_price_eur_display, get_price_string_for_hash, 
+use crate::models::{Currency, Money, Site, WatchListing, PapersStatus};
+use crate::parsers::{clean_text, legacy_price_key, parse_money, 
                       parse_year_from_string, parse_box_papers_status, get_condition_display};
 use crate::scrapers::WatchScraper;
-use crate::utils::http::fetch_with_retry;
+use crate::utils::http::{fetch_with_retry, HttpClient};
+
+static TITLE_REFERENCE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^([A-Za-z0-9./\-]+)")
+        .expect("Invalid title reference regex")
+});
 
 pub struct RueschenbeckScraper {
     config: Arc<Config>,
@@ -24,7 +29,7 @@ impl RueschenbeckScraper {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 struct WatchData {
     url: String,
     image_url: String,
@@ -32,12 +37,13 @@ struct WatchData {
     model: String,
     title: String,
     reference: String,
-    price_raw: String,
-    price_display: String,
+    price: Option<Money>,
+    legacy_price_key: String,
     is_cpo: bool,
+    is_sold: bool,
 }
 
-#[derive(Default)]
+#[derive(Debug, Default, PartialEq)]
 struct DetailPageData {
     year_text: String,
     reference_text: String,
@@ -51,7 +57,7 @@ struct DetailPageData {
 
 #[async_trait]
 impl WatchScraper for RueschenbeckScraper {
-    async fn scrape(&self, client: &Client) -> Result<Vec<WatchListing>> {
+    async fn scrape(&self, client: &HttpClient) -> Result<Vec<WatchListing>> {
         let site_config = self.site_config();
         info!("Scraping Rüschenbeck...");
         
@@ -59,7 +65,7 @@ impl WatchScraper for RueschenbeckScraper {
         let html = response.text().await?;
         
         // Extract all data synchronously
-        let watch_data = extract_watch_data(&html, &site_config.base_url)?;
+        let watch_data = extract_watch_data(&html, &site_config.base_url, site_config.currency)?;
         
         info!("Found {} watch items on Rüschenbeck listing page", watch_data.len());
         
@@ -67,7 +73,17 @@ impl WatchScraper for RueschenbeckScraper {
         
         // Process each watch with async operations
         for data in watch_data {
-            if !data.url.is_empty() && data.url != site_config.base_url {
+            if data.url.is_empty() || data.url == site_config.base_url {
+                continue;
+            }
+            
+            // Sold items are only reported so they can be marked as sold
+            if data.is_sold {
+                listings.push(WatchListing {
+                    sold: true,
+                    ..basic_listing(data, site_config)
+                });
+            } else {
                 match self.process_watch(data, client, site_config).await {
                     Ok(listing) => listings.push(listing),
                     Err(e) => error!("Error parsing Rüschenbeck item: {}", e),
@@ -87,7 +103,7 @@ impl WatchScraper for RueschenbeckScraper {
     }
 }
 
-fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
+fn extract_watch_data(html: &str, base_url: &str, currency: Currency) -> Result<Vec<WatchData>> {
     let document = Html::parse_document(html);
     let item_selector = Selector::parse("li.-rb-list-item")
         .map_err(|_| anyhow::anyhow!("Failed to parse item selector"))?;
@@ -95,16 +111,6 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
     let mut watch_data = Vec::new();
     
     for element in document.select(&item_selector) {
-        // Check if sold out
-        if let Ok(avail_selector) = Selector::parse(".-rb-availability .out-of-stock span.value, .-rb-availability .sold span.value") {
-            if let Some(avail_elem) = element.select(&avail_selector).next() {
-                let avail_text = clean_text(&avail_elem.text().collect::<String>()).to_lowercase();
-                if avail_text.contains("verkauft") {
-                    continue; // Skip sold items
-                }
-            }
-        }
-        
         let mut data = WatchData {
             url: String::new(),
             image_url: String::new(),
@@ -112,11 +118,20 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
             model: String::new(),
             title: String::new(),
             reference: String::new(),
-            price_raw: String::new(),
-            price_display: String::new(),
+            price: None,
+            legacy_price_key: String::new(),
             is_cpo: false,
+            is_sold: false,
         };
         
+        // Check if sold out
+        if let Ok(avail_selector) = Selector::parse(".-rb-availability .out-of-stock span.value, .-rb-availability .sold span.value") {
+            if let Some(avail_elem) = element.select(&avail_selector).next() {
+                let avail_text = clean_text(&avail_elem.text().collect::<String>()).to_lowercase();
+                data.is_sold = avail_text.contains("verkauft");
+            }
+        }
+        
         // Extract link
         if let Ok(link_selector) = Selector::parse("a.-rb-list-item-link") {
             if let Some(link) = element.select(&link_selector).next() {
@@ -163,13 +178,12 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
                 data.title = clean_text(&prod_elem.text().collect::<String>());
                 
                 // Extract reference from beginning of title
-                let ref_re = Regex::new(r"^([A-Za-z0-9./\-]+)").unwrap();
-                if let Some(cap) = ref_re.captures(&data.title) {
+                if let Some(cap) = TITLE_REFERENCE_REGEX.captures(&data.title) {
                     if let Some(m) = cap.get(1) {
                         let potential_ref = m.as_str();
                         // Filter out common non-reference words
                         if !potential_ref.to_lowercase().eq("certified") && 
-                           !(potential_ref.chars().all(|c| c.is_numeric()) && potential_ref.len() < 4) {
+                           (!potential_ref.chars().all(|c| c.is_numeric()) || potential_ref.len() >= 4) {
                             data.reference = potential_ref.to_string();
                         }
                     }
@@ -199,8 +213,8 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
                 }
                 
                 if !price_text.is_empty() {
-                    data.price_raw = get_price_string_for_hash(&price_text);
-                    data.price_display = format_price_eur_display(&price_text);
+                    data.price = parse_money(&price_text, currency);
+                    data.legacy_price_key = legacy_price_key(&price_text);
                 }
             }
         }
@@ -222,36 +236,21 @@ impl RueschenbeckScraper {
     async fn process_watch(
         &self,
         data: WatchData,
-        client: &Client,
+        client: &HttpClient,
         site_config: &SiteConfig,
     ) -> Result<WatchListing> {
-        let mut watch = WatchListing {
-            site_name: site_config.name.clone(),
-            watch_url: data.url.clone(),
-            image_url: data.image_url,
-            brand: data.brand,
-            model: data.model,
-            title: data.title,
-            reference: data.reference,
-            price_eur_raw_for_hash: data.price_raw,
-            price_eur_display: data.price_display,
-            ..Default::default()
-        };
-        
-        // Set CPO condition if applicable
-        if data.is_cpo {
-            watch.condition_display = "★★★★☆".to_string(); // 4 stars for CPO
-        }
+        let url = data.url.clone();
+        let mut watch = basic_listing(data, site_config);
         
         // Fetch detail page for additional information
         info!("Fetching details for Rüschenbeck item: {} (URL: {})", 
               if !watch.title.is_empty() { &watch.title } else { "N/A" }, 
-              data.url);
+              url);
         
         // Add delay to be respectful
         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
         
-        match fetch_with_retry(client, &data.url, 3).await {
+        match fetch_with_retry(client, &url, 3).await {
             Ok(detail_response) => {
                 let detail_html = detail_response.text().await?;
                 let details = parse_detail_page(&detail_html);
@@ -310,7 +309,7 @@ impl RueschenbeckScraper {
                 }
                 
                 // Parse box/papers status
-                let combined_text = vec![
+                let combined_text = [
                     details.packaging_text.clone(),
                     details.papers_text.clone()
                 ];
@@ -334,7 +333,7 @@ impl RueschenbeckScraper {
                 }
             }
             Err(e) => {
-                error!("Could not fetch detail page for {}: {}", data.url, e);
+                error!("Could not fetch detail page for {}: {}", url, e);
             }
         }
         
@@ -342,6 +341,29 @@ impl RueschenbeckScraper {
     }
 }
 
+/// Listing built from the listing page alone, before the detail page is fetched
+fn basic_listing(data: WatchData, site_config: &SiteConfig) -> WatchListing {
+    let mut watch = WatchListing {
+        site_name: site_config.name.clone(),
+        watch_url: data.url,
+        image_url: data.image_url,
+        brand: data.brand,
+        model: data.model,
+        title: data.title,
+        reference: data.reference,
+        price: data.price,
+        legacy_price_key: data.legacy_price_key,
+        ..Default::default()
+    };
+    
+    // Set CPO condition if applicable
+    if data.is_cpo {
+        watch.condition_display = "★★★★☆".to_string(); // 4 stars for CPO
+    }
+    
+    watch
+}
+
 fn parse_detail_page(html: &str) -> DetailPageData {
     let document = Html::parse_document(html);
     let mut details = DetailPageData::default();
@@ -428,4 +450,176 @@ fn parse_detail_page(html: &str) -> DetailPageData {
                                     (details.papers_text.to_lowercase() == "ja");
     
     details
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::models::BoxStatus;
+    use crate::scrapers::test_support::{client, config_for, mount_page};
+
+    const LISTING: &str = include_str!("../../tests/fixtures/rueschenbeck/listing.html");
+    const DETAIL_SUBMARINER: &str = include_str!("../../tests/fixtures/rueschenbeck/detail-submariner.html");
+    const DETAIL_CALATRAVA: &str = include_str!("../../tests/fixtures/rueschenbeck/detail-calatrava.html");
+
+    const BASE_URL: &str = "https://www.rueschenbeck.de";
+
+    #[test]
+    fn extracts_list_items() {
+        let data = extract_watch_data(LISTING, BASE_URL, Currency::Eur).unwrap();
+
+        assert_eq!(
+            data,
+            vec![
+                WatchData {
+                    url: format!("{}/rolex-submariner-date-126610ln.html", BASE_URL),
+                    image_url: format!("{}/media/catalog/product/r/o/rolex-126610ln.jpg", BASE_URL),
+                    brand: "Rolex".to_string(),
+                    model: "Submariner".to_string(),
+                    title: "126610LN Submariner Date Oystersteel".to_string(),
+                    reference: "126610LN".to_string(),
+                    price: Some(Money::new(1_199_000, Currency::Eur)),
+                    legacy_price_key: "11990.00".to_string(),
+                    is_cpo: true,
+                    is_sold: false,
+                },
+                WatchData {
+                    url: format!("{}/patek-philippe-calatrava-5196g.html", BASE_URL),
+                    image_url: format!("{}/media/catalog/product/p/p/patek-5196g.jpg", BASE_URL),
+                    brand: "Patek Philippe".to_string(),
+                    model: "Calatrava".to_string(),
+                    title: "Certified Pre-Owned Calatrava 5196G".to_string(),
+                    reference: String::new(),
+                    price: Some(Money::new(2_390_000, Currency::Eur)),
+                    legacy_price_key: "23900.00".to_string(),
+                    is_cpo: false,
+                    is_sold: false,
+                },
+                WatchData {
+                    url: format!("{}/omega-constellation-131-10-39-20-02-001.html", BASE_URL),
+                    image_url: format!("{}/media/catalog/product/o/m/omega-constellation.jpg", BASE_URL),
+                    brand: "Omega".to_string(),
+                    model: "Constellation".to_string(),
+                    title: "131.10.39.20.02.001 Constellation".to_string(),
+                    reference: "131.10.39.20.02.001".to_string(),
+                    price: Some(Money::new(520_000, Currency::Eur)),
+                    legacy_price_key: "5200.00".to_string(),
+                    is_cpo: false,
+                    is_sold: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_cpo_detail_page() {
+        assert_eq!(
+            parse_detail_page(DETAIL_SUBMARINER),
+            DetailPageData {
+                year_text: "2021".to_string(),
+                reference_text: "126610LN-0001".to_string(),
+                diameter_text: "41 mm".to_string(),
+                case_material_text: "oystersteel".to_string(),
+                condition_text: "Sehr gut".to_string(),
+                packaging_text: "Originalbox".to_string(),
+                papers_text: "Ja".to_string(),
+                papiere_direct_confirm: true,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_detail_page_without_papers() {
+        assert_eq!(
+            parse_detail_page(DETAIL_CALATRAVA),
+            DetailPageData {
+                year_text: "ca. 2015".to_string(),
+                reference_text: "5196G-001".to_string(),
+                diameter_text: "37,0 mm".to_string(),
+                case_material_text: "weißgold".to_string(),
+                condition_text: "Neuwertig".to_string(),
+                packaging_text: "Box".to_string(),
+                papers_text: String::new(),
+                papiere_direct_confirm: false,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn scrapes_listing_and_detail_pages() {
+        let server = MockServer::start().await;
+        mount_page(&server, "/uhren/certified-pre-owned/", LISTING).await;
+        mount_page(&server, "/rolex-submariner-date-126610ln.html", DETAIL_SUBMARINER).await;
+        mount_page(&server, "/patek-philippe-calatrava-5196g.html", DETAIL_CALATRAVA).await;
+
+        // Sold items are reported from the listing page alone
+        Mock::given(method("GET"))
+            .and(path("/omega-constellation-131-10-39-20-02-001.html"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let config = config_for(&Site::Rueschenbeck, "Rüschenbeck", &server, "/uhren/certified-pre-owned/", Currency::Eur);
+        let listings = RueschenbeckScraper::new(config).scrape(&client()).await.unwrap();
+
+        let base_url = server.uri();
+        assert_eq!(
+            listings,
+            vec![
+                WatchListing {
+                    brand: "Rolex".to_string(),
+                    model: "Submariner".to_string(),
+                    reference: "126610LN-0001".to_string(),
+                    year: "2021".to_string(),
+                    price: Some(Money::new(1_199_000, Currency::Eur)),
+                    legacy_price_key: "11990.00".to_string(),
+                    papers_status: PapersStatus::Yes,
+                    box_status: BoxStatus::Yes,
+                    condition_display: "★★★★☆".to_string(),
+                    case_material: "Oystersteel".to_string(),
+                    diameter: "41 mm".to_string(),
+                    title: "126610LN Submariner Date Oystersteel".to_string(),
+                    watch_url: format!("{}/rolex-submariner-date-126610ln.html", base_url),
+                    image_url: format!("{}/media/catalog/product/r/o/rolex-126610ln.jpg", base_url),
+                    site_name: "Rüschenbeck".to_string(),
+                    ..Default::default()
+                },
+                WatchListing {
+                    brand: "Patek Philippe".to_string(),
+                    model: "Calatrava".to_string(),
+                    reference: "5196G-001".to_string(),
+                    year: "2015".to_string(),
+                    price: Some(Money::new(2_390_000, Currency::Eur)),
+                    legacy_price_key: "23900.00".to_string(),
+                    papers_status: PapersStatus::Unknown,
+                    box_status: BoxStatus::Yes,
+                    condition_display: "Neuwertig".to_string(),
+                    case_material: "Weißgold".to_string(),
+                    diameter: "37.0 mm".to_string(),
+                    title: "Certified Pre-Owned Calatrava 5196G".to_string(),
+                    watch_url: format!("{}/patek-philippe-calatrava-5196g.html", base_url),
+                    image_url: format!("{}/media/catalog/product/p/p/patek-5196g.jpg", base_url),
+                    site_name: "Rüschenbeck".to_string(),
+                    ..Default::default()
+                },
+                WatchListing {
+                    brand: "Omega".to_string(),
+                    model: "Constellation".to_string(),
+                    reference: "131.10.39.20.02.001".to_string(),
+                    price: Some(Money::new(520_000, Currency::Eur)),
+                    legacy_price_key: "5200.00".to_string(),
+                    title: "131.10.39.20.02.001 Constellation".to_string(),
+                    watch_url: format!("{}/omega-constellation-131-10-39-20-02-001.html", base_url),
+                    image_url: format!("{}/media/catalog/product/o/m/omega-constellation.jpg", base_url),
+                    site_name: "Rüschenbeck".to_string(),
+                    sold: true,
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use serde_json::json;
+use std::sync::Arc;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::config::Config;
+use crate::models::{Currency, Site};
+use crate::utils::http::{create_client, HttpClient, HttpMode};
+
+/// Configuration with `site` as its only site, scraping `listing_path` on
+/// the mock server
+pub fn config_for(site: &Site, name: &str, server: &MockServer, listing_path: &str, currency: Currency) -> Arc<Config> {
+    let config = json!({
+        "sites": {
+            site.key(): {
+                "url": format!("{}{}", server.uri(), listing_path),
+                "webhook": "https://discord.com/api/webhooks/0/test",
+                "name": name,
+                "color": 0,
+                "base_url": server.uri(),
+                "currency": currency,
+            }
+        }
+    });
+
+    Arc::new(serde_json::from_value(config).expect("Invalid test configuration"))
+}
+
+/// Serve `html` at `page_path`, expecting exactly one request for it
+pub async fn mount_page(server: &MockServer, page_path: &str, html: &str) {
+    Mock::given(method("GET"))
+        .and(path(page_path))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(html, "text/html; charset=utf-8"))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+pub fn client() -> HttpClient {
+    create_client("watch-monitor-tests", HttpMode::Live).expect("Failed to build HTTP client")
+}
@@ -1,37 +1,35 @@
 use async_trait::async_trait;
 use anyhow::Result;
 use regex::Regex;
-use reqwest::Client;
 use scraper::{Html, Selector};
 use std::sync::Arc;
 use tracing::{error, info};
 use url::Url;
 
 use crate::config::{Config, SiteConfig};
-use crate::models::{Site, WatchListing};
-use crate::parsers::{clean_text, format_price_eur_display, get_price_string_for_hash, 
+use crate::models::{Currency, Money, Site, WatchListing};
+use crate::parsers::{clean_text, legacy_price_key, parse_money, 
                       parse_year_from_string,
                       extract_reference, parse_table_th_td};
 use crate::scrapers::WatchScraper;
-use crate::utils::http::fetch_with_retry;
-use crate::utils::exchange_rate::ExchangeRateClient;
+use crate::utils::http::{fetch_with_retry, HttpClient};
 
 pub struct TropicalWatchScraper {
     config: Arc<Config>,
-    exchange_rate_client: Arc<ExchangeRateClient>,
 }
 
 impl TropicalWatchScraper {
-    pub fn new(config: Arc<Config>, exchange_rate_client: Arc<ExchangeRateClient>) -> Self {
-        Self { config, exchange_rate_client }
+    pub fn new(config: Arc<Config>) -> Self {
+        Self { config }
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 struct WatchData {
     url: String,
     title: String,
-    price_usd_raw: String,
+    price: Option<Money>,
+    legacy_price_key: String,
     image_url: String,
 }
 
@@ -44,18 +42,15 @@ const KNOWN_BRANDS: &[&str] = &[
 
 #[async_trait]
 impl WatchScraper for TropicalWatchScraper {
-    async fn scrape(&self, client: &Client) -> Result<Vec<WatchListing>> {
+    async fn scrape(&self, client: &HttpClient) -> Result<Vec<WatchListing>> {
         let site_config = self.site_config();
         info!("Scraping Tropical Watch...");
         
-        // Get USD to EUR exchange rate
-        let eur_rate = self.exchange_rate_client.get_usd_to_eur_rate(client).await?;
-        
         let response = fetch_with_retry(client, &site_config.url, 3).await?;
         let html = response.text().await?;
         
         // Extract all data synchronously
-        let watch_data = extract_watch_data(&html, &site_config.base_url)?;
+        let watch_data = extract_watch_data(&html, &site_config.base_url, site_config.currency)?;
         
         info!("Found {} watch items on Tropical Watch listing page", watch_data.len());
         
@@ -64,7 +59,7 @@ impl WatchScraper for TropicalWatchScraper {
         // Process each watch with async operations
         for data in watch_data {
             if !data.url.is_empty() {
-                match self.process_watch(data, client, site_config, eur_rate).await {
+                match self.process_watch(data, client, site_config).await {
                     Ok(listing) => listings.push(listing),
                     Err(e) => error!("Error parsing Tropical Watch item: {}", e),
                 }
@@ -83,7 +78,7 @@ impl WatchScraper for TropicalWatchScraper {
     }
 }
 
-fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
+fn extract_watch_data(html: &str, base_url: &str, currency: Currency) -> Result<Vec<WatchData>> {
     let document = Html::parse_document(html);
     let watch_selector = Selector::parse("li.watch")
         .map_err(|_| anyhow::anyhow!("Failed to parse watch selector"))?;
@@ -94,7 +89,8 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
         let mut data = WatchData {
             url: String::new(),
             title: String::new(),
-            price_usd_raw: String::new(),
+            price: None,
+            legacy_price_key: String::new(),
             image_url: String::new(),
         };
         
@@ -122,7 +118,8 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
         if let Ok(price_selector) = Selector::parse("div.content a h3") {
             if let Some(price_elem) = element.select(&price_selector).next() {
                 let price_text = clean_text(&price_elem.text().collect::<String>());
-                data.price_usd_raw = get_price_string_for_hash(&price_text);
+                data.price = parse_money(&price_text, currency);
+                data.legacy_price_key = legacy_price_key(&price_text);
             }
         }
         
@@ -149,40 +146,19 @@ impl TropicalWatchScraper {
     async fn process_watch(
         &self,
         data: WatchData,
-        client: &Client,
+        client: &HttpClient,
         site_config: &SiteConfig,
-        eur_rate: f64,
     ) -> Result<WatchListing> {
         let mut watch = WatchListing {
             site_name: site_config.name.clone(),
             watch_url: data.url.clone(),
             image_url: data.image_url,
             title: data.title.clone(),
-            price_usd_raw_for_hash: Some(data.price_usd_raw.clone()),
+            price: data.price,
+            legacy_price_key: data.legacy_price_key,
             ..Default::default()
         };
         
-        // Convert USD to EUR
-        if !data.price_usd_raw.is_empty() && data.price_usd_raw != "❓" {
-            match data.price_usd_raw.parse::<f64>() {
-                Ok(usd_price) => {
-                    let eur_price = usd_price * eur_rate;
-                    watch.price_eur_display = format_price_eur_display(&eur_price.to_string());
-                }
-                Err(_) => {
-                    // Try extracting numeric value
-                    let re = Regex::new(r"[\d,.]+")?;
-                    if let Some(m) = re.find(&data.price_usd_raw) {
-                        let price_str = m.as_str().replace(",", "");
-                        if let Ok(usd_price) = price_str.parse::<f64>() {
-                            let eur_price = usd_price * eur_rate;
-                            watch.price_eur_display = format_price_eur_display(&eur_price.to_string());
-                        }
-                    }
-                }
-            }
-        }
-        
         // Fetch detail page for additional information
         info!("Fetching details for Tropical Watch item (URL: {})", data.url);
         
@@ -347,4 +323,118 @@ fn parse_detail_page(html: &str, watch: &mut WatchListing) {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::MockServer;
+
+    use crate::scrapers::test_support::{client, config_for, mount_page};
+
+    const LISTING: &str = include_str!("../../tests/fixtures/tropicalwatch/listing.html");
+    const DETAIL_GMT_MASTER: &str = include_str!("../../tests/fixtures/tropicalwatch/detail-gmt-master.html");
+    const DETAIL_AUTAVIA: &str = include_str!("../../tests/fixtures/tropicalwatch/detail-autavia.html");
+
+    const BASE_URL: &str = "https://www.tropicalwatch.com";
+
+    #[test]
+    fn extracts_listing_cards() {
+        let data = extract_watch_data(LISTING, BASE_URL, Currency::Usd).unwrap();
+
+        assert_eq!(
+            data,
+            vec![
+                WatchData {
+                    url: format!("{}/watches/1967-rolex-gmt-master-1675", BASE_URL),
+                    title: "1967 Rolex GMT-Master 1675".to_string(),
+                    price: Some(Money::new(1_250_000, Currency::Usd)),
+                    legacy_price_key: "12.500".to_string(),
+                    image_url: format!("{}/uploads/watches/gmt-1675/thumb.jpg", BASE_URL),
+                },
+                WatchData {
+                    url: format!("{}/watches/heuer-autavia-2446-second-execution", BASE_URL),
+                    title: "Heuer Autavia 2446 Second Execution".to_string(),
+                    price: Some(Money::new(3_800_000, Currency::Usd)),
+                    legacy_price_key: "38.000".to_string(),
+                    image_url: format!("{}/uploads/watches/autavia-2446/thumb.jpg", BASE_URL),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_details_table() {
+        let mut watch = WatchListing::default();
+        parse_detail_page(DETAIL_GMT_MASTER, &mut watch);
+
+        assert_eq!(watch, gmt_master(""));
+    }
+
+    #[test]
+    fn falls_back_to_title_without_details_table() {
+        let mut watch = WatchListing::default();
+        parse_detail_page(DETAIL_AUTAVIA, &mut watch);
+
+        assert_eq!(watch, autavia(""));
+    }
+
+    #[tokio::test]
+    async fn scrapes_listing_and_detail_pages() {
+        let server = MockServer::start().await;
+        mount_page(&server, "/watches/", LISTING).await;
+        mount_page(&server, "/watches/1967-rolex-gmt-master-1675", DETAIL_GMT_MASTER).await;
+        mount_page(&server, "/watches/heuer-autavia-2446-second-execution", DETAIL_AUTAVIA).await;
+
+        let config = config_for(&Site::TropicalWatch, "Tropical Watch", &server, "/watches/", Currency::Usd);
+        let listings = TropicalWatchScraper::new(config).scrape(&client()).await.unwrap();
+
+        let base_url = server.uri();
+        assert_eq!(
+            listings,
+            vec![
+                WatchListing {
+                    price: Some(Money::new(1_250_000, Currency::Usd)),
+                    legacy_price_key: "12.500".to_string(),
+                    watch_url: format!("{}/watches/1967-rolex-gmt-master-1675", base_url),
+                    image_url: format!("{}/uploads/watches/gmt-1675/thumb.jpg", base_url),
+                    ..gmt_master("Tropical Watch")
+                },
+                WatchListing {
+                    price: Some(Money::new(3_800_000, Currency::Usd)),
+                    legacy_price_key: "38.000".to_string(),
+                    watch_url: format!("{}/watches/heuer-autavia-2446-second-execution", base_url),
+                    image_url: format!("{}/uploads/watches/autavia-2446/thumb.jpg", base_url),
+                    ..autavia("Tropical Watch")
+                },
+            ]
+        );
+    }
+
+    /// What the GMT-Master detail page contributes to a listing
+    fn gmt_master(site_name: &str) -> WatchListing {
+        WatchListing {
+            brand: "Rolex".to_string(),
+            model: "GMT-Master".to_string(),
+            reference: "1675".to_string(),
+            year: "1967".to_string(),
+            case_material: "Stainless Steel".to_string(),
+            diameter: "40mm".to_string(),
+            title: "1967 Rolex GMT-Master 1675".to_string(),
+            site_name: site_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Brand, model and reference derived from the Autavia's title alone
+    fn autavia(site_name: &str) -> WatchListing {
+        WatchListing {
+            brand: "Heuer".to_string(),
+            model: "Autavia Second Execution".to_string(),
+            reference: "2446".to_string(),
+            title: "Heuer Autavia 2446 Second Execution".to_string(),
+            site_name: site_name.to_string(),
+            ..Default::default()
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use anyhow::Result;
+use once_cell::sync::Lazy;
 use regex::Regex;
-use reqwest::Client;
 use scraper::{Html, Selector};
 use serde_json::Value;
 use std::sync::Arc;
@@ -9,11 +9,21 @@ use tracing::{error, info};
 use url::Url;
 
 use crate::config::{Config, SiteConfig};
-use crate::models::{Site, WatchListing};
-use crate::parsers::{clean_text, format_price_eur_display, get_price_string_for_hash, 
+use crate::models::{Currency, Money, Site, WatchListing};
+use crate::parsers::{clean_text, legacy_price_key, parse_money, 
                       parse_year_from_string, parse_box_papers_status, get_condition_display};
 use crate::scrapers::WatchScraper;
-use crate::utils::http::fetch_with_retry;
+use crate::utils::http::{fetch_with_retry, HttpClient};
+
+static SHOPIFY_META_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"var meta = (\{.*?\});")
+        .expect("Invalid Shopify meta regex")
+});
+
+static BADGE_REFERENCE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b([A-Z0-9]{3,}(?:[-/\s]?[A-Z0-9]+)?)\b")
+        .expect("Invalid badge reference regex")
+});
 
 pub struct WatchOutScraper {
     config: Arc<Config>,
@@ -25,19 +35,19 @@ impl WatchOutScraper {
     }
 }
 
-#[derive(Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 struct WatchData {
     url: String,
     title: String,
     brand: String,
     reference: String,
-    price_raw: String,
-    price_display: String,
+    price: Option<Money>,
+    legacy_price_key: String,
     image_url: String,
     handle: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 struct ShopifyProduct {
     title: String,
     brand: String,
@@ -48,7 +58,7 @@ struct ShopifyProduct {
 
 #[async_trait]
 impl WatchScraper for WatchOutScraper {
-    async fn scrape(&self, client: &Client) -> Result<Vec<WatchListing>> {
+    async fn scrape(&self, client: &HttpClient) -> Result<Vec<WatchListing>> {
         let site_config = self.site_config();
         info!("Scraping Watch Out...");
         
@@ -56,7 +66,7 @@ impl WatchScraper for WatchOutScraper {
         let html = response.text().await?;
         
         // Extract Shopify analytics data and product cards
-        let (shopify_products, watch_data) = extract_watch_data(&html, &site_config.base_url)?;
+        let (shopify_products, watch_data) = extract_watch_data(&html, &site_config.base_url, site_config.currency)?;
         
         info!("Found {} product-card elements on Watch Out page", watch_data.len());
         if !shopify_products.is_empty() {
@@ -87,8 +97,9 @@ impl WatchScraper for WatchOutScraper {
                         data.reference = shopify.reference.clone();
                     }
                     if let Some(price_cents) = shopify.price_cents {
-                        data.price_raw = price_cents.to_string();
-                        data.price_display = format_price_eur_display(&(price_cents as f64 / 100.0).to_string());
+                        data.price = Some(Money::new(price_cents, site_config.currency));
+                        // Earlier releases keyed Shopify prices by their cents
+                        data.legacy_price_key = price_cents.to_string();
                     }
                 }
             }
@@ -113,7 +124,7 @@ impl WatchScraper for WatchOutScraper {
     }
 }
 
-fn extract_watch_data(html: &str, base_url: &str) -> Result<(Vec<ShopifyProduct>, Vec<WatchData>)> {
+fn extract_watch_data(html: &str, base_url: &str, currency: Currency) -> Result<(Vec<ShopifyProduct>, Vec<WatchData>)> {
     let document = Html::parse_document(html);
     let mut shopify_products = Vec::new();
     
@@ -123,8 +134,7 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<(Vec<ShopifyProduct>
             let script_text = script.text().collect::<String>();
             if script_text.contains("window.ShopifyAnalytics.meta") {
                 // Look for var meta = {...}
-                let re = Regex::new(r"var meta = (\{.*?\});").unwrap();
-                if let Some(cap) = re.captures(&script_text) {
+                if let Some(cap) = SHOPIFY_META_REGEX.captures(&script_text) {
                     if let Some(json_str) = cap.get(1) {
                         match serde_json::from_str::<Value>(json_str.as_str()) {
                             Ok(meta_data) => {
@@ -256,8 +266,8 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<(Vec<ShopifyProduct>
         if let Ok(price_selector) = Selector::parse("sale-price") {
             if let Some(price_elem) = element.select(&price_selector).next() {
                 let price_text = clean_text(&price_elem.text().collect::<String>());
-                data.price_raw = get_price_string_for_hash(&price_text);
-                data.price_display = format_price_eur_display(&price_text);
+                data.price = parse_money(&price_text, currency);
+                data.legacy_price_key = legacy_price_key(&price_text);
             }
         }
         
@@ -266,8 +276,7 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<(Vec<ShopifyProduct>
             if let Some(ref_elem) = element.select(&ref_selector).next() {
                 let ref_text = clean_text(&ref_elem.text().collect::<String>());
                 // Extract reference pattern
-                let ref_re = Regex::new(r"\b([A-Z0-9]{3,}(?:[-/\s]?[A-Z0-9]+)?)\b").unwrap();
-                if let Some(cap) = ref_re.captures(&ref_text) {
+                if let Some(cap) = BADGE_REFERENCE_REGEX.captures(&ref_text) {
                     if let Some(m) = cap.get(1) {
                         data.reference = m.as_str().to_string();
                     }
@@ -298,7 +307,7 @@ impl WatchOutScraper {
     async fn process_watch(
         &self,
         data: WatchData,
-        client: &Client,
+        client: &HttpClient,
         site_config: &SiteConfig,
     ) -> Result<WatchListing> {
         let mut watch = WatchListing {
@@ -308,8 +317,8 @@ impl WatchOutScraper {
             title: data.title.clone(),
             brand: data.brand.clone(),
             reference: data.reference.clone(),
-            price_eur_raw_for_hash: data.price_raw,
-            price_eur_display: data.price_display,
+            price: data.price,
+            legacy_price_key: data.legacy_price_key,
             ..Default::default()
         };
         
@@ -359,7 +368,7 @@ fn parse_detail_page(html: &str, watch: &mut WatchListing) {
                         watch.box_status = box_status;
                         
                         // Get condition
-                        watch.condition_display = get_condition_display("", Site::WatchOut, Some(&vec![description]));
+                        watch.condition_display = get_condition_display("", Site::WatchOut, Some(&[description]));
                     }
                 }
             }
@@ -414,4 +423,154 @@ fn parse_detail_page(html: &str, watch: &mut WatchListing) {
             watch.model = words.join(" ");
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::MockServer;
+
+    use crate::models::{BoxStatus, PapersStatus};
+    use crate::scrapers::test_support::{client, config_for, mount_page};
+
+    const LISTING: &str = include_str!("../../tests/fixtures/watch_out/listing.html");
+    const DETAIL_EXPLORER: &str = include_str!("../../tests/fixtures/watch_out/detail-explorer.html");
+    const DETAIL_SPEEDMASTER: &str = include_str!("../../tests/fixtures/watch_out/detail-speedmaster.html");
+
+    const BASE_URL: &str = "https://www.watch-out.shop";
+
+    #[test]
+    fn extracts_shopify_products_and_cards() {
+        let (products, cards) = extract_watch_data(LISTING, BASE_URL, Currency::Eur).unwrap();
+
+        assert_eq!(
+            products,
+            vec![
+                ShopifyProduct {
+                    title: "Rolex Explorer 214270".to_string(),
+                    brand: "Rolex".to_string(),
+                    reference: "214270".to_string(),
+                    price_cents: Some(1_290_000),
+                    url_part: "/products/rolex-explorer-214270".to_string(),
+                },
+                ShopifyProduct {
+                    title: "Omega Speedmaster Moonwatch Professional".to_string(),
+                    brand: "Omega".to_string(),
+                    reference: "311.30.42.30.01.005".to_string(),
+                    price_cents: Some(645_000),
+                    url_part: "/products/omega-speedmaster-311-30-42-30-01-005".to_string(),
+                },
+                ShopifyProduct {
+                    title: "Tudor Black Bay 58".to_string(),
+                    brand: "Tudor".to_string(),
+                    reference: "M79030N".to_string(),
+                    price_cents: Some(320_000),
+                    url_part: "/products/tudor-black-bay-58".to_string(),
+                },
+            ]
+        );
+
+        // The sold-out card is skipped
+        assert_eq!(
+            cards,
+            vec![
+                WatchData {
+                    url: format!("{}/products/rolex-explorer-214270", BASE_URL),
+                    title: "Rolex Explorer".to_string(),
+                    brand: "Rolex".to_string(),
+                    reference: "214270".to_string(),
+                    price: Some(Money::new(1_290_000, Currency::Eur)),
+                    legacy_price_key: "12900.00".to_string(),
+                    image_url: format!("{}/cdn/shop/files/rolex-explorer-214270.jpg?width=800", BASE_URL),
+                    handle: "rolex-explorer-214270".to_string(),
+                },
+                WatchData {
+                    url: format!("{}/products/omega-speedmaster-311-30-42-30-01-005?variant=8002", BASE_URL),
+                    title: "Omega Speedmaster Moonwatch".to_string(),
+                    brand: String::new(),
+                    reference: String::new(),
+                    price: Some(Money::new(645_000, Currency::Eur)),
+                    legacy_price_key: "6450.00".to_string(),
+                    image_url: format!("{}/cdn/shop/files/omega-speedmaster.jpg", BASE_URL),
+                    handle: "omega-speedmaster-311-30-42-30-01-005".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_detail_page() {
+        let mut watch = WatchListing {
+            brand: "Rolex".to_string(),
+            title: "Rolex Explorer 214270".to_string(),
+            ..Default::default()
+        };
+        parse_detail_page(DETAIL_EXPLORER, &mut watch);
+
+        assert_eq!(
+            watch,
+            WatchListing {
+                brand: "Rolex".to_string(),
+                model: "Explorer".to_string(),
+                year: "2018".to_string(),
+                papers_status: PapersStatus::Yes,
+                box_status: BoxStatus::Yes,
+                case_material: "Stainless Steel".to_string(),
+                diameter: "39 mm".to_string(),
+                title: "Rolex Explorer 214270".to_string(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn scrapes_listing_and_detail_pages() {
+        let server = MockServer::start().await;
+        mount_page(&server, "/collections/neuheiten", LISTING).await;
+        mount_page(&server, "/products/rolex-explorer-214270", DETAIL_EXPLORER).await;
+        mount_page(&server, "/products/omega-speedmaster-311-30-42-30-01-005", DETAIL_SPEEDMASTER).await;
+
+        let config = config_for(&Site::WatchOut, "Watch Out", &server, "/collections/neuheiten", Currency::Eur);
+        let listings = WatchOutScraper::new(config).scrape(&client()).await.unwrap();
+
+        let base_url = server.uri();
+        assert_eq!(
+            listings,
+            vec![
+                WatchListing {
+                    brand: "Rolex".to_string(),
+                    model: "Explorer".to_string(),
+                    reference: "214270".to_string(),
+                    year: "2018".to_string(),
+                    price: Some(Money::new(1_290_000, Currency::Eur)),
+                    legacy_price_key: "1290000".to_string(),
+                    papers_status: PapersStatus::Yes,
+                    box_status: BoxStatus::Yes,
+                    case_material: "Stainless Steel".to_string(),
+                    diameter: "39 mm".to_string(),
+                    title: "Rolex Explorer 214270".to_string(),
+                    watch_url: format!("{}/products/rolex-explorer-214270", base_url),
+                    image_url: format!("{}/cdn/shop/files/rolex-explorer-214270.jpg?width=800", base_url),
+                    site_name: "Watch Out".to_string(),
+                    ..Default::default()
+                },
+                WatchListing {
+                    brand: "Omega".to_string(),
+                    model: "Speedmaster Moonwatch Professional".to_string(),
+                    reference: "311.30.42.30.01.005".to_string(),
+                    price: Some(Money::new(645_000, Currency::Eur)),
+                    legacy_price_key: "645000".to_string(),
+                    papers_status: PapersStatus::No,
+                    box_status: BoxStatus::No,
+                    case_material: "Stainless Steel".to_string(),
+                    diameter: "42 mm".to_string(),
+                    title: "Omega Speedmaster Moonwatch Professional".to_string(),
+                    watch_url: format!("{}/products/omega-speedmaster-311-30-42-30-01-005?variant=8002", base_url),
+                    image_url: format!("{}/cdn/shop/files/omega-speedmaster.jpg", base_url),
+                    site_name: "Watch Out".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+}
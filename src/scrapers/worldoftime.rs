@@ -1,19 +1,18 @@
 use async_trait::async_trait;
 use anyhow::Result;
-use reqwest::Client;
 use scraper::{Html, Selector};
 use std::sync::Arc;
 use tracing::{error, info};
 use url::Url;
 
 use crate::config::{Config, SiteConfig};
-use crate::models::Site;
+use crate::models::{Currency, Money, Site};
 use crate::models::WatchListing;
-use crate::parsers::{clean_text, format_price_eur_display, get_price_string_for_hash, 
+use crate::parsers::{clean_text, legacy_price_key, parse_money, 
                       parse_year_from_string, parse_box_papers_status, get_condition_display,
                       extract_reference, parse_table_th_td};
 use crate::scrapers::WatchScraper;
-use crate::utils::http::fetch_with_retry;
+use crate::utils::http::{fetch_with_retry, HttpClient};
 
 pub struct WorldOfTimeScraper {
     config: Arc<Config>,
@@ -25,20 +24,20 @@ impl WorldOfTimeScraper {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 struct WatchData {
     url: String,
     title: String,
     brand: String,
     model: String,
-    price_raw: String,
-    price_display: String,
+    price: Option<Money>,
+    legacy_price_key: String,
     image_url: String,
 }
 
 #[async_trait]
 impl WatchScraper for WorldOfTimeScraper {
-    async fn scrape(&self, client: &Client) -> Result<Vec<WatchListing>> {
+    async fn scrape(&self, client: &HttpClient) -> Result<Vec<WatchListing>> {
         let site_config = self.site_config();
         info!("Scraping World of Time...");
         
@@ -46,7 +45,7 @@ impl WatchScraper for WorldOfTimeScraper {
         let html = response.text().await?;
         
         // Extract all data synchronously
-        let watch_data = extract_watch_data(&html, &site_config.base_url)?;
+        let watch_data = extract_watch_data(&html, &site_config.base_url, site_config.currency)?;
         
         info!("Found {} watch items on World of Time listing page", watch_data.len());
         
@@ -72,7 +71,7 @@ impl WatchScraper for WorldOfTimeScraper {
     }
 }
 
-fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
+fn extract_watch_data(html: &str, base_url: &str, currency: Currency) -> Result<Vec<WatchData>> {
     let document = Html::parse_document(html);
     let watch_selector = Selector::parse("div.new-arrivals-watch, div.paged-clocks-container div.watch-link")
         .map_err(|_| anyhow::anyhow!("Failed to parse watch selector"))?;
@@ -85,8 +84,8 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
             title: String::new(),
             brand: String::new(),
             model: String::new(),
-            price_raw: String::new(),
-            price_display: String::new(),
+            price: None,
+            legacy_price_key: String::new(),
             image_url: String::new(),
         };
         
@@ -139,8 +138,8 @@ fn extract_watch_data(html: &str, base_url: &str) -> Result<Vec<WatchData>> {
         if let Ok(price_selector) = Selector::parse(".watch-price, .price") {
             if let Some(price_elem) = element.select(&price_selector).next() {
                 let price_text = clean_text(&price_elem.text().collect::<String>());
-                data.price_raw = get_price_string_for_hash(&price_text);
-                data.price_display = format_price_eur_display(&price_text);
+                data.price = parse_money(&price_text, currency);
+                data.legacy_price_key = legacy_price_key(&price_text);
             }
         }
         
@@ -156,7 +155,7 @@ impl WorldOfTimeScraper {
     async fn process_watch(
         &self,
         data: WatchData,
-        client: &Client,
+        client: &HttpClient,
         site_config: &SiteConfig,
     ) -> Result<WatchListing> {
         let mut watch = WatchListing {
@@ -166,8 +165,8 @@ impl WorldOfTimeScraper {
             title: data.title,
             brand: data.brand,
             model: data.model,
-            price_eur_raw_for_hash: data.price_raw,
-            price_eur_display: data.price_display,
+            price: data.price,
+            legacy_price_key: data.legacy_price_key,
             ..Default::default()
         };
         
@@ -263,4 +262,129 @@ fn parse_detail_page(html: &str, watch: &mut WatchListing) {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::MockServer;
+
+    use crate::models::{BoxStatus, PapersStatus};
+    use crate::scrapers::test_support::{client, config_for, mount_page};
+
+    const LISTING: &str = include_str!("../../tests/fixtures/worldoftime/listing.html");
+    const DETAIL_SUBMARINER: &str = include_str!("../../tests/fixtures/worldoftime/detail-submariner.html");
+    const DETAIL_SEAMASTER: &str = include_str!("../../tests/fixtures/worldoftime/detail-seamaster.html");
+
+    const BASE_URL: &str = "https://www.worldoftime.de";
+
+    #[test]
+    fn extracts_listing_cards_with_links() {
+        let data = extract_watch_data(LISTING, BASE_URL, Currency::Eur).unwrap();
+
+        assert_eq!(
+            data,
+            vec![
+                WatchData {
+                    url: format!("{}/Watches/Rolex/Submariner-Date-16610", BASE_URL),
+                    title: "Rolex Submariner Date".to_string(),
+                    brand: "Rolex".to_string(),
+                    model: "Submariner Date".to_string(),
+                    price: Some(Money::new(985_000, Currency::Eur)),
+                    legacy_price_key: "9850".to_string(),
+                    image_url: format!("{}/images/watches/submariner-16610-small.jpg", BASE_URL),
+                },
+                WatchData {
+                    url: format!("{}/Watches/Omega/Seamaster-Diver-300M", BASE_URL),
+                    title: "Omega Seamaster Diver 300M".to_string(),
+                    brand: "Omega".to_string(),
+                    model: "Seamaster Diver 300M".to_string(),
+                    price: None,
+                    legacy_price_key: String::new(),
+                    image_url: format!("{}/images/watches/seamaster-300m-small.jpg", BASE_URL),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_english_detail_table() {
+        let mut watch = WatchListing::default();
+        parse_detail_page(DETAIL_SUBMARINER, &mut watch);
+
+        assert_eq!(watch, submariner(""));
+    }
+
+    #[test]
+    fn parses_german_detail_table() {
+        let mut watch = WatchListing::default();
+        parse_detail_page(DETAIL_SEAMASTER, &mut watch);
+
+        assert_eq!(watch, seamaster(""));
+    }
+
+    #[tokio::test]
+    async fn scrapes_listing_and_detail_pages() {
+        let server = MockServer::start().await;
+        mount_page(&server, "/Watches/NewArrivals", LISTING).await;
+        mount_page(&server, "/Watches/Rolex/Submariner-Date-16610", DETAIL_SUBMARINER).await;
+        mount_page(&server, "/Watches/Omega/Seamaster-Diver-300M", DETAIL_SEAMASTER).await;
+
+        let config = config_for(&Site::WorldOfTime, "World of Time", &server, "/Watches/NewArrivals", Currency::Eur);
+        let listings = WorldOfTimeScraper::new(config).scrape(&client()).await.unwrap();
+
+        let base_url = server.uri();
+        assert_eq!(
+            listings,
+            vec![
+                WatchListing {
+                    price: Some(Money::new(985_000, Currency::Eur)),
+                    legacy_price_key: "9850".to_string(),
+                    watch_url: format!("{}/Watches/Rolex/Submariner-Date-16610", base_url),
+                    image_url: format!("{}/images/watches/submariner-16610-small.jpg", base_url),
+                    ..submariner("World of Time")
+                },
+                WatchListing {
+                    watch_url: format!("{}/Watches/Omega/Seamaster-Diver-300M", base_url),
+                    image_url: format!("{}/images/watches/seamaster-300m-small.jpg", base_url),
+                    ..seamaster("World of Time")
+                },
+            ]
+        );
+    }
+
+    /// What the Submariner detail page contributes to a listing
+    fn submariner(site_name: &str) -> WatchListing {
+        WatchListing {
+            brand: "Rolex".to_string(),
+            model: "Submariner Date 16610".to_string(),
+            reference: "16610".to_string(),
+            year: "2003".to_string(),
+            papers_status: PapersStatus::Yes,
+            box_status: BoxStatus::Yes,
+            condition_display: "Very Good".to_string(),
+            case_material: "Stainless steel".to_string(),
+            diameter: "40 mm".to_string(),
+            title: "Rolex Submariner Date 16610".to_string(),
+            site_name: site_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// What the Seamaster detail page contributes to a listing
+    fn seamaster(site_name: &str) -> WatchListing {
+        WatchListing {
+            brand: "Omega".to_string(),
+            model: "Seamaster Diver 300M".to_string(),
+            reference: "210.30.42.20.03.001".to_string(),
+            papers_status: PapersStatus::Yes,
+            box_status: BoxStatus::Yes,
+            condition_display: "New".to_string(),
+            case_material: "Edelstahl".to_string(),
+            diameter: "42 mm".to_string(),
+            title: "Omega Seamaster Diver 300M".to_string(),
+            site_name: site_name.to_string(),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tracing::{error, info};
+
+use crate::api;
+use crate::config::Config;
+use crate::dashboard;
+use crate::metrics;
+use crate::monitor::ScrapeTriggers;
+use crate::storage::Storage;
+
+/// What the HTTP handlers share; the configuration follows reloads
+#[derive(Clone)]
+pub struct ServerState {
+    pub config: watch::Receiver<Arc<Config>>,
+    pub storage: Arc<dyn Storage>,
+    pub triggers: ScrapeTriggers,
+}
+
+/// Bind the HTTP listener up front so that a taken port fails startup
+pub async fn bind(addr: SocketAddr) -> Result<TcpListener> {
+    TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to listen on {}", addr))
+}
+
+/// Serve `/metrics`, the REST API and the dashboard until the process exits
+pub async fn serve(listener: TcpListener, state: ServerState) {
+    metrics::init();
+
+    if let Ok(addr) = listener.local_addr() {
+        info!("Serving metrics at http://{}/metrics", addr);
+        if state.config.borrow().api.enabled {
+            info!("Serving the REST API at http://{}/api", addr);
+        }
+        if state.config.borrow().dashboard.enabled {
+            info!("Serving the dashboard at http://{}/", addr);
+        }
+    }
+
+    if let Err(e) = axum::serve(listener, router(state)).await {
+        error!("HTTP listener stopped: {}", e);
+    }
+}
+
+fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/metrics", get(render_metrics))
+        .merge(api::router(state.clone()))
+        .merge(dashboard::router(state))
+}
+
+async fn render_metrics() -> impl IntoResponse {
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], metrics::render())
+}
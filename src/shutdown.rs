@@ -0,0 +1,63 @@
+use once_cell::sync::Lazy;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+/// Exit code after a second Ctrl-C or SIGTERM, as if killed by SIGINT
+const FORCED_EXIT_CODE: i32 = 130;
+
+/// Set once shutdown was requested; never reset
+static REQUESTED: Lazy<watch::Sender<bool>> = Lazy::new(|| watch::channel(false).0);
+
+/// Request shutdown on the first Ctrl-C or SIGTERM. A second one exits
+/// right away without waiting for in-flight work.
+pub fn spawn_signal_handler() {
+    tokio::spawn(async {
+        wait_for_signal().await;
+        info!("Shutdown requested, finishing in-flight notifications (press Ctrl-C again to exit immediately)");
+        request();
+
+        wait_for_signal().await;
+        warn!("Second shutdown request, exiting immediately");
+        std::process::exit(FORCED_EXIT_CODE);
+    });
+}
+
+/// Request shutdown as a signal would
+pub fn request() {
+    REQUESTED.send_replace(true);
+}
+
+pub fn is_requested() -> bool {
+    *REQUESTED.borrow()
+}
+
+/// Complete once shutdown was requested
+pub async fn requested() {
+    let mut requested = REQUESTED.subscribe();
+    // The sender lives in a static, so the channel never closes
+    let _ = requested.wait_for(|requested| *requested).await;
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            error!("Failed to install SIGTERM handler: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -0,0 +1,370 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use tracing::info;
+
+/// Schema migrations in order. `PRAGMA user_version` holds how many of them
+/// a database has applied; never edit a released migration, append a new one.
+const MIGRATIONS: &[&str] = &[
+    // 1: seen ids, price history and listing lifecycle. Databases created
+    // before versioning already have these tables, hence `IF NOT EXISTS`.
+    "CREATE TABLE IF NOT EXISTS seen_watches (
+        site TEXT NOT NULL,
+        watch_id TEXT NOT NULL,
+        first_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (site, watch_id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_site ON seen_watches(site);
+
+    CREATE TABLE IF NOT EXISTS price_history (
+        site TEXT NOT NULL,
+        listing_id TEXT NOT NULL,
+        price_raw TEXT NOT NULL,
+        price_eur REAL,
+        price_display TEXT NOT NULL,
+        first_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+        last_seen DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX IF NOT EXISTS idx_price_history_listing ON price_history(site, listing_id);
+
+    CREATE TABLE IF NOT EXISTS listing_lifecycle (
+        site TEXT NOT NULL,
+        listing_id TEXT NOT NULL,
+        snapshot TEXT NOT NULL,
+        status TEXT NOT NULL DEFAULT 'active',
+        missed_scrapes INTEGER NOT NULL DEFAULT 0,
+        first_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+        last_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+        ended_at DATETIME,
+        PRIMARY KEY (site, listing_id)
+    );",
+    // 2: full listing snapshots as columns, replacing the JSON snapshots
+    "CREATE TABLE listings (
+        site TEXT NOT NULL,
+        listing_id TEXT NOT NULL,
+        brand TEXT NOT NULL,
+        model TEXT NOT NULL,
+        reference TEXT NOT NULL,
+        year TEXT NOT NULL,
+        price_eur_display TEXT NOT NULL,
+        price_eur_raw TEXT NOT NULL,
+        price_usd_raw TEXT,
+        papers_status TEXT NOT NULL,
+        box_status TEXT NOT NULL,
+        condition TEXT NOT NULL,
+        case_material TEXT NOT NULL,
+        diameter TEXT NOT NULL,
+        title TEXT NOT NULL,
+        watch_url TEXT NOT NULL,
+        image_url TEXT NOT NULL,
+        site_name TEXT NOT NULL,
+        status TEXT NOT NULL DEFAULT 'active',
+        missed_scrapes INTEGER NOT NULL DEFAULT 0,
+        first_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+        last_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+        ended_at DATETIME,
+        PRIMARY KEY (site, listing_id)
+    );
+    CREATE INDEX idx_listings_status ON listings(site, status);
+    CREATE INDEX idx_listings_brand ON listings(brand, reference);
+
+    INSERT INTO listings
+    SELECT site, listing_id,
+        json_extract(snapshot, '$.brand'),
+        json_extract(snapshot, '$.model'),
+        json_extract(snapshot, '$.reference'),
+        json_extract(snapshot, '$.year'),
+        json_extract(snapshot, '$.price_eur_display'),
+        json_extract(snapshot, '$.price_eur_raw_for_hash'),
+        json_extract(snapshot, '$.price_usd_raw_for_hash'),
+        lower(json_extract(snapshot, '$.papers_status')),
+        lower(json_extract(snapshot, '$.box_status')),
+        json_extract(snapshot, '$.condition_display'),
+        json_extract(snapshot, '$.case_material'),
+        json_extract(snapshot, '$.diameter'),
+        json_extract(snapshot, '$.title'),
+        json_extract(snapshot, '$.watch_url'),
+        json_extract(snapshot, '$.image_url'),
+        json_extract(snapshot, '$.site_name'),
+        status, missed_scrapes, first_seen, last_seen, ended_at
+    FROM listing_lifecycle;
+
+    DROP TABLE listing_lifecycle;",
+    // 3: prices as amounts in minor units plus currency instead of strings.
+    // Old price history only has a reliable EUR amount, so it is kept as
+    // EUR; the first native observation afterwards counts as a change but
+    // never as a drop.
+    "CREATE TABLE price_history_v3 (
+        site TEXT NOT NULL,
+        listing_id TEXT NOT NULL,
+        amount_minor INTEGER NOT NULL,
+        currency TEXT NOT NULL,
+        price_eur_minor INTEGER,
+        first_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+        last_seen DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+    INSERT INTO price_history_v3
+    SELECT site, listing_id, CAST(ROUND(price_eur * 100) AS INTEGER), 'EUR',
+        CAST(ROUND(price_eur * 100) AS INTEGER), first_seen, last_seen
+    FROM price_history WHERE price_eur IS NOT NULL ORDER BY rowid;
+    DROP TABLE price_history;
+    ALTER TABLE price_history_v3 RENAME TO price_history;
+    CREATE INDEX idx_price_history_listing ON price_history(site, listing_id);
+
+    ALTER TABLE listings ADD COLUMN price_minor INTEGER;
+    ALTER TABLE listings ADD COLUMN price_currency TEXT;
+    ALTER TABLE listings ADD COLUMN price_eur_minor INTEGER;
+    UPDATE listings
+    SET price_currency = 'USD',
+        price_minor = CAST(REPLACE(REPLACE(price_usd_raw, '.', ''), ',', '') AS INTEGER) * 100
+    WHERE COALESCE(price_usd_raw, '') != '';
+    UPDATE listings
+    SET price_currency = 'EUR',
+        price_minor = CAST(ROUND(CAST(price_eur_raw AS REAL) * 100) AS INTEGER),
+        price_eur_minor = CAST(ROUND(CAST(price_eur_raw AS REAL) * 100) AS INTEGER)
+    WHERE price_currency IS NULL AND price_eur_raw != '';
+    ALTER TABLE listings DROP COLUMN price_eur_display;
+    ALTER TABLE listings DROP COLUMN price_eur_raw;
+    ALTER TABLE listings DROP COLUMN price_usd_raw;",
+    // 4: last good exchange rates, units of `currency` per one `base`
+    "CREATE TABLE exchange_rates (
+        base TEXT NOT NULL,
+        currency TEXT NOT NULL,
+        rate REAL NOT NULL,
+        fetched_at TEXT NOT NULL,
+        PRIMARY KEY (base, currency)
+    );",
+    // 5: scraper health per site; `problems` is a JSON array of strings
+    "CREATE TABLE site_health (
+        site TEXT PRIMARY KEY,
+        status TEXT NOT NULL,
+        consecutive_failures INTEGER NOT NULL,
+        average_items REAL,
+        samples INTEGER NOT NULL,
+        last_items INTEGER,
+        unknown_share REAL,
+        problems TEXT NOT NULL,
+        last_error TEXT,
+        last_success TEXT,
+        updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );",
+    // 6: per-scrape percentage of listings for sale with each detail resolved
+    "CREATE TABLE field_completeness (
+        site TEXT NOT NULL,
+        checked_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        listings INTEGER NOT NULL,
+        brand REAL NOT NULL,
+        reference REAL NOT NULL,
+        year REAL NOT NULL,
+        condition REAL NOT NULL,
+        material REAL NOT NULL,
+        diameter REAL NOT NULL,
+        box REAL NOT NULL,
+        papers REAL NOT NULL
+    );
+    CREATE INDEX idx_field_completeness_site ON field_completeness(site, checked_at);",
+    // 7: listings waiting for an email digest; `entry` is a JSON `DigestEntry`
+    "CREATE TABLE digest_entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        digest TEXT NOT NULL,
+        entry TEXT NOT NULL,
+        queued_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX idx_digest_entries_digest ON digest_entries(digest, id);",
+    // 8: Discord messages waiting for delivery; `target` is a key of the
+    // webhook rather than its URL, which contains the token, `site` and
+    // `listing_id` name the new listing a message announces
+    "CREATE TABLE outbox (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        target TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        site TEXT,
+        listing_id TEXT,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        next_attempt_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        last_error TEXT,
+        queued_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX idx_outbox_due ON outbox(next_attempt_at, id);
+    CREATE INDEX idx_outbox_listing ON outbox(site, listing_id);",
+];
+
+/// Bring the schema up to date, applying each pending migration in its own
+/// transaction
+pub fn run(conn: &mut Connection) -> Result<()> {
+    let current: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    if current > MIGRATIONS.len() {
+        anyhow::bail!(
+            "Database schema version {} is newer than this build supports ({})",
+            current,
+            MIGRATIONS.len()
+        );
+    }
+
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = index + 1;
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)
+            .with_context(|| format!("Failed to apply database migration {}", version))?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+
+        info!("Applied database migration {}", version);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Schema and rows of a database written before migrations were versioned
+    fn unversioned_database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE seen_watches (
+                site TEXT NOT NULL,
+                watch_id TEXT NOT NULL,
+                first_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (site, watch_id)
+            );
+            CREATE TABLE price_history (
+                site TEXT NOT NULL,
+                listing_id TEXT NOT NULL,
+                price_raw TEXT NOT NULL,
+                price_eur REAL,
+                price_display TEXT NOT NULL,
+                first_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+                last_seen DATETIME DEFAULT CURRENT_TIMESTAMP
+            );
+            CREATE TABLE listing_lifecycle (
+                site TEXT NOT NULL,
+                listing_id TEXT NOT NULL,
+                snapshot TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'active',
+                missed_scrapes INTEGER NOT NULL DEFAULT 0,
+                first_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+                last_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+                ended_at DATETIME,
+                PRIMARY KEY (site, listing_id)
+            );
+
+            INSERT INTO seen_watches (site, watch_id) VALUES ('worldoftime', 'a'), ('watchout', 'b');
+            INSERT INTO price_history (site, listing_id, price_raw, price_eur, price_display)
+            VALUES ('worldoftime', 'a', '12500', 12500.0, '12.500 €'),
+                   ('worldoftime', 'a', '11999.99', 11999.99, '11.999,99 €'),
+                   ('watchout', 'b', 'Auf Anfrage', NULL, 'Auf Anfrage');",
+        )
+        .unwrap();
+
+        let snapshot = |price_eur: &str, price_usd: Option<&str>| {
+            json!({
+                "brand": "Omega",
+                "model": "Speedmaster",
+                "reference": "311.30.42.30.01.005",
+                "year": "2019",
+                "price_eur_display": "12.500 €",
+                "price_eur_raw_for_hash": price_eur,
+                "price_usd_raw_for_hash": price_usd,
+                "papers_status": "Yes",
+                "box_status": "No",
+                "condition_display": "Very good",
+                "case_material": "Steel",
+                "diameter": "42 mm",
+                "title": "Omega Speedmaster",
+                "watch_url": "https://example.com/a",
+                "image_url": "https://example.com/a.jpg",
+                "site_name": "World of Time",
+            })
+            .to_string()
+        };
+        conn.execute(
+            "INSERT INTO listing_lifecycle (site, listing_id, snapshot) VALUES ('worldoftime', 'a', ?1)",
+            [snapshot("11999.99", None)],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO listing_lifecycle (site, listing_id, snapshot, status, missed_scrapes, ended_at)
+             VALUES ('watchout', 'b', ?1, 'sold', 3, CURRENT_TIMESTAMP)",
+            [snapshot("", Some("13,250"))],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    fn price_history(conn: &Connection) -> Vec<(String, i64, String, Option<i64>)> {
+        let mut stmt = conn
+            .prepare("SELECT listing_id, amount_minor, currency, price_eur_minor FROM price_history ORDER BY rowid")
+            .unwrap();
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)));
+        rows.unwrap().collect::<Result<_, _>>().unwrap()
+    }
+
+    /// Listing id, native amount and currency, EUR amount
+    type ListingPrice = (String, Option<i64>, Option<String>, Option<i64>);
+
+    fn listing_prices(conn: &Connection) -> Vec<ListingPrice> {
+        let mut stmt = conn
+            .prepare("SELECT listing_id, price_minor, price_currency, price_eur_minor FROM listings ORDER BY listing_id")
+            .unwrap();
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)));
+        rows.unwrap().collect::<Result<_, _>>().unwrap()
+    }
+
+    fn user_version(conn: &Connection) -> usize {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn upgrades_an_unversioned_database_once() {
+        let mut conn = unversioned_database();
+
+        run(&mut conn).unwrap();
+
+        assert_eq!(user_version(&conn), MIGRATIONS.len());
+        assert_eq!(user_version(&conn), 8);
+        let seen: i64 = conn.query_row("SELECT COUNT(*) FROM seen_watches", [], |row| row.get(0)).unwrap();
+        assert_eq!(seen, 2);
+        // Prices without an EUR amount are dropped, the rest become cents
+        assert_eq!(
+            price_history(&conn),
+            vec![
+                ("a".to_string(), 1250000, "EUR".to_string(), Some(1250000)),
+                ("a".to_string(), 1199999, "EUR".to_string(), Some(1199999)),
+            ]
+        );
+        assert_eq!(
+            listing_prices(&conn),
+            vec![
+                ("a".to_string(), Some(1199999), Some("EUR".to_string()), Some(1199999)),
+                ("b".to_string(), Some(1325000), Some("USD".to_string()), None),
+            ]
+        );
+        let status: (String, i64, String) = conn
+            .query_row(
+                "SELECT status, missed_scrapes, papers_status FROM listings WHERE listing_id = 'b'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(status, ("sold".to_string(), 3, "yes".to_string()));
+
+        // Running again changes nothing
+        let before = (price_history(&conn), listing_prices(&conn));
+        run(&mut conn).unwrap();
+        assert_eq!(user_version(&conn), 8);
+        assert_eq!((price_history(&conn), listing_prices(&conn)), before);
+    }
+
+    #[test]
+    fn refuses_a_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() + 1).unwrap();
+
+        assert!(run(&mut conn).is_err());
+    }
+}
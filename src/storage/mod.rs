@@ -1,14 +1,274 @@
 use async_trait::async_trait;
 use anyhow::Result;
-use crate::models::{Site, WatchId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use crate::health::{FieldCompleteness, SiteHealth};
+use crate::models::{format_price, BoxStatus, ListingStatus, Money, PapersStatus, Site, WatchId, WatchListing};
+use crate::utils::exchange_rate::RateTable;
 
+mod migrations;
 mod sqlite;
 pub use sqlite::SqliteStorage;
 
+/// A price seen for a listing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceObservation {
+    /// Price in the dealer's currency, used to decide whether the price
+    /// changed so exchange rate moves alone never count as a change
+    pub price: Money,
+    pub price_eur: Option<Money>,
+}
+
+impl PriceObservation {
+    /// `None` when the listing carries no price at all
+    pub fn from_listing(listing: &WatchListing) -> Option<Self> {
+        listing.price.map(|price| Self {
+            price,
+            price_eur: listing.price_eur,
+        })
+    }
+    
+    pub fn display(&self) -> String {
+        format_price(Some(self.price), self.price_eur)
+    }
+}
+
+/// A listing that just left the market
+#[derive(Debug, Clone)]
+pub struct EndedListing {
+    /// Last snapshot of the listing while it was on the market
+    pub listing: WatchListing,
+    pub status: ListingStatus,
+    pub time_on_market: chrono::Duration,
+}
+
+/// A new listing or price drop waiting to go out in an email digest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestEntry {
+    /// Dealer name as configured
+    pub site: String,
+    /// Watchlist rule the listing matched, if it was queued for one
+    pub rule: Option<String>,
+    pub listing: WatchListing,
+    /// Previous price for price drops; the new one is the listing's
+    pub old_price: Option<PriceObservation>,
+}
+
+/// A digest entry as queued
+#[derive(Debug, Clone)]
+pub struct QueuedDigestEntry {
+    pub id: i64,
+    pub queued_at: DateTime<Utc>,
+    pub entry: DigestEntry,
+}
+
+/// A Discord webhook post waiting for delivery
+#[derive(Debug, Clone)]
+pub struct OutboxMessage {
+    /// Key of the webhook to post to, see `outbox::webhook_key`. The URL
+    /// itself is not stored, as it contains the webhook's token.
+    pub target: String,
+    /// Notification kind, for logs and metrics
+    pub kind: String,
+    pub payload: serde_json::Value,
+    /// New listing the message announces; it counts as seen once every
+    /// message announcing it was delivered
+    pub listing: Option<(Site, WatchId)>,
+}
+
+/// An outbox message as queued
+#[derive(Debug, Clone)]
+pub struct QueuedOutboxMessage {
+    pub id: i64,
+    /// Failed attempts so far
+    pub attempts: u32,
+    pub message: OutboxMessage,
+}
+
+/// Row counts for one site
+#[derive(Debug, Clone, Default)]
+pub struct SiteStats {
+    pub seen: u64,
+    pub active: u64,
+    pub sold: u64,
+    pub removed: u64,
+    pub price_observations: u64,
+}
+
+/// Overview of what the database holds
+#[derive(Debug, Clone, Default)]
+pub struct DbStats {
+    pub schema_version: u32,
+    /// Counts keyed by site key, in key order
+    pub sites: Vec<(String, SiteStats)>,
+}
+
+/// A tracked listing with where it is in its lifecycle
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredListing {
+    pub site: String,
+    pub listing_id: String,
+    /// Latest snapshot of the listing
+    #[serde(flatten)]
+    pub listing: WatchListing,
+    pub status: ListingStatus,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+/// A price a listing was offered at, and while it was
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceRecord {
+    pub price: Money,
+    pub price_eur: Option<Money>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Filters for `Storage::search_listings`; unset filters match every listing
+#[derive(Debug, Clone, Default)]
+pub struct ListingFilter {
+    pub site: Option<Site>,
+    pub status: Option<ListingStatus>,
+    /// Brand name, ignoring case
+    pub brand: Option<String>,
+    /// Text contained in the title, brand, model or reference, ignoring case
+    pub text: Option<String>,
+    /// Bounds on the EUR price in cents; listings without one never match
+    pub min_price_eur: Option<i64>,
+    pub max_price_eur: Option<i64>,
+    /// Bounds on the year; listings without a plain year never match
+    pub min_year: Option<u32>,
+    pub max_year: Option<u32>,
+    pub box_status: Option<BoxStatus>,
+    pub papers_status: Option<PapersStatus>,
+    pub sort: ListingSort,
+    pub ascending: bool,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+/// Order of `Storage::search_listings` results; listings without a value
+/// for the sort key come last
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListingSort {
+    #[default]
+    FirstSeen,
+    Price,
+    Year,
+    Brand,
+}
+
+impl ListingSort {
+    pub fn key(&self) -> &'static str {
+        match self {
+            ListingSort::FirstSeen => "first_seen",
+            ListingSort::Price => "price",
+            ListingSort::Year => "year",
+            ListingSort::Brand => "brand",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "first_seen" => Some(ListingSort::FirstSeen),
+            "price" => Some(ListingSort::Price),
+            "year" => Some(ListingSort::Year),
+            "brand" => Some(ListingSort::Brand),
+            _ => None,
+        }
+    }
+
+    /// Newest listings come first by default, everything else is ascending
+    pub fn ascending_by_default(&self) -> bool {
+        *self != ListingSort::FirstSeen
+    }
+}
+
+/// Field completeness of one scrape
+#[derive(Debug, Clone)]
+pub struct CompletenessRecord {
+    pub site: String,
+    /// When the scrape finished, in UTC
+    pub checked_at: chrono::NaiveDateTime,
+    pub completeness: FieldCompleteness,
+}
+
 #[async_trait]
 pub trait Storage: Send + Sync {
     async fn migrate(&self) -> Result<()>;
     async fn has_seen(&self, site: &Site, watch_id: &WatchId) -> Result<bool>;
     async fn mark_seen(&self, site: &Site, watch_id: &WatchId) -> Result<()>;
-    async fn import_from_json(&self, json_path: &str) -> Result<()>;
-}
\ No newline at end of file
+    /// Forget that an id was seen. Returns whether it had been.
+    async fn unmark_seen(&self, site: &Site, watch_id: &WatchId) -> Result<bool>;
+    /// Record a price observation. Returns the previous price when it differs
+    /// from `price`, i.e. when the listing's price changed.
+    async fn record_price(
+        &self,
+        site: &Site,
+        listing_id: &WatchId,
+        price: &PriceObservation,
+    ) -> Result<Option<PriceObservation>>;
+    /// Record that a listing was present in a scrape: refreshes its snapshot
+    /// and `last_seen`, and reactivates it if it had ended
+    async fn touch_listing(&self, site: &Site, listing_id: &WatchId, listing: &WatchListing) -> Result<()>;
+    /// End an active listing as sold. Returns `None` if it was not active.
+    async fn mark_sold(&self, site: &Site, listing_id: &WatchId) -> Result<Option<EndedListing>>;
+    /// Count a missed scrape for every active listing of `site` not in
+    /// `present`, and end those missing for `threshold` consecutive scrapes
+    /// as removed
+    async fn record_missing(
+        &self,
+        site: &Site,
+        present: &[WatchId],
+        threshold: u32,
+    ) -> Result<Vec<EndedListing>>;
+    /// Tracked listings matching `filter`, in its order
+    async fn search_listings(&self, filter: &ListingFilter) -> Result<Vec<StoredListing>>;
+    async fn load_listing(&self, site: &Site, listing_id: &WatchId) -> Result<Option<StoredListing>>;
+    /// Every price recorded for a listing, oldest first
+    async fn price_history(&self, site: &Site, listing_id: &WatchId) -> Result<Vec<PriceRecord>>;
+    /// `price_history` of several listings in one go; listings without a
+    /// recorded price are left out
+    async fn price_histories(&self, listings: &[(Site, WatchId)]) -> Result<HashMap<(Site, WatchId), Vec<PriceRecord>>>;
+    /// Replace the persisted exchange rates with `rates`
+    async fn save_exchange_rates(&self, rates: &RateTable) -> Result<()>;
+    /// The last exchange rates saved, if any
+    async fn load_exchange_rates(&self) -> Result<Option<RateTable>>;
+    /// Scraper health of `site`; `None` until it has been checked
+    async fn load_site_health(&self, site: &Site) -> Result<Option<SiteHealth>>;
+    async fn save_site_health(&self, site: &Site, health: &SiteHealth) -> Result<()>;
+    /// Append the field completeness of a scrape of `site`
+    async fn record_completeness(&self, site: &Site, completeness: &FieldCompleteness) -> Result<()>;
+    /// Latest completeness records, newest first, of one site or all sites
+    async fn completeness_history(&self, site: Option<&Site>, limit: u32) -> Result<Vec<CompletenessRecord>>;
+    /// Add an entry to the digest named `digest`
+    async fn queue_digest_entry(&self, digest: &str, entry: &DigestEntry) -> Result<()>;
+    /// Entries of `digest` not yet sent, oldest first
+    async fn pending_digest_entries(&self, digest: &str) -> Result<Vec<QueuedDigestEntry>>;
+    /// Drop digest entries once they were sent
+    async fn remove_digest_entries(&self, ids: &[i64]) -> Result<()>;
+    /// Queue a Discord message for delivery
+    async fn queue_outbox_message(&self, message: &OutboxMessage) -> Result<()>;
+    /// Queued messages due for an attempt, oldest first
+    async fn due_outbox_messages(&self, limit: u32) -> Result<Vec<QueuedOutboxMessage>>;
+    /// Remove a message that was delivered or given up on, marking the
+    /// listing it announced as seen if no other message about it is left.
+    /// A listing Discord keeps rejecting is not queued again every check.
+    async fn finish_outbox_message(&self, id: i64) -> Result<()>;
+    /// Record a failed attempt and when to try again, holding back later
+    /// messages for the same webhook until then
+    async fn retry_outbox_message(&self, id: i64, next_attempt_at: DateTime<Utc>, error: &str) -> Result<()>;
+    /// Whether messages announcing a listing are still waiting for delivery
+    async fn has_pending_outbox(&self, site: &Site, listing_id: &WatchId) -> Result<bool>;
+    /// Number of queued messages
+    async fn outbox_len(&self) -> Result<u64>;
+    /// Import seen watch ids from a JSON file of `{"<site>": ["<id>", ...]}`
+    async fn import_from_json(&self, json_path: &Path) -> Result<()>;
+    async fn stats(&self) -> Result<DbStats>;
+    /// Wait for writes in progress and flush everything to disk before exit
+    async fn close(&self) -> Result<()>;
+}
@@ -1,19 +1,33 @@
 use async_trait::async_trait;
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection, OptionalExtension};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rusqlite::backup::Progress;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, DatabaseName, OptionalExtension};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use tracing::info;
 
-use crate::models::{Site, WatchId};
-use crate::storage::Storage;
+use crate::health::{FieldCompleteness, HealthStatus, SiteHealth};
+use crate::metrics;
+use crate::models::{
+    BoxStatus, Currency, ListingStatus, Money, PapersStatus, Site, WatchId, WatchListing, DETAIL_FIELDS,
+    EMOJI_QUESTION,
+};
+use crate::storage::{
+    migrations, CompletenessRecord, DbStats, DigestEntry, EndedListing, ListingFilter, ListingSort,
+    OutboxMessage, PriceObservation, PriceRecord, QueuedDigestEntry, QueuedOutboxMessage, SiteStats, Storage,
+    StoredListing,
+};
+use crate::utils::exchange_rate::RateTable;
 
 pub struct SqliteStorage {
     conn: Arc<Mutex<Connection>>,
 }
 
 impl SqliteStorage {
-    pub async fn new(db_path: &str) -> Result<Self> {
+    pub async fn new(db_path: &Path) -> Result<Self> {
         let conn = Connection::open(db_path)
             .context("Failed to open SQLite database")?;
         
@@ -21,34 +35,38 @@ impl SqliteStorage {
             conn: Arc::new(Mutex::new(conn)),
         })
     }
+
+    /// In-memory copy of the database at `db_path`, used for dry runs: it
+    /// starts from the real state but writes never reach the file
+    pub async fn overlay(db_path: &Path) -> Result<Self> {
+        let mut conn = Connection::open_in_memory()
+            .context("Failed to open in-memory SQLite database")?;
+        
+        if db_path.exists() {
+            conn.restore(DatabaseName::Main, db_path, None::<fn(Progress)>)
+                .with_context(|| format!("Failed to copy {} into memory", db_path.display()))?;
+        }
+        
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
 }
 
 #[async_trait]
 impl Storage for SqliteStorage {
     async fn migrate(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let _timer = metrics::time_db("migrate");
+        let mut conn = self.conn.lock().unwrap();
         
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS seen_watches (
-                site TEXT NOT NULL,
-                watch_id TEXT NOT NULL,
-                first_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
-                PRIMARY KEY (site, watch_id)
-            )",
-            [],
-        )?;
-        
-        // Create index for faster lookups
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_site ON seen_watches(site)",
-            [],
-        )?;
+        migrations::run(&mut conn)?;
         
         info!("Database migration completed");
         Ok(())
     }
     
     async fn has_seen(&self, site: &Site, watch_id: &WatchId) -> Result<bool> {
+        let _timer = metrics::time_db("has_seen");
         let conn = self.conn.lock().unwrap();
         
         let count: Option<i32> = conn
@@ -63,6 +81,7 @@ impl Storage for SqliteStorage {
     }
     
     async fn mark_seen(&self, site: &Site, watch_id: &WatchId) -> Result<()> {
+        let _timer = metrics::time_db("mark_seen");
         let conn = self.conn.lock().unwrap();
         
         conn.execute(
@@ -73,13 +92,670 @@ impl Storage for SqliteStorage {
         Ok(())
     }
     
-    async fn import_from_json(&self, json_path: &str) -> Result<()> {
-        if !Path::new(json_path).exists() {
-            info!("No existing JSON file to import");
-            return Ok(());
+    async fn unmark_seen(&self, site: &Site, watch_id: &WatchId) -> Result<bool> {
+        let _timer = metrics::time_db("unmark_seen");
+        let conn = self.conn.lock().unwrap();
+        
+        let deleted = conn.execute(
+            "DELETE FROM seen_watches WHERE site = ?1 AND watch_id = ?2",
+            params![site.key(), &watch_id.0],
+        )?;
+        
+        Ok(deleted > 0)
+    }
+    
+    async fn record_price(
+        &self,
+        site: &Site,
+        listing_id: &WatchId,
+        price: &PriceObservation,
+    ) -> Result<Option<PriceObservation>> {
+        let _timer = metrics::time_db("record_price");
+        let conn = self.conn.lock().unwrap();
+        
+        let latest = conn
+            .query_row(
+                "SELECT rowid, amount_minor, currency, price_eur_minor FROM price_history
+                 WHERE site = ?1 AND listing_id = ?2
+                 ORDER BY rowid DESC LIMIT 1",
+                params![site.key(), &listing_id.0],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        money(row.get(1)?, row.get(2)?),
+                        row.get::<_, Option<i64>>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+        
+        let latest = latest.and_then(|(rowid, previous, previous_eur)| {
+            let previous = PriceObservation {
+                price: previous?,
+                price_eur: previous_eur.map(|amount| Money::new(amount, Currency::Eur)),
+            };
+            Some((rowid, previous))
+        });
+        
+        match latest {
+            Some((rowid, previous)) if previous.price == price.price => {
+                conn.execute(
+                    "UPDATE price_history SET last_seen = CURRENT_TIMESTAMP WHERE rowid = ?1",
+                    params![rowid],
+                )?;
+                Ok(None)
+            }
+            latest => {
+                conn.execute(
+                    "INSERT INTO price_history (site, listing_id, amount_minor, currency, price_eur_minor)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        site.key(),
+                        &listing_id.0,
+                        price.price.amount_minor,
+                        price.price.currency.code(),
+                        price.price_eur.map(|price| price.amount_minor),
+                    ],
+                )?;
+                Ok(latest.map(|(_, previous)| previous))
+            }
+        }
+    }
+    
+    async fn touch_listing(&self, site: &Site, listing_id: &WatchId, listing: &WatchListing) -> Result<()> {
+        let _timer = metrics::time_db("touch_listing");
+        let conn = self.conn.lock().unwrap();
+        
+        // Listings seen before lifecycle tracking keep their original first_seen
+        conn.execute(
+            "INSERT INTO listings (
+                site, listing_id, brand, model, reference, year,
+                price_minor, price_currency, price_eur_minor, papers_status, box_status,
+                condition, case_material, diameter, title, watch_url, image_url, site_name,
+                first_seen
+            )
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, COALESCE(
+                 (SELECT first_seen FROM seen_watches WHERE site = ?1 AND watch_id = ?2),
+                 CURRENT_TIMESTAMP
+             ))
+             ON CONFLICT (site, listing_id) DO UPDATE SET
+                 brand = excluded.brand,
+                 model = excluded.model,
+                 reference = excluded.reference,
+                 year = excluded.year,
+                 price_minor = excluded.price_minor,
+                 price_currency = excluded.price_currency,
+                 price_eur_minor = excluded.price_eur_minor,
+                 papers_status = excluded.papers_status,
+                 box_status = excluded.box_status,
+                 condition = excluded.condition,
+                 case_material = excluded.case_material,
+                 diameter = excluded.diameter,
+                 title = excluded.title,
+                 watch_url = excluded.watch_url,
+                 image_url = excluded.image_url,
+                 site_name = excluded.site_name,
+                 status = 'active',
+                 missed_scrapes = 0,
+                 last_seen = CURRENT_TIMESTAMP,
+                 ended_at = NULL",
+            params![
+                site.key(),
+                &listing_id.0,
+                &listing.brand,
+                &listing.model,
+                &listing.reference,
+                &listing.year,
+                listing.price.map(|price| price.amount_minor),
+                listing.price.map(|price| price.currency.code()),
+                listing.price_eur.map(|price| price.amount_minor),
+                listing.papers_status.key(),
+                listing.box_status.key(),
+                &listing.condition_display,
+                &listing.case_material,
+                &listing.diameter,
+                &listing.title,
+                &listing.watch_url,
+                &listing.image_url,
+                &listing.site_name,
+            ],
+        )?;
+        
+        Ok(())
+    }
+    
+    async fn mark_sold(&self, site: &Site, listing_id: &WatchId) -> Result<Option<EndedListing>> {
+        let _timer = metrics::time_db("mark_sold");
+        let conn = self.conn.lock().unwrap();
+        
+        let updated = conn.execute(
+            "UPDATE listings
+             SET status = 'sold', last_seen = CURRENT_TIMESTAMP, ended_at = CURRENT_TIMESTAMP
+             WHERE site = ?1 AND listing_id = ?2 AND status = 'active'",
+            params![site.key(), &listing_id.0],
+        )?;
+        
+        if updated == 0 {
+            return Ok(None);
+        }
+        
+        load_ended_listing(&conn, site, listing_id).map(Some)
+    }
+    
+    async fn record_missing(
+        &self,
+        site: &Site,
+        present: &[WatchId],
+        threshold: u32,
+    ) -> Result<Vec<EndedListing>> {
+        let _timer = metrics::time_db("record_missing");
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let present: HashSet<&str> = present.iter().map(|id| id.0.as_str()).collect();
+        
+        let active: Vec<String> = {
+            let mut stmt = tx.prepare(
+                "SELECT listing_id FROM listings WHERE site = ?1 AND status = 'active'",
+            )?;
+            let rows = stmt.query_map(params![site.key()], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        
+        let mut ended = Vec::new();
+        
+        for listing_id in active.into_iter().filter(|id| !present.contains(id.as_str())) {
+            // A removed listing left the market when it was last seen
+            let status: String = tx.query_row(
+                "UPDATE listings
+                 SET missed_scrapes = missed_scrapes + 1,
+                     status = CASE WHEN missed_scrapes + 1 >= ?3 THEN 'removed' ELSE status END,
+                     ended_at = CASE WHEN missed_scrapes + 1 >= ?3 THEN last_seen ELSE ended_at END
+                 WHERE site = ?1 AND listing_id = ?2
+                 RETURNING status",
+                params![site.key(), &listing_id, threshold],
+                |row| row.get(0),
+            )?;
+            
+            if status == ListingStatus::Removed.key() {
+                ended.push(load_ended_listing(&tx, site, &WatchId(listing_id))?);
+            }
+        }
+        
+        tx.commit()?;
+        Ok(ended)
+    }
+    
+    async fn search_listings(&self, filter: &ListingFilter) -> Result<Vec<StoredListing>> {
+        let _timer = metrics::time_db("search_listings");
+        let conn = self.conn.lock().unwrap();
+        
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(site) = &filter.site {
+            conditions.push("site = ?");
+            values.push(Value::from(site.key().to_string()));
+        }
+        if let Some(status) = &filter.status {
+            conditions.push("status = ?");
+            values.push(Value::from(status.key().to_string()));
+        }
+        if let Some(brand) = &filter.brand {
+            conditions.push("brand LIKE ? ESCAPE '\\'");
+            values.push(Value::from(escape_like(brand.trim())));
+        }
+        if let Some(text) = &filter.text {
+            conditions.push(
+                "(title LIKE ? ESCAPE '\\' OR brand LIKE ? ESCAPE '\\'
+                  OR model LIKE ? ESCAPE '\\' OR reference LIKE ? ESCAPE '\\')",
+            );
+            let pattern = Value::from(format!("%{}%", escape_like(text.trim())));
+            values.extend(std::iter::repeat_n(pattern, 4));
+        }
+        if let Some(min) = filter.min_price_eur {
+            conditions.push("price_eur_minor >= ?");
+            values.push(Value::from(min));
+        }
+        if let Some(max) = filter.max_price_eur {
+            conditions.push("price_eur_minor <= ?");
+            values.push(Value::from(max));
+        }
+        // Years such as `ca. 1970` or ❓ cast to 0 and never match
+        if let Some(min) = filter.min_year {
+            conditions.push("CAST(year AS INTEGER) >= ?");
+            values.push(Value::from(min));
+        }
+        if let Some(max) = filter.max_year {
+            conditions.push("CAST(year AS INTEGER) BETWEEN 1 AND ?");
+            values.push(Value::from(max));
+        }
+        if let Some(box_status) = &filter.box_status {
+            conditions.push("box_status = ?");
+            values.push(Value::from(box_status.key().to_string()));
+        }
+        if let Some(papers_status) = &filter.papers_status {
+            conditions.push("papers_status = ?");
+            values.push(Value::from(papers_status.key().to_string()));
+        }
+        
+        let mut sql = String::new();
+        if !conditions.is_empty() {
+            sql = format!(" WHERE {}", conditions.join(" AND "));
+        }
+        let (missing, key) = match filter.sort {
+            ListingSort::FirstSeen => ("first_seen IS NULL", "first_seen"),
+            ListingSort::Price => ("price_eur_minor IS NULL", "price_eur_minor"),
+            ListingSort::Year => ("CAST(year AS INTEGER) = 0", "CAST(year AS INTEGER)"),
+            ListingSort::Brand => {
+                values.push(Value::from(EMOJI_QUESTION.to_string()));
+                ("brand = ?", "brand COLLATE NOCASE")
+            }
+        };
+        let direction = if filter.ascending { "ASC" } else { "DESC" };
+        sql.push_str(&format!(
+            " ORDER BY {}, {} {}, rowid {} LIMIT ? OFFSET ?",
+            missing, key, direction, direction
+        ));
+        values.push(Value::from(filter.limit));
+        values.push(Value::from(filter.offset));
+        
+        load_stored_listings(&conn, &sql, values)
+    }
+    
+    async fn load_listing(&self, site: &Site, listing_id: &WatchId) -> Result<Option<StoredListing>> {
+        let _timer = metrics::time_db("load_listing");
+        let conn = self.conn.lock().unwrap();
+        
+        let values = vec![Value::from(site.key().to_string()), Value::from(listing_id.0.clone())];
+        let listings = load_stored_listings(&conn, " WHERE site = ? AND listing_id = ?", values)?;
+        
+        Ok(listings.into_iter().next())
+    }
+    
+    async fn price_history(&self, site: &Site, listing_id: &WatchId) -> Result<Vec<PriceRecord>> {
+        let _timer = metrics::time_db("price_history");
+        let conn = self.conn.lock().unwrap();
+        
+        let key = (site.clone(), listing_id.clone());
+        let mut histories = read_price_histories(&conn, std::slice::from_ref(&key))?;
+        Ok(histories.remove(&key).unwrap_or_default())
+    }
+    
+    async fn price_histories(&self, listings: &[(Site, WatchId)]) -> Result<HashMap<(Site, WatchId), Vec<PriceRecord>>> {
+        let _timer = metrics::time_db("price_histories");
+        let conn = self.conn.lock().unwrap();
+        
+        read_price_histories(&conn, listings)
+    }
+    
+    async fn save_exchange_rates(&self, rates: &RateTable) -> Result<()> {
+        let _timer = metrics::time_db("save_exchange_rates");
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        
+        tx.execute("DELETE FROM exchange_rates", [])?;
+        for (currency, rate) in &rates.rates {
+            tx.execute(
+                "INSERT INTO exchange_rates (base, currency, rate, fetched_at) VALUES (?1, ?2, ?3, ?4)",
+                params![rates.base.code(), currency.code(), rate, rates.fetched_at.to_rfc3339()],
+            )?;
+        }
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    async fn load_exchange_rates(&self) -> Result<Option<RateTable>> {
+        let _timer = metrics::time_db("load_exchange_rates");
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare("SELECT base, currency, rate, fetched_at FROM exchange_rates")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, f64>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        
+        let mut table: Option<RateTable> = None;
+        for (base, currency, rate, fetched_at) in rows {
+            let (Some(base), Some(currency)) = (Currency::from_code(&base), Currency::from_code(&currency)) else {
+                continue;
+            };
+            let fetched_at = DateTime::parse_from_rfc3339(&fetched_at)
+                .context("Invalid exchange rate timestamp")?
+                .with_timezone(&Utc);
+            
+            table
+                .get_or_insert_with(|| RateTable { base, rates: HashMap::new(), fetched_at })
+                .rates
+                .insert(currency, rate);
         }
         
-        let content = std::fs::read_to_string(json_path)?;
+        Ok(table)
+    }
+    
+    async fn load_site_health(&self, site: &Site) -> Result<Option<SiteHealth>> {
+        let _timer = metrics::time_db("load_site_health");
+        let conn = self.conn.lock().unwrap();
+        
+        let row = conn
+            .query_row(
+                "SELECT status, consecutive_failures, average_items, samples, last_items,
+                        unknown_share, problems, last_error, last_success
+                 FROM site_health WHERE site = ?1",
+                params![site.key()],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        SiteHealth {
+                            consecutive_failures: row.get(1)?,
+                            average_items: row.get(2)?,
+                            samples: row.get(3)?,
+                            last_items: row.get(4)?,
+                            unknown_share: row.get(5)?,
+                            last_error: row.get(7)?,
+                            ..Default::default()
+                        },
+                        row.get::<_, String>(6)?,
+                        row.get::<_, Option<String>>(8)?,
+                    ))
+                },
+            )
+            .optional()?;
+        
+        let Some((status, health, problems, last_success)) = row else {
+            return Ok(None);
+        };
+        
+        Ok(Some(SiteHealth {
+            status: HealthStatus::from_key(&status)
+                .ok_or_else(|| anyhow::anyhow!("Unknown health status `{}`", status))?,
+            problems: serde_json::from_str(&problems).context("Invalid site health problems")?,
+            last_success: last_success
+                .map(|value| DateTime::parse_from_rfc3339(&value).map(|time| time.with_timezone(&Utc)))
+                .transpose()
+                .context("Invalid site health timestamp")?,
+            ..health
+        }))
+    }
+    
+    async fn save_site_health(&self, site: &Site, health: &SiteHealth) -> Result<()> {
+        let _timer = metrics::time_db("save_site_health");
+        let conn = self.conn.lock().unwrap();
+        
+        conn.execute(
+            "INSERT OR REPLACE INTO site_health (
+                site, status, consecutive_failures, average_items, samples, last_items,
+                unknown_share, problems, last_error, last_success, updated_at
+            )
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, CURRENT_TIMESTAMP)",
+            params![
+                site.key(),
+                health.status.key(),
+                health.consecutive_failures,
+                health.average_items,
+                health.samples,
+                health.last_items,
+                health.unknown_share,
+                serde_json::to_string(&health.problems)?,
+                &health.last_error,
+                health.last_success.map(|time| time.to_rfc3339()),
+            ],
+        )?;
+        
+        Ok(())
+    }
+    
+    async fn record_completeness(&self, site: &Site, completeness: &FieldCompleteness) -> Result<()> {
+        let _timer = metrics::time_db("record_completeness");
+        let conn = self.conn.lock().unwrap();
+        
+        // One column per detail field, named like the field
+        let placeholders: Vec<String> = (0..DETAIL_FIELDS.len()).map(|index| format!("?{}", index + 3)).collect();
+        let mut values = vec![Value::from(site.key().to_string()), Value::from(completeness.listings)];
+        values.extend(completeness.fields.iter().map(|(_, percent)| Value::from(*percent)));
+        
+        conn.execute(
+            &format!(
+                "INSERT INTO field_completeness (site, listings, {}) VALUES (?1, ?2, {})",
+                DETAIL_FIELDS.join(", "),
+                placeholders.join(", ")
+            ),
+            params_from_iter(values),
+        )?;
+        
+        Ok(())
+    }
+    
+    async fn completeness_history(&self, site: Option<&Site>, limit: u32) -> Result<Vec<CompletenessRecord>> {
+        let _timer = metrics::time_db("completeness_history");
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(&format!(
+            "SELECT site, checked_at, listings, {} FROM field_completeness
+             WHERE ?1 IS NULL OR site = ?1
+             ORDER BY rowid DESC LIMIT ?2",
+            DETAIL_FIELDS.join(", ")
+        ))?;
+        let rows = stmt
+            .query_map(params![site.map(|site| site.key()), limit], |row| {
+                let mut fields = DETAIL_FIELDS.map(|name| (name, 0.0));
+                for (index, (_, percent)) in fields.iter_mut().enumerate() {
+                    *percent = row.get(index + 3)?;
+                }
+                
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    FieldCompleteness {
+                        listings: row.get(2)?,
+                        fields,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        
+        rows.into_iter()
+            .map(|(site, checked_at, completeness)| {
+                Ok(CompletenessRecord {
+                    site,
+                    checked_at: NaiveDateTime::parse_from_str(&checked_at, "%Y-%m-%d %H:%M:%S")
+                        .context("Invalid completeness timestamp")?,
+                    completeness,
+                })
+            })
+            .collect()
+    }
+    
+    async fn queue_digest_entry(&self, digest: &str, entry: &DigestEntry) -> Result<()> {
+        let _timer = metrics::time_db("queue_digest_entry");
+        let conn = self.conn.lock().unwrap();
+        
+        conn.execute(
+            "INSERT INTO digest_entries (digest, entry) VALUES (?1, ?2)",
+            params![digest, serde_json::to_string(entry)?],
+        )?;
+        
+        Ok(())
+    }
+    
+    async fn pending_digest_entries(&self, digest: &str) -> Result<Vec<QueuedDigestEntry>> {
+        let _timer = metrics::time_db("pending_digest_entries");
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(
+            "SELECT id, entry, queued_at FROM digest_entries WHERE digest = ?1 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map(params![digest], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        
+        rows.into_iter()
+            .map(|(id, entry, queued_at)| {
+                Ok(QueuedDigestEntry {
+                    id,
+                    queued_at: sqlite_time(&queued_at)?,
+                    entry: serde_json::from_str(&entry)
+                        .with_context(|| format!("Invalid digest entry {}", id))?,
+                })
+            })
+            .collect()
+    }
+    
+    async fn remove_digest_entries(&self, ids: &[i64]) -> Result<()> {
+        let _timer = metrics::time_db("remove_digest_entries");
+        let mut conn = self.conn.lock().unwrap();
+        
+        let tx = conn.transaction()?;
+        for id in ids {
+            tx.execute("DELETE FROM digest_entries WHERE id = ?1", params![id])?;
+        }
+        tx.commit()?;
+        
+        Ok(())
+    }
+    
+    async fn queue_outbox_message(&self, message: &OutboxMessage) -> Result<()> {
+        let _timer = metrics::time_db("queue_outbox_message");
+        let conn = self.conn.lock().unwrap();
+        
+        let (site, listing_id) = match &message.listing {
+            Some((site, listing_id)) => (Some(site.key()), Some(listing_id.0.as_str())),
+            None => (None, None),
+        };
+        conn.execute(
+            "INSERT INTO outbox (target, kind, payload, site, listing_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![&message.target, &message.kind, message.payload.to_string(), site, listing_id],
+        )?;
+        
+        Ok(())
+    }
+    
+    async fn due_outbox_messages(&self, limit: u32) -> Result<Vec<QueuedOutboxMessage>> {
+        let _timer = metrics::time_db("due_outbox_messages");
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(
+            "SELECT id, attempts, target, kind, payload, site, listing_id FROM outbox
+             WHERE next_attempt_at <= CURRENT_TIMESTAMP ORDER BY id LIMIT ?1",
+        )?;
+        let rows = stmt
+            .query_map(params![limit], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        
+        rows.into_iter()
+            .map(|(id, attempts, target, kind, payload, site, listing_id)| {
+                let listing = match (site, listing_id) {
+                    (Some(site), Some(listing_id)) => Some((
+                        Site::from_key(&site).ok_or_else(|| anyhow::anyhow!("Unknown site `{}`", site))?,
+                        WatchId(listing_id),
+                    )),
+                    _ => None,
+                };
+                
+                Ok(QueuedOutboxMessage {
+                    id,
+                    attempts,
+                    message: OutboxMessage {
+                        target,
+                        kind,
+                        payload: serde_json::from_str(&payload)
+                            .with_context(|| format!("Invalid outbox payload {}", id))?,
+                        listing,
+                    },
+                })
+            })
+            .collect()
+    }
+    
+    async fn finish_outbox_message(&self, id: i64) -> Result<()> {
+        let _timer = metrics::time_db("finish_outbox_message");
+        let mut conn = self.conn.lock().unwrap();
+        
+        let tx = conn.transaction()?;
+        let listing: Option<(Option<String>, Option<String>)> = tx
+            .query_row(
+                "DELETE FROM outbox WHERE id = ?1 RETURNING site, listing_id",
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        
+        if let Some((Some(site), Some(listing_id))) = listing {
+            tx.execute(
+                "INSERT OR IGNORE INTO seen_watches (site, watch_id)
+                 SELECT ?1, ?2 WHERE NOT EXISTS (SELECT 1 FROM outbox WHERE site = ?1 AND listing_id = ?2)",
+                params![site, listing_id],
+            )?;
+        }
+        tx.commit()?;
+        
+        Ok(())
+    }
+    
+    async fn retry_outbox_message(&self, id: i64, next_attempt_at: DateTime<Utc>, error: &str) -> Result<()> {
+        let _timer = metrics::time_db("retry_outbox_message");
+        let mut conn = self.conn.lock().unwrap();
+        
+        let next_attempt_at = next_attempt_at.format("%Y-%m-%d %H:%M:%S").to_string();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE outbox SET attempts = attempts + 1, next_attempt_at = ?2, last_error = ?3 WHERE id = ?1",
+            params![id, &next_attempt_at, error],
+        )?;
+        // Later messages for the webhook keep their place behind this one
+        tx.execute(
+            "UPDATE outbox SET next_attempt_at = ?2
+             WHERE target = (SELECT target FROM outbox WHERE id = ?1) AND id > ?1 AND next_attempt_at < ?2",
+            params![id, &next_attempt_at],
+        )?;
+        tx.commit()?;
+        
+        Ok(())
+    }
+    
+    async fn has_pending_outbox(&self, site: &Site, listing_id: &WatchId) -> Result<bool> {
+        let _timer = metrics::time_db("has_pending_outbox");
+        let conn = self.conn.lock().unwrap();
+        
+        let pending: Option<i32> = conn
+            .query_row(
+                "SELECT 1 FROM outbox WHERE site = ?1 AND listing_id = ?2 LIMIT 1",
+                params![site.key(), &listing_id.0],
+                |row| row.get(0),
+            )
+            .optional()?;
+        
+        Ok(pending.is_some())
+    }
+    
+    async fn outbox_len(&self) -> Result<u64> {
+        let _timer = metrics::time_db("outbox_len");
+        let conn = self.conn.lock().unwrap();
+        
+        Ok(conn.query_row("SELECT COUNT(*) FROM outbox", [], |row| row.get(0))?)
+    }
+    
+    async fn import_from_json(&self, json_path: &Path) -> Result<()> {
+        let _timer = metrics::time_db("import_from_json");
+        let content = std::fs::read_to_string(json_path)
+            .with_context(|| format!("Failed to read {}", json_path.display()))?;
         let data: serde_json::Value = serde_json::from_str(&content)?;
         
         let mut conn = self.conn.lock().unwrap();
@@ -103,7 +779,323 @@ impl Storage for SqliteStorage {
         }
         
         tx.commit()?;
-        info!("Successfully imported data from {}", json_path);
+        info!("Successfully imported data from {}", json_path.display());
         Ok(())
     }
-}
\ No newline at end of file
+
+    async fn stats(&self) -> Result<DbStats> {
+        let _timer = metrics::time_db("stats");
+        let conn = self.conn.lock().unwrap();
+        let mut sites: BTreeMap<String, SiteStats> = BTreeMap::new();
+
+        let schema_version = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        let mut stmt = conn.prepare("SELECT site, COUNT(*) FROM seen_watches GROUP BY site")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?)))?;
+        for row in rows {
+            let (site, count) = row?;
+            sites.entry(site).or_default().seen = count;
+        }
+
+        let mut stmt = conn.prepare("SELECT site, status, COUNT(*) FROM listings GROUP BY site, status")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, u64>(2)?))
+        })?;
+        for row in rows {
+            let (site, status, count) = row?;
+            let stats = sites.entry(site).or_default();
+            match ListingStatus::from_key(&status) {
+                Some(ListingStatus::Active) => stats.active = count,
+                Some(ListingStatus::Sold) => stats.sold = count,
+                Some(ListingStatus::Removed) => stats.removed = count,
+                None => {}
+            }
+        }
+
+        let mut stmt = conn.prepare("SELECT site, COUNT(*) FROM price_history GROUP BY site")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?)))?;
+        for row in rows {
+            let (site, count) = row?;
+            sites.entry(site).or_default().price_observations = count;
+        }
+
+        Ok(DbStats {
+            schema_version,
+            sites: sites.into_iter().collect(),
+        })
+    }
+
+    async fn close(&self) -> Result<()> {
+        let _timer = metrics::time_db("close");
+        // Holding the lock means no other write is in progress
+        let conn = self.conn.lock().unwrap();
+
+        // Move a write-ahead log, if any, into the database file
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        conn.execute_batch("PRAGMA optimize")?;
+
+        Ok(())
+    }
+}
+
+/// Columns that make up a `WatchListing`, in the order `row_to_listing` reads them
+const LISTING_COLUMNS: &str = "brand, model, reference, year, price_minor, price_currency,
+    price_eur_minor, papers_status, box_status, condition, case_material, diameter, title,
+    watch_url, image_url, site_name";
+
+fn row_to_listing(row: &rusqlite::Row<'_>) -> rusqlite::Result<WatchListing> {
+    Ok(WatchListing {
+        brand: row.get(0)?,
+        model: row.get(1)?,
+        reference: row.get(2)?,
+        year: row.get(3)?,
+        price: money(row.get(4)?, row.get(5)?),
+        price_eur: row.get::<_, Option<i64>>(6)?.map(|amount| Money::new(amount, Currency::Eur)),
+        // Only scrapes are matched against composite ids
+        legacy_price_key: String::new(),
+        papers_status: PapersStatus::from_key(&row.get::<_, String>(7)?),
+        box_status: BoxStatus::from_key(&row.get::<_, String>(8)?),
+        condition_display: row.get(9)?,
+        case_material: row.get(10)?,
+        diameter: row.get(11)?,
+        title: row.get(12)?,
+        watch_url: row.get(13)?,
+        image_url: row.get(14)?,
+        site_name: row.get(15)?,
+        sold: false,
+    })
+}
+
+fn money(amount_minor: Option<i64>, currency: Option<String>) -> Option<Money> {
+    Some(Money::new(amount_minor?, Currency::from_code(&currency?)?))
+}
+
+/// Every price recorded for each of `listings` in one query, oldest first.
+/// Listings without a recorded price are left out.
+fn read_price_histories(
+    conn: &Connection,
+    listings: &[(Site, WatchId)],
+) -> Result<HashMap<(Site, WatchId), Vec<PriceRecord>>> {
+    let mut histories: HashMap<(Site, WatchId), Vec<PriceRecord>> = HashMap::new();
+    if listings.is_empty() {
+        return Ok(histories);
+    }
+    
+    let pairs: Vec<String> = (0..listings.len())
+        .map(|index| format!("(?{}, ?{})", 2 * index + 1, 2 * index + 2))
+        .collect();
+    let values = listings
+        .iter()
+        .flat_map(|(site, listing_id)| [site.key().to_string(), listing_id.0.clone()]);
+    
+    let mut stmt = conn.prepare(&format!(
+        "SELECT site, listing_id, amount_minor, currency, price_eur_minor, first_seen, last_seen FROM price_history
+         WHERE (site, listing_id) IN (VALUES {})
+         ORDER BY rowid",
+        pairs.join(", ")
+    ))?;
+    let rows = stmt
+        .query_map(params_from_iter(values), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                money(row.get(2)?, row.get(3)?),
+                row.get::<_, Option<i64>>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    
+    for (site, listing_id, price, price_eur, first_seen, last_seen) in rows {
+        // Prices in a currency this build no longer knows are skipped
+        let (Some(site), Some(price)) = (Site::from_key(&site), price) else {
+            continue;
+        };
+        histories.entry((site, WatchId(listing_id))).or_default().push(PriceRecord {
+            price,
+            price_eur: price_eur.map(|amount| Money::new(amount, Currency::Eur)),
+            first_seen: sqlite_time(&first_seen)?,
+            last_seen: sqlite_time(&last_seen)?,
+        });
+    }
+    
+    Ok(histories)
+}
+
+fn load_ended_listing(conn: &Connection, site: &Site, listing_id: &WatchId) -> Result<EndedListing> {
+    let (listing, status, seconds_on_market): (WatchListing, String, Option<i64>) = conn.query_row(
+        &format!(
+            "SELECT {}, status, strftime('%s', ended_at) - strftime('%s', first_seen)
+             FROM listings WHERE site = ?1 AND listing_id = ?2",
+            LISTING_COLUMNS
+        ),
+        params![site.key(), &listing_id.0],
+        |row| Ok((row_to_listing(row)?, row.get(16)?, row.get(17)?)),
+    )?;
+    
+    Ok(EndedListing {
+        status: ListingStatus::from_key(&status)
+            .ok_or_else(|| anyhow::anyhow!("Unknown listing status `{}`", status))?,
+        listing: WatchListing {
+            sold: status == ListingStatus::Sold.key(),
+            ..listing
+        },
+        time_on_market: chrono::Duration::seconds(seconds_on_market.unwrap_or(0).max(0)),
+    })
+}
+
+/// Tracked listings selected by `clauses` (`WHERE ...` and what follows)
+fn load_stored_listings(conn: &Connection, clauses: &str, values: Vec<Value>) -> Result<Vec<StoredListing>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, site, listing_id, status, first_seen, last_seen, ended_at FROM listings{}",
+        LISTING_COLUMNS, clauses
+    ))?;
+    let rows = stmt
+        .query_map(params_from_iter(values), |row| {
+            Ok((
+                row_to_listing(row)?,
+                row.get::<_, String>(16)?,
+                row.get::<_, String>(17)?,
+                row.get::<_, String>(18)?,
+                row.get::<_, String>(19)?,
+                row.get::<_, String>(20)?,
+                row.get::<_, Option<String>>(21)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    
+    rows.into_iter()
+        .map(|(listing, site, listing_id, status, first_seen, last_seen, ended_at)| {
+            let status = ListingStatus::from_key(&status)
+                .ok_or_else(|| anyhow::anyhow!("Unknown listing status `{}`", status))?;
+            
+            Ok(StoredListing {
+                site,
+                listing_id,
+                listing: WatchListing {
+                    sold: status == ListingStatus::Sold,
+                    ..listing
+                },
+                status,
+                first_seen: sqlite_time(&first_seen)?,
+                last_seen: sqlite_time(&last_seen)?,
+                ended_at: ended_at.as_deref().map(sqlite_time).transpose()?,
+            })
+        })
+        .collect()
+}
+
+/// Parse a `CURRENT_TIMESTAMP` value, which SQLite writes in UTC
+fn sqlite_time(value: &str) -> Result<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .map(|time| time.and_utc())
+        .with_context(|| format!("Invalid timestamp `{}`", value))
+}
+
+/// Escape `%`, `_` and the escape character itself for `LIKE ... ESCAPE '\'`
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn storage() -> SqliteStorage {
+        let storage = SqliteStorage::overlay(Path::new("/nonexistent/watch-monitor-sqlite-test.db"))
+            .await
+            .unwrap();
+        storage.migrate().await.unwrap();
+        storage
+    }
+
+    fn observation(amount_minor: i64, currency: Currency, eur_minor: i64) -> PriceObservation {
+        PriceObservation {
+            price: Money::new(amount_minor, currency),
+            price_eur: Some(Money::new(eur_minor, Currency::Eur)),
+        }
+    }
+
+    #[tokio::test]
+    async fn records_a_price_row_only_when_the_price_changes() {
+        let storage = storage().await;
+        let (site, id) = (Site::TropicalWatch, WatchId("submariner".to_string()));
+        let first = observation(1250000, Currency::Usd, 1150000);
+
+        assert_eq!(storage.record_price(&site, &id, &first).await.unwrap(), None);
+        assert_eq!(storage.record_price(&site, &id, &first).await.unwrap(), None);
+        // Exchange rate moves alone are no change
+        let rate_moved = observation(1250000, Currency::Usd, 1170000);
+        assert_eq!(storage.record_price(&site, &id, &rate_moved).await.unwrap(), None);
+        assert_eq!(storage.price_history(&site, &id).await.unwrap().len(), 1);
+
+        let drop = observation(1190000, Currency::Usd, 1095000);
+        assert_eq!(storage.record_price(&site, &id, &drop).await.unwrap(), Some(first));
+        assert_eq!(storage.record_price(&site, &id, &drop).await.unwrap(), None);
+
+        let history = storage.price_history(&site, &id).await.unwrap();
+        let prices: Vec<Money> = history.iter().map(|record| record.price).collect();
+        assert_eq!(prices, vec![Money::new(1250000, Currency::Usd), Money::new(1190000, Currency::Usd)]);
+        // Other listings keep their own history
+        let other = WatchId("daytona".to_string());
+        assert_eq!(storage.record_price(&site, &other, &drop).await.unwrap(), None);
+
+        // Several listings at once, as the dashboard reads them
+        let unpriced = WatchId("explorer".to_string());
+        let keys = [(site.clone(), id.clone()), (site.clone(), other.clone()), (site.clone(), unpriced.clone())];
+        let histories = storage.price_histories(&keys).await.unwrap();
+        assert_eq!(histories[&(site.clone(), id)].len(), 2);
+        assert_eq!(histories[&(site.clone(), other)].len(), 1);
+        assert!(!histories.contains_key(&(site, unpriced)));
+    }
+
+    async fn status(storage: &SqliteStorage, site: &Site, id: &WatchId) -> ListingStatus {
+        storage.load_listing(site, id).await.unwrap().unwrap().status
+    }
+
+    #[tokio::test]
+    async fn removes_listings_missing_for_consecutive_scrapes() {
+        let storage = storage().await;
+        let site = Site::TropicalWatch;
+        let (kept, flaky) = (WatchId("submariner".to_string()), WatchId("daytona".to_string()));
+        let listing = WatchListing::default();
+        storage.touch_listing(&site, &kept, &listing).await.unwrap();
+        storage.touch_listing(&site, &flaky, &listing).await.unwrap();
+
+        for _ in 0..2 {
+            assert!(storage.record_missing(&site, std::slice::from_ref(&kept), 3).await.unwrap().is_empty());
+        }
+        // Showing up again resets the count
+        storage.touch_listing(&site, &flaky, &listing).await.unwrap();
+        for _ in 0..2 {
+            assert!(storage.record_missing(&site, std::slice::from_ref(&kept), 3).await.unwrap().is_empty());
+        }
+        assert_eq!(status(&storage, &site, &flaky).await, ListingStatus::Active);
+
+        let ended = storage.record_missing(&site, std::slice::from_ref(&kept), 3).await.unwrap();
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].status, ListingStatus::Removed);
+        assert_eq!(status(&storage, &site, &flaky).await, ListingStatus::Removed);
+        assert_eq!(status(&storage, &site, &kept).await, ListingStatus::Active);
+        // Ended listings are not counted again
+        assert!(storage.record_missing(&site, std::slice::from_ref(&kept), 3).await.unwrap().is_empty());
+
+        // A removed listing that reappears is active again
+        storage.touch_listing(&site, &flaky, &listing).await.unwrap();
+        assert_eq!(status(&storage, &site, &flaky).await, ListingStatus::Active);
+    }
+
+    #[tokio::test]
+    async fn marks_active_listings_sold_once() {
+        let storage = storage().await;
+        let (site, id) = (Site::TropicalWatch, WatchId("submariner".to_string()));
+        storage.touch_listing(&site, &id, &WatchListing::default()).await.unwrap();
+
+        let ended = storage.mark_sold(&site, &id).await.unwrap().unwrap();
+        assert_eq!(ended.status, ListingStatus::Sold);
+        assert!(storage.mark_sold(&site, &id).await.unwrap().is_none());
+        assert!(storage.record_missing(&site, &[], 1).await.unwrap().is_empty());
+        assert_eq!(status(&storage, &site, &id).await, ListingStatus::Sold);
+    }
+}
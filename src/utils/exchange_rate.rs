@@ -1,91 +1,297 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::{DateTime, Utc, Duration};
-use reqwest::Client;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 
-#[derive(Debug, Clone)]
-pub struct ExchangeRateCache {
-    rate: Option<f64>,
-    last_updated: Option<DateTime<Utc>>,
+use crate::models::{Currency, Money, WatchListing};
+use crate::metrics;
+use crate::storage::Storage;
+use crate::utils::http::HttpClient;
+
+/// Placeholder in `exchange_rate_api_url` replaced by the base currency code
+const BASE_PLACEHOLDER: &str = "{base}";
+
+/// Rates older than this are refreshed
+const MAX_RATE_AGE_HOURS: i64 = 24;
+
+/// Exchange rates relative to a base currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateTable {
+    pub base: Currency,
+    /// Units of each currency per one unit of `base`
+    pub rates: HashMap<Currency, f64>,
+    pub fetched_at: DateTime<Utc>,
 }
 
-impl Default for ExchangeRateCache {
-    fn default() -> Self {
-        Self {
-            rate: None,
-            last_updated: None,
+impl RateTable {
+    /// Rate from `from` to `to`, derived via the base currency
+    pub fn rate(&self, from: Currency, to: Currency) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
         }
+
+        let per_base = |currency: Currency| {
+            if currency == self.base {
+                Some(1.0)
+            } else {
+                self.rates.get(&currency).copied().filter(|rate| *rate > 0.0)
+            }
+        };
+
+        Some(per_base(to)? / per_base(from)?)
     }
-}
 
+    pub fn is_fresh(&self) -> bool {
+        Utc::now() - self.fetched_at < Duration::hours(MAX_RATE_AGE_HOURS)
+    }
+}
 
 pub struct ExchangeRateClient {
-    cache: Arc<Mutex<ExchangeRateCache>>,
+    api_url: String,
+    base: Currency,
+    storage: Arc<dyn Storage>,
+    cache: Mutex<Option<RateTable>>,
 }
 
 impl ExchangeRateClient {
-    pub fn new() -> Self {
+    pub fn new(api_url: &str, base: Currency, storage: Arc<dyn Storage>) -> Self {
         Self {
-            cache: Arc::new(Mutex::new(ExchangeRateCache::default())),
+            api_url: api_url.replace(BASE_PLACEHOLDER, base.code()),
+            base,
+            storage,
+            cache: Mutex::new(None),
         }
     }
-    
-    pub async fn get_usd_to_eur_rate(&self, client: &Client) -> Result<f64> {
+
+    /// Current rate table: cached if fresh, otherwise fetched from the API.
+    /// If fetching fails the last good table (kept in storage across
+    /// restarts) is used however old it is.
+    pub async fn rates(&self, client: &HttpClient) -> Result<RateTable> {
         let mut cache = self.cache.lock().await;
-        
-        // Check if cache is valid (less than 24 hours old)
-        if let (Some(rate), Some(last_updated)) = (cache.rate, cache.last_updated) {
-            if Utc::now() - last_updated < Duration::hours(24) {
-                info!("Using cached USD to EUR rate: {}", rate);
-                return Ok(rate);
-            }
+
+        if cache.is_none() {
+            *cache = self.storage.load_exchange_rates().await?;
         }
-        
-        // Fetch new rate
-        info!("Fetching fresh USD to EUR exchange rate");
-        
-        // Using exchangerate-api.com free tier
-        let url = "https://api.exchangerate-api.com/v4/latest/USD";
-        
-        match client.get(url)
-            .timeout(std::time::Duration::from_secs(10))
-            .send()
-            .await 
-        {
-            Ok(response) => {
-                if response.status().is_success() {
-                    let data: serde_json::Value = response.json().await?;
-                    
-                    if let Some(rates) = data.get("rates") {
-                        if let Some(eur_rate) = rates.get("EUR") {
-                            if let Some(rate_value) = eur_rate.as_f64() {
-                                info!("Successfully fetched USD to EUR rate: {}", rate_value);
-                                cache.rate = Some(rate_value);
-                                cache.last_updated = Some(Utc::now());
-                                return Ok(rate_value);
-                            }
-                        }
-                    }
+
+        if let Some(table) = cache.as_ref().filter(|table| table.is_fresh()) {
+            metrics::record_exchange_rates(table.fetched_at);
+            return Ok(table.clone());
+        }
+
+        info!("Fetching fresh exchange rates for {}", self.base);
+
+        match self.fetch(client).await {
+            Ok(table) => {
+                info!("Successfully fetched {} exchange rates", table.rates.len());
+                if let Err(e) = self.storage.save_exchange_rates(&table).await {
+                    error!("Failed to persist exchange rates: {}", e);
                 }
+                metrics::record_exchange_rates(table.fetched_at);
+                *cache = Some(table.clone());
+                Ok(table)
             }
             Err(e) => {
-                error!("Failed to fetch exchange rate: {}", e);
+                error!("Failed to fetch exchange rates: {:#}", e);
+                match cache.as_ref() {
+                    Some(table) => {
+                        warn!("Using stale exchange rates from {}", table.fetched_at.format("%Y-%m-%d %H:%M"));
+                        metrics::record_exchange_rates(table.fetched_at);
+                        Ok(table.clone())
+                    }
+                    None => Err(e.context("No exchange rates available")),
+                }
             }
         }
-        
-        // Fallback to cached rate if available, or use a default
-        if let Some(rate) = cache.rate {
-            info!("Using stale cached rate due to fetch failure: {}", rate);
-            Ok(rate)
+    }
+
+    async fn fetch(&self, client: &HttpClient) -> Result<RateTable> {
+        let request = client.get(&self.api_url).timeout(std::time::Duration::from_secs(10));
+        let response = client.send(request).await?.error_for_status()?;
+
+        let data: serde_json::Value = response.json().await?;
+
+        // The API reports its own base, which wins over the configured one
+        let base = data
+            .get("base")
+            .or_else(|| data.get("base_code"))
+            .and_then(|base| base.as_str())
+            .and_then(Currency::from_code)
+            .unwrap_or(self.base);
+
+        let rates = data
+            .get("rates")
+            .or_else(|| data.get("conversion_rates"))
+            .and_then(|rates| rates.as_object())
+            .context("Response has no rates")?;
+
+        let rates: HashMap<Currency, f64> = Currency::all()
+            .iter()
+            .filter_map(|currency| {
+                let rate = rates.get(currency.code())?.as_f64()?;
+                Some((*currency, rate))
+            })
+            .collect();
+
+        if rates.is_empty() {
+            anyhow::bail!("Response has no rates for supported currencies");
+        }
+
+        Ok(RateTable {
+            base,
+            rates,
+            fetched_at: Utc::now(),
+        })
+    }
+
+    /// Fill in the EUR price of every listing from its native price.
+    /// Listings keep no EUR price when no rate is available.
+    pub async fn convert_listing_prices(&self, client: &HttpClient, listings: &mut [WatchListing]) {
+        let needs_rates = listings
+            .iter()
+            .any(|listing| listing.price.is_some_and(|price| price.currency != Currency::Eur));
+
+        let table = if needs_rates {
+            match self.rates(client).await {
+                Ok(table) => Some(table),
+                Err(e) => {
+                    error!("Cannot convert prices to EUR: {:#}", e);
+                    None
+                }
+            }
         } else {
-            // Default fallback rate
-            let fallback_rate = 0.92;
-            info!("Using fallback USD to EUR rate: {}", fallback_rate);
-            cache.rate = Some(fallback_rate);
-            cache.last_updated = Some(Utc::now());
-            Ok(fallback_rate)
+            None
+        };
+
+        for listing in listings {
+            listing.price_eur = listing.price.and_then(|price| convert(table.as_ref(), price, Currency::Eur));
         }
     }
-}
\ No newline at end of file
+}
+
+fn convert(table: Option<&RateTable>, price: Money, to: Currency) -> Option<Money> {
+    if price.currency == to {
+        return Some(price);
+    }
+
+    let rate = table?.rate(price.currency, to)?;
+    Some(price.convert(rate, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::SqliteStorage;
+    use crate::utils::http::{create_client, HttpMode};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn table(age: Duration) -> RateTable {
+        RateTable {
+            base: Currency::Eur,
+            rates: HashMap::from([(Currency::Usd, 1.10), (Currency::Chf, 0.95), (Currency::Jpy, 160.0)]),
+            fetched_at: Utc::now() - age,
+        }
+    }
+
+    fn listing(price: Money) -> WatchListing {
+        WatchListing {
+            price: Some(price),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn derives_rates_via_the_base_currency() {
+        let table = table(Duration::zero());
+        let rate = |from, to| table.rate(from, to).unwrap();
+
+        assert_eq!(rate(Currency::Eur, Currency::Usd), 1.10);
+        assert!((rate(Currency::Usd, Currency::Eur) - 1.0 / 1.10).abs() < 1e-12);
+        assert!((rate(Currency::Usd, Currency::Chf) - 0.95 / 1.10).abs() < 1e-12);
+        assert_eq!(rate(Currency::Gbp, Currency::Gbp), 1.0);
+        assert_eq!(table.rate(Currency::Gbp, Currency::Eur), None);
+        assert_eq!(table.rate(Currency::Usd, Currency::Hkd), None);
+    }
+
+    #[test]
+    fn rounds_converted_prices_to_minor_units() {
+        let table = table(Duration::zero());
+        let cases = [
+            (Money::new(1250000, Currency::Usd), Currency::Eur, Money::new(1136364, Currency::Eur)),
+            (Money::new(1250050, Currency::Chf), Currency::Eur, Money::new(1315842, Currency::Eur)),
+            (Money::new(12345, Currency::Usd), Currency::Jpy, Money::new(17956, Currency::Jpy)),
+            (Money::new(1000000, Currency::Jpy), Currency::Eur, Money::new(625000, Currency::Eur)),
+            (Money::new(999, Currency::Eur), Currency::Eur, Money::new(999, Currency::Eur)),
+        ];
+
+        for (price, to, expected) in cases {
+            assert_eq!(convert(Some(&table), price, to), Some(expected), "{} to {}", price, to);
+        }
+        assert_eq!(convert(None, Money::new(999, Currency::Eur), Currency::Eur), Some(Money::new(999, Currency::Eur)));
+        assert_eq!(convert(None, Money::new(999, Currency::Usd), Currency::Eur), None);
+    }
+
+    async fn client_with_saved_rates(server: &MockServer, saved: Option<RateTable>) -> ExchangeRateClient {
+        let storage = SqliteStorage::overlay(std::path::Path::new("/nonexistent/watch-monitor-rates-test.db"))
+            .await
+            .unwrap();
+        storage.migrate().await.unwrap();
+        if let Some(saved) = saved {
+            storage.save_exchange_rates(&saved).await.unwrap();
+        }
+
+        let api_url = format!("{}/latest/{}", server.uri(), BASE_PLACEHOLDER);
+        ExchangeRateClient::new(&api_url, Currency::Eur, Arc::new(storage))
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_stale_rates_when_the_api_fails() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/latest/EUR"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+        let http = create_client("watch-monitor-tests", HttpMode::Live).unwrap();
+
+        let stale = client_with_saved_rates(&server, Some(table(Duration::days(3)))).await;
+        let mut listings = vec![listing(Money::new(1250000, Currency::Usd)), listing(Money::new(999, Currency::Eur))];
+        stale.convert_listing_prices(&http, &mut listings).await;
+        assert_eq!(listings[0].price_eur, Some(Money::new(1136364, Currency::Eur)));
+        assert_eq!(listings[1].price_eur, Some(Money::new(999, Currency::Eur)));
+
+        // Without any saved rates only EUR prices are known
+        let empty = client_with_saved_rates(&server, None).await;
+        let mut listings = vec![listing(Money::new(1250000, Currency::Usd)), listing(Money::new(999, Currency::Eur))];
+        empty.convert_listing_prices(&http, &mut listings).await;
+        assert_eq!(listings[0].price_eur, None);
+        assert_eq!(listings[1].price_eur, Some(Money::new(999, Currency::Eur)));
+    }
+
+    #[tokio::test]
+    async fn refreshes_stale_rates_and_keeps_fresh_ones() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/latest/EUR"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "base": "EUR", "rates": { "USD": 1.25, "XYZ": 2.0 } })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let http = create_client("watch-monitor-tests", HttpMode::Live).unwrap();
+
+        let fresh = client_with_saved_rates(&server, Some(table(Duration::hours(1)))).await;
+        assert_eq!(fresh.rates(&http).await.unwrap().rates[&Currency::Usd], 1.10);
+
+        let stale = client_with_saved_rates(&server, Some(table(Duration::days(3)))).await;
+        let refreshed = stale.rates(&http).await.unwrap();
+        assert_eq!(refreshed.rates, HashMap::from([(Currency::Usd, 1.25)]));
+        assert!(refreshed.is_fresh());
+        // Cached from now on
+        assert_eq!(stale.rates(&http).await.unwrap(), refreshed);
+    }
+}
@@ -1,26 +1,155 @@
 use anyhow::{Context, Result};
-use reqwest::{Client, ClientBuilder, Response};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, ClientBuilder, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tokio::time::sleep;
-use tracing::{error, warn};
+use tracing::{debug, error, warn};
 
-pub fn create_client() -> Result<Client> {
+use crate::metrics;
+
+/// Where responses come from: the network, the network with every exchange
+/// saved to a directory, or only such a directory
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum HttpMode {
+    #[default]
+    Live,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// HTTP client that can record its responses and replay them offline
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+    mode: HttpMode,
+}
+
+/// One saved request/response pair, stored as
+/// `<dir>/<host-and-path>-<hash>.json`
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedExchange {
+    method: String,
+    url: String,
+    status: u16,
+    content_type: Option<String>,
+    body: String,
+}
+
+pub fn create_client(user_agent: &str, mode: HttpMode) -> Result<HttpClient> {
     let client = ClientBuilder::new()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/108.0.0.0 Safari/537.36")
+        .user_agent(user_agent)
         .timeout(Duration::from_secs(25))
         .pool_max_idle_per_host(6)
         .build()?;
-    
-    Ok(client)
+
+    match &mode {
+        HttpMode::Live => {}
+        HttpMode::Record(dir) => std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create recording directory {}", dir.display()))?,
+        HttpMode::Replay(dir) => {
+            if !dir.is_dir() {
+                anyhow::bail!("Replay directory {} does not exist", dir.display());
+            }
+        }
+    }
+
+    Ok(HttpClient { client, mode })
+}
+
+impl HttpClient {
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    /// Send `request` according to the client's mode. Replaying never
+    /// touches the network and fails for requests that were not recorded.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let request = request.build()?;
+        let method = request.method().to_string();
+        let url = request.url().to_string();
+
+        match &self.mode {
+            HttpMode::Live => Ok(self.client.execute(request).await?),
+            HttpMode::Record(dir) => {
+                let response = self.client.execute(request).await?;
+                let exchange = RecordedExchange {
+                    method,
+                    url,
+                    status: response.status().as_u16(),
+                    content_type: response
+                        .headers()
+                        .get(CONTENT_TYPE)
+                        .and_then(|value| value.to_str().ok())
+                        .map(|value| value.to_string()),
+                    body: response.text().await?,
+                };
+
+                let path = recording_path(dir, &exchange.method, &exchange.url);
+                tokio::fs::write(&path, serde_json::to_vec_pretty(&exchange)?)
+                    .await
+                    .with_context(|| format!("Failed to record {}", path.display()))?;
+                debug!("Recorded {} {} to {}", exchange.method, exchange.url, path.display());
+
+                exchange.into_response()
+            }
+            HttpMode::Replay(dir) => {
+                let path = recording_path(dir, &method, &url);
+                let data = tokio::fs::read(&path)
+                    .await
+                    .with_context(|| format!("No recorded response for {} {} in {}", method, url, dir.display()))?;
+                let exchange: RecordedExchange = serde_json::from_slice(&data)
+                    .with_context(|| format!("Invalid recording {}", path.display()))?;
+
+                exchange.into_response()
+            }
+        }
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.mode, HttpMode::Replay(_))
+    }
 }
 
-pub async fn fetch_with_retry(client: &Client, url: &str, max_retries: u32) -> Result<Response> {
+impl RecordedExchange {
+    /// The body was decoded when it was recorded, so it is served as UTF-8
+    /// whatever charset the site declared
+    fn into_response(self) -> Result<Response> {
+        let mut response = http::Response::builder().status(self.status);
+        if let Some(content_type) = &self.content_type {
+            let mime = content_type.split(';').next().unwrap_or_default().trim();
+            response = response.header(CONTENT_TYPE, format!("{}; charset=utf-8", mime));
+        }
+
+        Ok(Response::from(response.body(self.body)?))
+    }
+}
+
+/// Recording file for a request: readable, yet unique per method and URL
+fn recording_path(dir: &Path, method: &str, url: &str) -> PathBuf {
+    let readable: String = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let readable: String = readable.trim_matches('-').chars().take(80).collect();
+    let hash = format!("{:x}", md5::compute(format!("{} {}", method, url)));
+
+    dir.join(format!("{}-{}.json", readable, &hash[..12]))
+}
+
+pub async fn fetch_with_retry(client: &HttpClient, url: &str, max_retries: u32) -> Result<Response> {
+    // Replayed responses never change, so retrying them only adds delay
+    let max_retries = if client.is_replaying() { 1 } else { max_retries };
     let mut attempts = 0;
     let mut last_error = None;
-    
+
     while attempts < max_retries {
-        match client.get(url).send().await {
+        match client.send(client.get(url)).await {
             Ok(response) => {
+                metrics::record_http_request(url, Some(response.status().as_u16()));
                 if response.status().is_success() {
                     return Ok(response);
                 } else {
@@ -30,20 +159,85 @@ pub async fn fetch_with_retry(client: &Client, url: &str, max_retries: u32) -> R
                 }
             }
             Err(e) => {
-                error!("Request failed for {}: {}", url, e);
-                last_error = Some(e.into());
+                metrics::record_http_request(url, None);
+                error!("Request failed for {}: {:#}", url, e);
+                last_error = Some(e);
             }
         }
-        
+
         attempts += 1;
         if attempts < max_retries {
             let delay = Duration::from_secs(2u64.pow(attempts));
             warn!("Retrying in {:?}... (attempt {}/{})", delay, attempts + 1, max_retries);
+            metrics::record_http_retry(url);
             sleep(delay).await;
         }
     }
-    
+
     Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Max retries exceeded")))
         .context(format!("Failed to fetch {} after {} attempts", url, max_retries))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("watch-monitor-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_responses_offline() {
+        let dir = scratch_dir("record-replay");
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/uhren"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("<p>Größe 40 mm</p>", "text/html; charset=utf-8"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let recorder = create_client("watch-monitor-tests", HttpMode::Record(dir.clone())).unwrap();
+        let listing_url = format!("{}/uhren", server.uri());
+        let missing_url = format!("{}/missing", server.uri());
+        let recorded = fetch_with_retry(&recorder, &listing_url, 1).await.unwrap();
+        assert_eq!(recorded.text().await.unwrap(), "<p>Größe 40 mm</p>");
+        assert!(fetch_with_retry(&recorder, &missing_url, 1).await.is_err());
+
+        // Replaying must not reach the server, which verifies its expectations here
+        drop(server);
+
+        let replayer = create_client("watch-monitor-tests", HttpMode::Replay(dir.clone())).unwrap();
+        let replayed = fetch_with_retry(&replayer, &listing_url, 3).await.unwrap();
+        assert_eq!(
+            replayed.headers().get(CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(replayed.text().await.unwrap(), "<p>Größe 40 mm</p>");
+
+        let missing = replayer.send(replayer.get(&missing_url)).await.unwrap();
+        assert_eq!(missing.status().as_u16(), 404);
+
+        let unrecorded = format!("{}/other", listing_url);
+        let error = replayer.send(replayer.get(&unrecorded)).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("No recorded response"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn replay_requires_an_existing_directory() {
+        let dir = scratch_dir("replay-missing");
+        assert!(create_client("watch-monitor-tests", HttpMode::Replay(dir)).is_err());
+    }
+}
@@ -1,2 +1,3 @@
 pub mod http;
-pub mod exchange_rate;
\ No newline at end of file
+pub mod exchange_rate;
+pub mod secret;
\ No newline at end of file
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+
+const ENV_PREFIX: &str = "env:";
+const FILE_PREFIX: &str = "file:";
+const REDACTED: &str = "<redacted>";
+
+/// A credential such as a Discord webhook URL.
+///
+/// The configured value is either the secret itself or a reference to it:
+/// `env:NAME` reads the environment variable `NAME`, `file:/path` reads the
+/// (trimmed) contents of a file. References are resolved while the
+/// configuration is deserialized, so a missing secret fails loading.
+/// `Debug` and `Serialize` never reveal the resolved value.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Secret {
+    reference: String,
+    value: String,
+}
+
+impl Secret {
+    pub fn resolve(reference: &str) -> Result<Self, String> {
+        let reference = reference.trim();
+
+        let value = if let Some(name) = reference.strip_prefix(ENV_PREFIX) {
+            std::env::var(name)
+                .map_err(|_| format!("environment variable `{}` is not set", name))?
+        } else if let Some(path) = reference.strip_prefix(FILE_PREFIX) {
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("cannot read secret file `{}`: {}", path, e))?
+                .trim()
+                .to_string()
+        } else {
+            reference.to_string()
+        };
+
+        if value.is_empty() {
+            return Err(format!("secret `{}` resolved to an empty value", Self::describe(reference)));
+        }
+
+        Ok(Self {
+            reference: reference.to_string(),
+            value,
+        })
+    }
+
+    /// The resolved secret; only pass this to the code that actually needs it
+    pub fn expose(&self) -> &str {
+        &self.value
+    }
+
+    fn is_reference(reference: &str) -> bool {
+        reference.starts_with(ENV_PREFIX) || reference.starts_with(FILE_PREFIX)
+    }
+
+    /// The reference itself is safe to show; inline secrets are not
+    fn describe(reference: &str) -> &str {
+        if Self::is_reference(reference) {
+            reference
+        } else {
+            REDACTED
+        }
+    }
+}
+
+impl TryFrom<String> for Secret {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::resolve(&value)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(Self::describe(&self.reference))
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", Self::describe(&self.reference))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALUE: &str = "https://discord.com/api/webhooks/1/s3cr3t";
+
+    fn assert_redacted(secret: &Secret) {
+        assert!(!format!("{:?}", secret).contains("s3cr3t"));
+        assert!(!serde_json::to_string(secret).unwrap().contains("s3cr3t"));
+    }
+
+    #[test]
+    fn keeps_inline_secrets_as_they_are() {
+        let secret = Secret::resolve(&format!("  {}\n", VALUE)).unwrap();
+
+        assert_eq!(secret.expose(), VALUE);
+        assert_eq!(format!("{:?}", secret), "Secret(<redacted>)");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"<redacted>\"");
+    }
+
+    #[test]
+    fn reads_environment_variables() {
+        std::env::set_var("WATCH_MONITOR_SECRET_TEST", VALUE);
+        let secret = Secret::resolve("env:WATCH_MONITOR_SECRET_TEST").unwrap();
+
+        assert_eq!(secret.expose(), VALUE);
+        assert_eq!(format!("{:?}", secret), "Secret(env:WATCH_MONITOR_SECRET_TEST)");
+        assert_redacted(&secret);
+    }
+
+    #[test]
+    fn reads_trimmed_files() {
+        let path = std::env::temp_dir().join(format!("watch-monitor-secret-{}", std::process::id()));
+        std::fs::write(&path, format!("\n{}  \n", VALUE)).unwrap();
+
+        let secret = Secret::resolve(&format!("file:{}", path.display()));
+        std::fs::remove_file(&path).unwrap();
+
+        let secret = secret.unwrap();
+        assert_eq!(secret.expose(), VALUE);
+        assert_redacted(&secret);
+    }
+
+    #[test]
+    fn fails_on_missing_or_empty_secrets() {
+        assert_eq!(
+            Secret::resolve("env:WATCH_MONITOR_SECRET_MISSING"),
+            Err("environment variable `WATCH_MONITOR_SECRET_MISSING` is not set".to_string())
+        );
+        assert!(Secret::resolve("file:/nonexistent/watch-monitor-secret")
+            .unwrap_err()
+            .starts_with("cannot read secret file `/nonexistent/watch-monitor-secret`"));
+
+        std::env::set_var("WATCH_MONITOR_SECRET_EMPTY", "");
+        assert!(Secret::resolve("env:WATCH_MONITOR_SECRET_EMPTY").is_err());
+        assert!(Secret::resolve("").is_err());
+    }
+
+    #[test]
+    fn fails_deserializing_unresolvable_references() {
+        let error = serde_json::from_str::<Secret>("\"env:WATCH_MONITOR_SECRET_MISSING\"").unwrap_err();
+
+        assert!(error.to_string().contains("WATCH_MONITOR_SECRET_MISSING"));
+    }
+}